@@ -3,23 +3,32 @@ use crate::plist_parser::PlistParser;
 use anyhow::{bail, Result};
 use serde::Serialize;
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize)]
 pub struct Application {
     pub name: String,
     pub path: PathBuf,
     pub bundle_id: Option<String>,
+    /// `CFBundleVersion`, used to attribute drift to application updates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
     pub extensions: Vec<String>,
+    pub utis: Vec<String>,
+    pub mime_types: Vec<String>,
+    pub url_schemes: Vec<String>,
 }
 
 #[derive(Debug)]
 pub struct ApplicationCatalog {
     pub applications: Vec<Application>,
     pub metadata_failures: usize,
+    pub scan_duration: Duration,
 }
 
 impl ApplicationCatalog {
     pub fn scan() -> Result<Self> {
+        let started = Instant::now();
         let installed_apps = AppScanner::new().scan_applications()?;
         let parser = PlistParser::new();
         let mut metadata_failures = 0;
@@ -31,16 +40,16 @@ impl ApplicationCatalog {
                 if metadata.is_err() {
                     metadata_failures += 1;
                 }
-                let metadata = metadata.ok();
+                let metadata = metadata.ok().unwrap_or_default();
                 Application {
                     name: installed.name,
                     path: installed.path,
-                    bundle_id: metadata
-                        .as_ref()
-                        .and_then(|metadata| metadata.bundle_id.clone()),
-                    extensions: metadata
-                        .map(|metadata| metadata.extensions)
-                        .unwrap_or_default(),
+                    bundle_id: metadata.bundle_id,
+                    version: metadata.version,
+                    extensions: metadata.extensions,
+                    utis: metadata.utis,
+                    mime_types: metadata.mime_types,
+                    url_schemes: metadata.url_schemes,
                 }
             })
             .collect();
@@ -48,6 +57,7 @@ impl ApplicationCatalog {
         Ok(Self {
             applications,
             metadata_failures,
+            scan_duration: started.elapsed(),
         })
     }
 }
@@ -129,7 +139,11 @@ mod tests {
             name: name.to_owned(),
             path: PathBuf::from(path),
             bundle_id: bundle_id.map(str::to_owned),
+            version: None,
             extensions: vec!["txt".to_owned()],
+            utis: Vec::new(),
+            mime_types: Vec::new(),
+            url_schemes: Vec::new(),
         }
     }
 
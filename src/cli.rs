@@ -1,5 +1,8 @@
 use clap::{Args, Parser, Subcommand};
 use dutis::association::{AssociationKind, HandlerRole};
+use dutis::governance::{AuditExportFormat, AuditOutcome, MutationChannel, MutationOperation};
+use dutis::launch_agent::ProcessType;
+use std::net::SocketAddr;
 use std::path::PathBuf;
 
 #[derive(Debug, Parser)]
@@ -33,21 +36,27 @@ pub enum CliCommand {
     /// Create a local snapshot of current associations
     Snapshot(SnapshotArgs),
     /// List locally stored snapshots
-    History(OutputArgs),
+    History(HistoryArgs),
     /// Restore associations from a local snapshot
     Rollback(RollbackArgs),
+    /// Revert the entries changed by the latest or a named audited mutation
+    Undo(UndoArgs),
     /// Inspect the effective local mutation policy
     Policy(PolicyArgs),
-    /// List persistent local mutation audit records
-    Audit(OutputArgs),
-    /// Inspect built-in association profiles
+    /// List or verify persistent local mutation audit records
+    Audit(AuditArgs),
+    /// Prune or archive snapshots and audit records past their retention
+    Gc(GcArgs),
+    /// Inspect built-in and user-defined association profiles
     Profile(ProfileArgs),
-    /// Generate an explainable proposal from a built-in profile
+    /// Generate an explainable proposal from a profile
     Recommend(RecommendArgs),
     /// Read or set typed Launch Services handlers
     Handler(HandlerArgs),
     /// Detect and optionally remediate drift from a declarative configuration
     Watch(WatchArgs),
+    /// Inspect recorded drift transitions
+    Drift(DriftArgs),
     /// Manage the optional per-user drift monitoring LaunchAgent
     LaunchAgent(LaunchAgentArgs),
     /// Run the local Model Context Protocol server over stdio
@@ -114,6 +123,9 @@ pub struct ApplyArgs {
     /// Confirm a non-interactive system change
     #[arg(long)]
     pub yes: bool,
+    /// Revert every applied change if any association fails
+    #[arg(long)]
+    pub atomic: bool,
     /// Emit stable machine-readable JSON
     #[arg(long)]
     pub json: bool,
@@ -132,13 +144,79 @@ pub struct SnapshotArgs {
 pub enum SnapshotCommand {
     /// Capture current associations without changing the system
     Create(SnapshotCreateArgs),
+    /// Show handlers added, removed, or changed between snapshots or live state
+    Diff(SnapshotDiffArgs),
+    /// Write a portable, checksummed copy of a snapshot
+    Export(SnapshotExportArgs),
+    /// Verify and store a snapshot exported on another machine
+    Import(SnapshotImportArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct SnapshotExportArgs {
+    /// Snapshot identifier or name shown by the history command
+    pub snapshot_id: String,
+    /// Bundle file to create, for example setup.dutis-snapshot
+    #[arg(long)]
+    pub out: PathBuf,
+    /// Emit stable machine-readable JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct SnapshotImportArgs {
+    /// Bundle file created by snapshot export
+    pub file: PathBuf,
+    /// Store the snapshot under this name instead of the exported one
+    #[arg(long)]
+    pub name: Option<String>,
+    /// Emit stable machine-readable JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct SnapshotDiffArgs {
+    /// Earlier snapshot identifier or name shown by the history command
+    pub from: String,
+    /// Later snapshot identifier or name; omit to compare with live state
+    pub to: Option<String>,
+    /// Compare with current associations for the snapshot's targets
+    #[arg(long, conflicts_with = "to")]
+    pub live: bool,
+    /// Emit stable machine-readable JSON
+    #[arg(long)]
+    pub json: bool,
 }
 
 #[derive(Debug, Args)]
 pub struct SnapshotCreateArgs {
     /// Limit the snapshot to extensions in this configuration
-    #[arg(long)]
+    #[arg(long, conflicts_with = "all")]
     pub config: Option<PathBuf>,
+    /// Unique name usable in place of the snapshot ID
+    #[arg(long)]
+    pub name: Option<String>,
+    /// Free-form note stored with the snapshot
+    #[arg(long)]
+    pub note: Option<String>,
+    /// Label for filtering history; repeat or separate with commas
+    #[arg(long = "tag", value_delimiter = ',')]
+    pub tags: Vec<String>,
+    /// Capture every extension, UTI, MIME type, and URL scheme declared by installed apps
+    #[arg(long)]
+    pub all: bool,
+    /// Emit stable machine-readable JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct HistoryArgs {
+    /// Only list snapshots carrying this tag
+    #[arg(long)]
+    pub tag: Option<String>,
     /// Emit stable machine-readable JSON
     #[arg(long)]
     pub json: bool,
@@ -146,8 +224,20 @@ pub struct SnapshotCreateArgs {
 
 #[derive(Debug, Args)]
 pub struct RollbackArgs {
-    /// Snapshot identifier shown by the history command
+    /// Snapshot identifier or name shown by the history command
     pub snapshot_id: String,
+    /// Restore only these associations, for example `.md,.json` or `https`
+    #[arg(long, value_delimiter = ',', value_name = "SELECTORS")]
+    pub only: Vec<String>,
+    /// Restore only associations of these kinds
+    #[arg(long = "kind", value_enum, value_delimiter = ',')]
+    pub kinds: Vec<AssociationKind>,
+    /// Leave these associations untouched
+    #[arg(long, value_delimiter = ',', value_name = "SELECTORS")]
+    pub exclude: Vec<String>,
+    /// Choose which differing associations to restore from a numbered list
+    #[arg(long, conflicts_with = "json")]
+    pub pick: bool,
     /// Build and display the rollback plan without changing the system
     #[arg(long)]
     pub dry_run: bool,
@@ -162,6 +252,27 @@ pub struct RollbackArgs {
     pub requester: Option<String>,
 }
 
+#[derive(Debug, Args)]
+pub struct UndoArgs {
+    /// Audit record identifier; defaults to the most recent mutation that changed associations
+    pub audit_id: Option<String>,
+    /// Build and display the undo plan without changing the system
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Confirm a non-interactive undo
+    #[arg(long)]
+    pub yes: bool,
+    /// Undo even if the changed associations have been changed again since
+    #[arg(long)]
+    pub force: bool,
+    /// Emit stable machine-readable JSON
+    #[arg(long)]
+    pub json: bool,
+    /// Identity recorded in the local mutation audit
+    #[arg(long)]
+    pub requester: Option<String>,
+}
+
 #[derive(Debug, Args)]
 pub struct PolicyArgs {
     #[command(subcommand)]
@@ -176,6 +287,77 @@ pub enum PolicyCommand {
     Check(PolicyCheckArgs),
 }
 
+#[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct AuditArgs {
+    #[command(subcommand)]
+    pub command: Option<AuditCommand>,
+    /// Only records at or after this time (RFC 3339, YYYY-MM-DD, or an age such as 7d)
+    #[arg(long)]
+    pub since: Option<String>,
+    /// Only records at or before this time (RFC 3339, YYYY-MM-DD, or an age such as 7d)
+    #[arg(long)]
+    pub until: Option<String>,
+    /// Only records from this requester
+    #[arg(long)]
+    pub requester: Option<String>,
+    /// Only records from this mutation channel
+    #[arg(long, value_enum)]
+    pub channel: Option<MutationChannel>,
+    /// Only records for this mutation operation
+    #[arg(long, value_enum)]
+    pub operation: Option<MutationOperation>,
+    /// Only records with this outcome
+    #[arg(long, value_enum)]
+    pub outcome: Option<AuditOutcome>,
+    /// Only records whose plan changes this extension, UTI, MIME type, or URL scheme
+    #[arg(long)]
+    pub target: Option<String>,
+    /// Only match --target as this association kind
+    #[arg(long, value_enum, requires = "target")]
+    pub kind: Option<AssociationKind>,
+    /// Maximum number of records to return
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    pub limit: Option<u64>,
+    /// Number of matching records to skip, newest first
+    #[arg(long, default_value_t = 0)]
+    pub offset: u64,
+    /// Export records for a log pipeline instead of the default listing
+    #[arg(long, value_enum, conflicts_with = "json")]
+    pub format: Option<AuditExportFormat>,
+    /// Emit stable machine-readable JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AuditCommand {
+    /// Verify the audit hash chain and any record signatures
+    Verify(OutputArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct GcArgs {
+    /// Report what would be pruned without changing local state
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Confirm pruning local snapshots and audit records
+    #[arg(long)]
+    pub yes: bool,
+    /// Keep at least this many of the newest snapshots and audit records
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    pub keep_last: Option<u64>,
+    /// Keep snapshots and audit records newer than this many days
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub keep_days: Option<u32>,
+    /// Move pruned records into compressed archive bundles
+    #[arg(long)]
+    pub archive: bool,
+    /// Emit stable machine-readable JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
 #[derive(Debug, Args)]
 pub struct PolicyCheckArgs {
     /// Path to a versioned dutis TOML configuration
@@ -193,16 +375,29 @@ pub struct ProfileArgs {
 
 #[derive(Debug, Subcommand)]
 pub enum ProfileCommand {
-    /// List available built-in profiles
-    List(OutputArgs),
+    /// List built-in and user-defined profiles
+    List(ProfileListArgs),
     /// Show one profile and its ordered candidate applications
     Show(ProfileShowArgs),
 }
 
+#[derive(Debug, Args)]
+pub struct ProfileListArgs {
+    /// Also load a user-defined profile TOML file; repeatable
+    #[arg(long = "profile-file", value_name = "PATH")]
+    pub profile_files: Vec<PathBuf>,
+    /// Emit stable machine-readable JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
 #[derive(Debug, Args)]
 pub struct ProfileShowArgs {
-    /// Built-in profile name
+    /// Profile name
     pub name: String,
+    /// Also load a user-defined profile TOML file; repeatable
+    #[arg(long = "profile-file", value_name = "PATH")]
+    pub profile_files: Vec<PathBuf>,
     /// Emit stable machine-readable JSON
     #[arg(long)]
     pub json: bool,
@@ -210,8 +405,11 @@ pub struct ProfileShowArgs {
 
 #[derive(Debug, Args)]
 pub struct RecommendArgs {
-    /// Built-in profile name
+    /// Profile name
     pub profile: String,
+    /// Also load a user-defined profile TOML file; repeatable
+    #[arg(long = "profile-file", value_name = "PATH")]
+    pub profile_files: Vec<PathBuf>,
     /// Emit stable machine-readable JSON
     #[arg(long)]
     pub json: bool,
@@ -279,13 +477,25 @@ pub struct WatchArgs {
     /// Seconds between checks in continuous mode
     #[arg(long, default_value_t = 60, value_parser = clap::value_parser!(u64).range(1..))]
     pub interval_seconds: u64,
+    /// Recheck when Launch Services preferences, applications, or the configuration change; the interval becomes a fallback
+    #[arg(long, conflicts_with = "once")]
+    pub events: bool,
+    /// Seconds without further changes before an event-driven recheck
+    #[arg(long, default_value_t = 2, requires = "events", value_parser = clap::value_parser!(u64).range(0..=300))]
+    pub debounce_seconds: u64,
     /// Check once and exit instead of monitoring continuously
     #[arg(long)]
     pub once: bool,
+    /// Detections of one association within the flap window before it counts as flapping
+    #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u64).range(1..))]
+    pub flap_threshold: u64,
+    /// Minutes of drift history considered for flap detection
+    #[arg(long, default_value_t = 60, value_parser = clap::value_parser!(i64).range(1..=10080))]
+    pub flap_window_minutes: i64,
     /// Send a macOS notification when drift changes or recovers
     #[arg(long)]
     pub notify: bool,
-    /// Automatically restore drift through policy, snapshot, audit, and verification
+    /// Automatically restore drift through policy, snapshot, audit, and verification; without it, on_drift = "remediate" rules are only notified
     #[arg(long)]
     pub remediate: bool,
     /// Explicitly approve automatic remediation
@@ -294,9 +504,52 @@ pub struct WatchArgs {
     /// Identity recorded for automatic remediation audit entries
     #[arg(long)]
     pub requester: Option<String>,
+    /// Serve Prometheus metrics at http://ADDRESS/metrics, for example 127.0.0.1:9464
+    #[arg(long, value_name = "ADDRESS", conflicts_with = "once")]
+    pub metrics_listen: Option<SocketAddr>,
+    /// Rewrite Prometheus metrics to this file after every check, for a textfile collector
+    #[arg(long, value_name = "PATH")]
+    pub metrics_textfile: Option<PathBuf>,
     /// Emit one compact JSON object per check
     #[arg(long)]
     pub json: bool,
+    /// Append JSON output to this file instead of stdout
+    #[arg(long, value_name = "PATH", requires = "json")]
+    pub log_file: Option<PathBuf>,
+    /// Rotate the log file before it grows past this many bytes
+    #[arg(long, default_value_t = 10 * 1024 * 1024, requires = "log_file", value_parser = clap::value_parser!(u64).range(1..))]
+    pub log_max_bytes: u64,
+    /// Rotated log files to keep
+    #[arg(long, default_value_t = 5, requires = "log_file", value_parser = clap::value_parser!(u32).range(0..=100))]
+    pub log_keep: u32,
+}
+
+#[derive(Debug, Args)]
+pub struct DriftArgs {
+    #[command(subcommand)]
+    pub command: DriftCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DriftCommand {
+    /// Show the drift timeline and per-association statistics
+    History(DriftHistoryArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct DriftHistoryArgs {
+    /// Only events at or after this time (RFC 3339, YYYY-MM-DD, or an age such as 30d)
+    #[arg(long)]
+    pub since: Option<String>,
+    /// Only events recorded by watchers of this configuration
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+    /// Maximum number of timeline events to show, newest first
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    pub limit: Option<u64>,
+    /// Emit stable machine-readable JSON
+    #[arg(long)]
+    pub json: bool,
 }
 
 #[derive(Debug, Args)]
@@ -310,22 +563,63 @@ pub enum LaunchAgentCommand {
     /// Install or replace the per-user drift monitor
     Install(LaunchAgentInstallArgs),
     /// Remove the per-user drift monitor
-    Uninstall(OutputArgs),
+    Uninstall(LaunchAgentTargetArgs),
     /// Show whether the per-user drift monitor is installed and loaded
-    Status(OutputArgs),
+    Status(LaunchAgentTargetArgs),
+    /// List installed drift monitors with their configurations and schedules
+    List(OutputArgs),
+    /// Print the plist that install would write, without touching the filesystem or launchd
+    Render(LaunchAgentInstallArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct LaunchAgentTargetArgs {
+    /// Name of the agent; omit for the unnamed agent
+    #[arg(long)]
+    pub name: Option<String>,
+    /// Emit stable machine-readable JSON
+    #[arg(long)]
+    pub json: bool,
 }
 
 #[derive(Debug, Args)]
 pub struct LaunchAgentInstallArgs {
     /// Path to a versioned dutis TOML configuration
     pub config: PathBuf,
+    /// Install a separately labeled agent so several configurations can be monitored
+    #[arg(long)]
+    pub name: Option<String>,
     /// Seconds between scheduled checks (minimum 10)
     #[arg(long, default_value_t = 300, value_parser = clap::value_parser!(u64).range(10..))]
     pub interval_seconds: u64,
+    /// Recheck when associations may have changed; the interval becomes a fallback
+    #[arg(long)]
+    pub events: bool,
     /// Send macOS notifications when drift is detected
     #[arg(long)]
     pub notify: bool,
-    /// Automatically restore drift through the governed mutation pipeline
+    /// Serve Prometheus metrics from the monitor, for example 127.0.0.1:9464
+    #[arg(long, value_name = "ADDRESS")]
+    pub metrics_listen: Option<SocketAddr>,
+    /// Run one check at HH:MM or DAYS@HH:MM (daily, weekdays, weekends, or mon,wed,...) instead of monitoring continuously; repeatable
+    #[arg(long = "at", value_name = "SCHEDULE", conflicts_with_all = ["events", "metrics_listen"])]
+    pub schedule: Vec<String>,
+    /// Run one check whenever this path changes instead of monitoring continuously; repeatable
+    #[arg(long = "watch-path", value_name = "PATH", conflicts_with_all = ["events", "metrics_listen"])]
+    pub watch_paths: Vec<PathBuf>,
+    /// launchd scheduling class for the monitor
+    #[arg(long, value_enum, default_value_t = ProcessType::Background)]
+    pub process_type: ProcessType,
+    /// Ask launchd to throttle the monitor's disk I/O
+    #[arg(long)]
+    pub low_priority_io: bool,
+    /// Rotate the monitor's JSON log before it grows past this many bytes
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    pub log_max_bytes: Option<u64>,
+    /// Rotated log files to keep
+    #[arg(long, default_value_t = 5, requires = "log_max_bytes", value_parser = clap::value_parser!(u32).range(0..=100))]
+    pub log_keep: u32,
+    /// Automatically restore drift through the governed mutation pipeline; without it, on_drift = "remediate" rules are only notified
     #[arg(long)]
     pub remediate: bool,
     /// Explicitly approve scheduled automatic remediation
@@ -426,11 +720,126 @@ mod tests {
                 command: SnapshotCommand::Create(SnapshotCreateArgs { json: true, .. })
             }))
         ));
+        let cli = Cli::try_parse_from(["dutis", "snapshot", "create", "--all"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(CliCommand::Snapshot(SnapshotArgs {
+                command: SnapshotCommand::Create(SnapshotCreateArgs {
+                    all: true,
+                    config: None,
+                    ..
+                })
+            }))
+        ));
+        assert!(Cli::try_parse_from([
+            "dutis",
+            "snapshot",
+            "create",
+            "--all",
+            "--config",
+            "dutis.toml"
+        ])
+        .is_err());
+        let cli = Cli::try_parse_from([
+            "dutis",
+            "snapshot",
+            "create",
+            "--name",
+            "before-xcode-16",
+            "--note",
+            "baseline",
+            "--tag",
+            "release,audit",
+            "--tag",
+            "mdm",
+        ])
+        .unwrap();
+        let Some(CliCommand::Snapshot(SnapshotArgs {
+            command: SnapshotCommand::Create(args),
+        })) = cli.command
+        else {
+            panic!("expected snapshot create command");
+        };
+        assert_eq!(args.name.as_deref(), Some("before-xcode-16"));
+        assert_eq!(args.tags, vec!["release", "audit", "mdm"]);
+        assert!(Cli::try_parse_from([
+            "dutis",
+            "snapshot",
+            "export",
+            "before-xcode-16",
+            "--out",
+            "setup.dutis-snapshot"
+        ])
+        .is_ok());
+        assert!(Cli::try_parse_from(["dutis", "snapshot", "export", "before-xcode-16"]).is_err());
+        assert!(Cli::try_parse_from([
+            "dutis",
+            "snapshot",
+            "import",
+            "setup.dutis-snapshot",
+            "--name",
+            "new-hire",
+            "--json"
+        ])
+        .is_ok());
         assert!(Cli::try_parse_from(["dutis", "history", "--json"]).is_ok());
+        let cli = Cli::try_parse_from(["dutis", "history", "--tag", "release"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(CliCommand::History(HistoryArgs { tag: Some(_), .. }))
+        ));
+        let cli = Cli::try_parse_from(["dutis", "snapshot", "diff", "a", "--live"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(CliCommand::Snapshot(SnapshotArgs {
+                command: SnapshotCommand::Diff(SnapshotDiffArgs {
+                    to: None,
+                    live: true,
+                    ..
+                })
+            }))
+        ));
+        assert!(Cli::try_parse_from(["dutis", "snapshot", "diff", "a", "b", "--json"]).is_ok());
+        assert!(Cli::try_parse_from(["dutis", "snapshot", "diff", "a", "b", "--live"]).is_err());
         assert!(
             Cli::try_parse_from(["dutis", "rollback", "snapshot-id", "--dry-run", "--json"])
                 .is_ok()
         );
+        let cli = Cli::try_parse_from([
+            "dutis",
+            "rollback",
+            "snapshot-id",
+            "--only",
+            ".md,.json",
+            "--kind",
+            "url-scheme",
+            "--exclude",
+            "txt",
+            "--dry-run",
+        ])
+        .unwrap();
+        let Some(CliCommand::Rollback(args)) = cli.command else {
+            panic!("expected rollback command");
+        };
+        assert_eq!(args.only, vec![".md", ".json"]);
+        assert_eq!(args.kinds, vec![AssociationKind::UrlScheme]);
+        assert_eq!(args.exclude, vec!["txt"]);
+        assert!(
+            Cli::try_parse_from(["dutis", "rollback", "snapshot-id", "--pick", "--json"]).is_err()
+        );
+        let cli = Cli::try_parse_from(["dutis", "undo", "--dry-run"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(CliCommand::Undo(UndoArgs {
+                audit_id: None,
+                dry_run: true,
+                force: false,
+                ..
+            }))
+        ));
+        assert!(
+            Cli::try_parse_from(["dutis", "undo", "1-abc", "--yes", "--force", "--json"]).is_ok()
+        );
     }
 
     #[test]
@@ -454,6 +863,52 @@ mod tests {
         assert!(Cli::try_parse_from(["dutis", "policy", "show", "--json"]).is_ok());
         assert!(Cli::try_parse_from(["dutis", "policy", "check", "dutis.toml", "--json"]).is_ok());
         assert!(Cli::try_parse_from(["dutis", "audit", "--json"]).is_ok());
+        let cli = Cli::try_parse_from([
+            "dutis",
+            "audit",
+            "--since",
+            "7d",
+            "--channel",
+            "mcp",
+            "--outcome",
+            "partial-failure",
+            "--target",
+            ".md",
+            "--limit",
+            "20",
+            "--format",
+            "csv",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(CliCommand::Audit(AuditArgs {
+                channel: Some(MutationChannel::Mcp),
+                outcome: Some(AuditOutcome::PartialFailure),
+                limit: Some(20),
+                format: Some(AuditExportFormat::Csv),
+                ..
+            }))
+        ));
+        assert!(Cli::try_parse_from(["dutis", "audit", "--format", "jsonl", "--json"]).is_err());
+        let cli = Cli::try_parse_from(["dutis", "gc", "--dry-run", "--keep-last", "50"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(CliCommand::Gc(GcArgs {
+                dry_run: true,
+                keep_last: Some(50),
+                ..
+            }))
+        ));
+        assert!(Cli::try_parse_from(["dutis", "gc", "--keep-days", "0"]).is_err());
+        let cli = Cli::try_parse_from(["dutis", "audit", "verify", "--json"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(CliCommand::Audit(AuditArgs {
+                command: Some(AuditCommand::Verify(OutputArgs { json: true })),
+                ..
+            }))
+        ));
     }
 
     #[test]
@@ -461,6 +916,16 @@ mod tests {
         assert!(Cli::try_parse_from(["dutis", "profile", "list", "--json"]).is_ok());
         assert!(Cli::try_parse_from(["dutis", "profile", "show", "developer", "--json"]).is_ok());
         assert!(Cli::try_parse_from(["dutis", "recommend", "minimal", "--json"]).is_ok());
+        assert!(Cli::try_parse_from([
+            "dutis",
+            "recommend",
+            "data-science",
+            "--profile-file",
+            "data-science.toml",
+            "--profile-file",
+            "team.toml",
+        ])
+        .is_ok());
     }
 
     #[test]
@@ -24,6 +24,36 @@ pub struct AssociationRule {
     #[serde(default)]
     pub role: HandlerRole,
     pub application: String,
+    /// What `watch` does when this association drifts. Rules without it
+    /// follow the watcher's `--remediate` and `--notify` flags, and
+    /// `remediate` acts as `notify` unless the watcher runs with `--remediate`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_drift: Option<DriftAction>,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftAction {
+    /// Restore the declared handler when the watcher runs with `--remediate`;
+    /// without it the rule is notified and reported as `notify`.
+    Remediate,
+    /// Report and notify, but never remediate.
+    Notify,
+    /// Report and record in drift history only.
+    Record,
+    /// Leave the association out of drift checks entirely.
+    Ignore,
+}
+
+impl DriftAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Remediate => "remediate",
+            Self::Notify => "notify",
+            Self::Record => "record",
+            Self::Ignore => "ignore",
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -93,6 +123,7 @@ impl DutisConfig {
                 identifier: target.identifier,
                 role: target.role,
                 application: application.to_owned(),
+                on_drift: handler.on_drift,
             });
         }
         handlers.sort_by(|left, right| {
@@ -130,6 +161,33 @@ impl DutisConfig {
         rules.sort_by(|left, right| left.0.cmp(&right.0));
         Ok(rules)
     }
+
+    /// The explicit `on_drift` of every rule that sets one.
+    pub fn drift_actions(&self) -> Result<BTreeMap<AssociationTarget, DriftAction>> {
+        self.handlers
+            .iter()
+            .filter_map(|handler| {
+                let action = handler.on_drift?;
+                Some(
+                    AssociationTarget::new(handler.kind, &handler.identifier, handler.role)
+                        .map(|target| (target, action)),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the configuration without rules whose drift is ignored.
+    pub fn without_ignored_drift(&self) -> Self {
+        Self {
+            handlers: self
+                .handlers
+                .iter()
+                .filter(|handler| handler.on_drift != Some(DriftAction::Ignore))
+                .cloned()
+                .collect(),
+            ..self.clone()
+        }
+    }
 }
 
 #[cfg(test)]
@@ -221,6 +279,46 @@ mod tests {
         assert!(DutisConfig::parse("version = 1\n[associations]\nmd = '   '").is_err());
     }
 
+    #[test]
+    fn reads_per_rule_drift_actions_and_drops_ignored_rules() {
+        let config = DutisConfig::parse(
+            r#"
+                version = 2
+
+                [associations]
+                txt = "com.example.Editor"
+
+                [[handlers]]
+                kind = "extension"
+                identifier = "pdf"
+                application = "com.apple.Preview"
+                on_drift = "ignore"
+
+                [[handlers]]
+                kind = "url_scheme"
+                identifier = "https"
+                application = "com.apple.Safari"
+                on_drift = "notify"
+            "#,
+        )
+        .unwrap();
+        let actions = config.drift_actions().unwrap();
+        assert_eq!(
+            actions[&AssociationTarget::extension("pdf").unwrap()],
+            DriftAction::Ignore
+        );
+        assert_eq!(actions.len(), 2);
+
+        let monitored = config.without_ignored_drift();
+        let rules = monitored.rules().unwrap();
+        assert_eq!(rules.len(), 2);
+        assert!(rules.iter().all(|(target, _)| target.identifier != "pdf"));
+        assert!(DutisConfig::parse(
+            "version = 2\n[[handlers]]\nkind = 'extension'\nidentifier = 'md'\napplication = 'Editor'\non_drift = 'sometimes'"
+        )
+        .is_err());
+    }
+
     #[test]
     fn repository_example_uses_the_current_schema() {
         let config = DutisConfig::parse(include_str!("../dutis.example.toml")).unwrap();
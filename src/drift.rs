@@ -1,18 +1,142 @@
-use crate::governance::{PolicyAssessment, PolicySummary};
-use crate::planner::{AssociationPlan, PlanAction, PlanEntry};
+use crate::application::Application;
+use crate::association::{AssociationKind, AssociationTarget, HandlerRole};
+use crate::governance::{GovernedMutation, Policy, PolicyAssessment, PolicySummary};
+use crate::planner::{assemble_plan, ApplyStatus, AssociationPlan, PlanAction, PlanEntry};
+use crate::snapshot::SnapshotStore;
 use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::SystemTime;
 use time::format_description::well_known::Rfc3339;
-use time::OffsetDateTime;
+use time::{Duration, OffsetDateTime};
 
 pub const DRIFT_SCHEMA_VERSION: u32 = 1;
+const DRIFT_HISTORY_FILE: &str = "history.jsonl";
+/// Once the history grows past this size, its oldest events are dropped until
+/// it is half as large, which bounds what every watcher start reads.
+const MAX_DRIFT_HISTORY_BYTES: u64 = 4 * 1024 * 1024;
+const BUNDLE_VERSIONS_FILE: &str = "bundles.json";
+/// Flap detection and remediation backoff for `watch`. An entry detected more
+/// than `threshold` times within `window` is flapping; after each further
+/// detection its remediation pauses for twice as long, up to `max_backoff`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct FlapPolicy {
+    pub threshold: usize,
+    pub window: Duration,
+    pub base_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for FlapPolicy {
+    fn default() -> Self {
+        Self {
+            threshold: 3,
+            window: Duration::hours(1),
+            base_backoff: Duration::minutes(5),
+            max_backoff: Duration::hours(24),
+        }
+    }
+}
+
+impl FlapPolicy {
+    fn backoff(&self, detections: usize) -> Duration {
+        let doublings = detections.saturating_sub(self.threshold + 1).min(20) as u32;
+        (self.base_backoff * 2_i32.pow(doublings)).min(self.max_backoff)
+    }
+}
+
+/// An association that keeps drifting within the flap window.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct FlappingEntry {
+    pub association: AssociationTarget,
+    pub detections: usize,
+    pub window_seconds: i64,
+    /// Remediation is skipped until this time; absent once the backoff has
+    /// elapsed and one more attempt is allowed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remediation_suspended_until: Option<String>,
+}
+
+/// Finds the drifted entries of `report` that `history` shows flapping for
+/// `config`. A detection counts only when the entry was hijacked again: it
+/// had been remediated or recovered since its previous detection, or now
+/// points at a different application. Detections recorded because another
+/// entry changed the plan digest do not count. The backoff runs from the
+/// entry's last remediation, or its last counted detection when it was never
+/// remediated.
+pub fn detect_flapping(
+    report: &DriftReport,
+    config: &str,
+    history: &[DriftEvent],
+    policy: &FlapPolicy,
+    now: OffsetDateTime,
+) -> Vec<FlappingEntry> {
+    let window_start = now - policy.window;
+    report
+        .changes
+        .iter()
+        .filter_map(|entry| {
+            let association = entry.association();
+            let mut active: Option<Option<&str>> = None;
+            let mut detected_at = Vec::new();
+            let mut remediated_at = None;
+            for event in history.iter().filter(|event| event.config == config) {
+                let recorded = event
+                    .entries
+                    .iter()
+                    .find(|recorded| recorded.association() == association);
+                match (event.event, recorded) {
+                    (DriftEventKind::Detected, Some(recorded)) => {
+                        let current = recorded.current_bundle_id.as_deref();
+                        if active.replace(current) != Some(current) {
+                            detected_at
+                                .extend(OffsetDateTime::parse(&event.timestamp, &Rfc3339).ok());
+                        }
+                    }
+                    (DriftEventKind::Detected, None) => active = None,
+                    (DriftEventKind::Remediated, Some(_)) => {
+                        active = None;
+                        remediated_at = OffsetDateTime::parse(&event.timestamp, &Rfc3339).ok();
+                    }
+                    (DriftEventKind::Recovered, Some(_)) => active = None,
+                    _ => {}
+                }
+            }
+            let detections = detected_at
+                .iter()
+                .filter(|timestamp| **timestamp >= window_start)
+                .count();
+            if detections <= policy.threshold {
+                return None;
+            }
+            let anchor = remediated_at.or_else(|| detected_at.last().copied())?;
+            let until = anchor + policy.backoff(detections);
+            Some(FlappingEntry {
+                association,
+                detections,
+                window_seconds: policy.window.whole_seconds(),
+                remediation_suspended_until: (now < until)
+                    .then(|| until.format(&Rfc3339).ok())
+                    .flatten(),
+            })
+        })
+        .collect()
+}
+
+/// Installs and updates this close to the start of a drift are suspects.
+const ATTRIBUTION_WINDOW: Duration = Duration::hours(24);
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum DriftState {
     InSync,
     DriftDetected,
+    /// Drift keeps coming back after remediation; see `DriftReport::flapping`.
+    Flapping,
     Unresolved,
 }
 
@@ -27,6 +151,10 @@ pub struct DriftReport {
     pub plan: AssociationPlan,
     pub policy: PolicySummary,
     pub assessment: PolicyAssessment,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suspected_cause: Option<SuspectedCause>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub flapping: Vec<FlappingEntry>,
 }
 
 impl DriftReport {
@@ -76,9 +204,71 @@ impl DriftReport {
             plan,
             policy,
             assessment,
+            suspected_cause: None,
+            flapping: Vec::new(),
         }
     }
 
+    /// Records which drifted entries are flapping. A drifted report with any
+    /// becomes `Flapping`; unresolved selectors still take precedence.
+    pub fn mark_flapping(&mut self, flapping: Vec<FlappingEntry>) {
+        if !flapping.is_empty() && self.state == DriftState::DriftDetected {
+            self.state = DriftState::Flapping;
+        }
+        self.flapping = flapping;
+    }
+
+    /// Whether remediation of `entry` is paused by flap backoff at `now`.
+    pub fn remediation_suspended(&self, entry: &PlanEntry, now: OffsetDateTime) -> bool {
+        let association = entry.association();
+        self.flapping.iter().any(|flapping| {
+            flapping.association == association
+                && flapping
+                    .remediation_suspended_until
+                    .as_deref()
+                    .and_then(|until| OffsetDateTime::parse(until, &Rfc3339).ok())
+                    .is_some_and(|until| now < until)
+        })
+    }
+
+    /// The same check limited to the entries `keep` selects, with its own plan
+    /// digest and `policy` assessment, so notification and remediation can
+    /// cover part of a config.
+    pub fn restricted<F>(&self, policy: &Policy, keep: F) -> Result<Self>
+    where
+        F: Fn(&PlanEntry) -> bool,
+    {
+        let entries = self
+            .plan
+            .entries
+            .iter()
+            .filter(|entry| keep(entry))
+            .cloned()
+            .collect();
+        let plan = assemble_plan(self.plan.config_version, entries)?;
+        let assessment = policy.assess(&plan);
+        let mut report = Self::at(
+            self.checked_at.clone(),
+            plan,
+            self.policy.clone(),
+            assessment,
+        );
+        report.suspected_cause = self.suspected_cause.clone();
+        report.mark_flapping(
+            self.flapping
+                .iter()
+                .filter(|flapping| {
+                    report
+                        .changes
+                        .iter()
+                        .any(|entry| entry.association() == flapping.association)
+                })
+                .cloned()
+                .collect(),
+        );
+        Ok(report)
+    }
+
     pub fn notification(&self) -> DriftNotification {
         match self.state {
             DriftState::InSync => DriftNotification {
@@ -93,6 +283,13 @@ impl DriftReport {
                     self.changes.len()
                 ),
             },
+            DriftState::Flapping => DriftNotification {
+                title: "Dutis remediation paused".to_owned(),
+                message: format!(
+                    "{} file association(s) keep drifting back; automatic remediation backs off.",
+                    self.flapping.len()
+                ),
+            },
             DriftState::Unresolved => DriftNotification {
                 title: "Dutis drift check needs attention".to_owned(),
                 message: format!(
@@ -104,6 +301,189 @@ impl DriftReport {
     }
 }
 
+/// The application that most likely took over drifted associations, because
+/// it was installed or updated around the time the drift started.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct SuspectedCause {
+    pub bundle_id: String,
+    pub name: Option<String>,
+    pub path: Option<PathBuf>,
+    /// Drifted associations that now point at this application.
+    pub associations: Vec<AssociationTarget>,
+    pub evidence: Vec<CauseEvidence>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CauseEvidence {
+    /// The bundle was written, as an install or update does.
+    BundleModified { modified_at: String },
+    /// `CFBundleVersion` changed since an earlier check.
+    VersionChanged {
+        previous_version: String,
+        version: String,
+        observed_at: String,
+    },
+}
+
+impl std::fmt::Display for CauseEvidence {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BundleModified { modified_at } => {
+                write!(formatter, "bundle modified at {modified_at}")
+            }
+            Self::VersionChanged {
+                previous_version,
+                version,
+                observed_at,
+            } => write!(
+                formatter,
+                "updated from {previous_version} to {version}, first seen at {observed_at}"
+            ),
+        }
+    }
+}
+
+/// Last observed `CFBundleVersion` per bundle ID, kept so a later check can
+/// tell that an application was updated.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BundleVersionIndex {
+    pub bundles: BTreeMap<String, BundleVersion>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BundleVersion {
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changed_at: Option<String>,
+}
+
+impl BundleVersionIndex {
+    /// Records the version of every cataloged application and returns whether
+    /// anything changed. When several copies share a bundle ID, the first in
+    /// catalog order is used.
+    pub fn observe(&mut self, applications: &[Application], observed_at: &str) -> bool {
+        let mut seen = Vec::new();
+        let mut changed = false;
+        for application in applications {
+            let (Some(bundle_id), Some(version)) = (&application.bundle_id, &application.version)
+            else {
+                continue;
+            };
+            if seen.contains(&bundle_id) {
+                continue;
+            }
+            seen.push(bundle_id);
+            match self.bundles.get_mut(bundle_id) {
+                Some(known) if &known.version == version => {}
+                Some(known) => {
+                    known.previous_version =
+                        Some(std::mem::replace(&mut known.version, version.clone()));
+                    known.changed_at = Some(observed_at.to_owned());
+                    changed = true;
+                }
+                None => {
+                    self.bundles.insert(
+                        bundle_id.clone(),
+                        BundleVersion {
+                            version: version.clone(),
+                            previous_version: None,
+                            changed_at: None,
+                        },
+                    );
+                    changed = true;
+                }
+            }
+        }
+        changed
+    }
+}
+
+/// Sets `report.suspected_cause` to the new handler with the most install or
+/// update evidence near `drift_started_at`. Handlers without such evidence
+/// are not suspected.
+pub fn attribute_drift(
+    report: &mut DriftReport,
+    applications: &[Application],
+    versions: &BundleVersionIndex,
+    drift_started_at: OffsetDateTime,
+) {
+    let mut handlers = BTreeMap::<&str, (Option<&Path>, Vec<AssociationTarget>)>::new();
+    for entry in &report.changes {
+        if let Some(current) = &entry.current {
+            let handler = handlers
+                .entry(current.bundle_id.as_str())
+                .or_insert_with(|| (current.path.as_deref().map(Path::new), Vec::new()));
+            handler.1.push(entry.association());
+        }
+    }
+    let near =
+        |timestamp: OffsetDateTime| (timestamp - drift_started_at).abs() <= ATTRIBUTION_WINDOW;
+    report.suspected_cause = handlers
+        .into_iter()
+        .filter_map(|(bundle_id, (reported_path, associations))| {
+            let application = applications
+                .iter()
+                .find(|application| Some(application.path.as_path()) == reported_path)
+                .or_else(|| {
+                    applications
+                        .iter()
+                        .find(|application| application.bundle_id.as_deref() == Some(bundle_id))
+                });
+            let path = reported_path.or(application.map(|application| application.path.as_path()));
+            let mut evidence = Vec::new();
+            if let Some(modified_at) = path
+                .and_then(bundle_modified_at)
+                .filter(|modified_at| near(*modified_at))
+            {
+                evidence.push(CauseEvidence::BundleModified {
+                    modified_at: modified_at.format(&Rfc3339).ok()?,
+                });
+            }
+            if let Some(BundleVersion {
+                version,
+                previous_version: Some(previous_version),
+                changed_at: Some(changed_at),
+            }) = versions.bundles.get(bundle_id)
+            {
+                if OffsetDateTime::parse(changed_at, &Rfc3339).is_ok_and(near) {
+                    evidence.push(CauseEvidence::VersionChanged {
+                        previous_version: previous_version.clone(),
+                        version: version.clone(),
+                        observed_at: changed_at.clone(),
+                    });
+                }
+            }
+            (!evidence.is_empty()).then(|| SuspectedCause {
+                bundle_id: bundle_id.to_owned(),
+                name: application.map(|application| application.name.clone()),
+                path: path.map(Path::to_path_buf),
+                associations,
+                evidence,
+            })
+        })
+        .max_by(|left, right| {
+            (left.evidence.len(), left.associations.len())
+                .cmp(&(right.evidence.len(), right.associations.len()))
+                .then_with(|| right.bundle_id.cmp(&left.bundle_id))
+        });
+}
+
+/// The latest write to the bundle directory or its `Info.plist`.
+fn bundle_modified_at(path: &Path) -> Option<OffsetDateTime> {
+    [path.to_path_buf(), path.join("Contents/Info.plist")]
+        .iter()
+        .filter_map(|path| {
+            fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        })
+        .max()
+        .map(|modified: SystemTime| OffsetDateTime::from(modified))
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct DriftNotification {
     pub title: String,
@@ -131,6 +511,466 @@ impl DriftTracker {
     }
 }
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftEventKind {
+    Detected,
+    Unresolved,
+    Recovered,
+    Remediated,
+}
+
+impl DriftEventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Detected => "detected",
+            Self::Unresolved => "unresolved",
+            Self::Recovered => "recovered",
+            Self::Remediated => "remediated",
+        }
+    }
+}
+
+/// One association involved in a drift transition. For detections the
+/// previous handler is the declared one; for recoveries and remediations it is
+/// the handler that had drifted.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DriftEventEntry {
+    pub kind: AssociationKind,
+    pub role: HandlerRole,
+    pub identifier: String,
+    pub previous_bundle_id: Option<String>,
+    pub current_bundle_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl DriftEventEntry {
+    pub fn association(&self) -> AssociationTarget {
+        AssociationTarget {
+            kind: self.kind,
+            identifier: self.identifier.clone(),
+            role: self.role,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DriftEvent {
+    pub schema_version: u32,
+    pub timestamp: String,
+    pub event: DriftEventKind,
+    /// The monitored configuration, so one history can cover several watchers.
+    pub config: String,
+    pub plan_digest: String,
+    pub entries: Vec<DriftEventEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audit_id: Option<String>,
+}
+
+/// Append-only JSON Lines log of drift transitions in the state directory.
+/// The oldest events are dropped once it grows past its size limit.
+#[derive(Debug, Clone)]
+pub struct DriftHistory {
+    root: PathBuf,
+    max_bytes: u64,
+}
+
+impl DriftHistory {
+    pub fn from_environment() -> Result<Self> {
+        Ok(Self::new(
+            SnapshotStore::from_environment()?.root().join("drift"),
+        ))
+    }
+
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            max_bytes: MAX_DRIFT_HISTORY_BYTES,
+        }
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.root.join(DRIFT_HISTORY_FILE)
+    }
+
+    pub fn append(&self, event: &DriftEvent) -> Result<()> {
+        self.create_root()?;
+        let mut line = serde_json::to_string(event).context("failed to serialize drift event")?;
+        line.push('\n');
+        let path = self.path();
+        let mut options = OpenOptions::new();
+        options.append(true).create(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options
+            .open(&path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        file.write_all(line.as_bytes())
+            .with_context(|| format!("failed to append to {}", path.display()))?;
+        file.sync_all()?;
+        if file.metadata()?.len() > self.max_bytes {
+            self.truncate_oldest()?;
+        }
+        Ok(())
+    }
+
+    /// Rewrites the history with only the newest events that fit in half of
+    /// the size limit.
+    fn truncate_oldest(&self) -> Result<()> {
+        let path = self.path();
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let budget = usize::try_from(self.max_bytes / 2).unwrap_or(usize::MAX);
+        let mut kept = 0;
+        let start = contents
+            .split_inclusive('\n')
+            .rev()
+            .take_while(|line| {
+                kept += line.len();
+                kept <= budget
+            })
+            .fold(contents.len(), |start, line| start - line.len());
+        let temporary = path.with_extension(format!("{}.tmp", std::process::id()));
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options
+            .open(&temporary)
+            .with_context(|| format!("failed to create {}", temporary.display()))?;
+        file.write_all(&contents.as_bytes()[start..])?;
+        file.sync_all()?;
+        fs::rename(&temporary, &path)
+            .with_context(|| format!("failed to store drift history {}", path.display()))
+    }
+
+    pub fn bundle_versions(&self) -> Result<BundleVersionIndex> {
+        let path = self.root.join(BUNDLE_VERSIONS_FILE);
+        match fs::read(&path) {
+            Ok(contents) => serde_json::from_slice(&contents)
+                .with_context(|| format!("invalid bundle version index {}", path.display())),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                Ok(BundleVersionIndex::default())
+            }
+            Err(error) => Err(error).with_context(|| format!("failed to read {}", path.display())),
+        }
+    }
+
+    pub fn save_bundle_versions(&self, index: &BundleVersionIndex) -> Result<()> {
+        self.create_root()?;
+        let path = self.root.join(BUNDLE_VERSIONS_FILE);
+        let temporary = path.with_extension(format!("{}.tmp", std::process::id()));
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options
+            .open(&temporary)
+            .with_context(|| format!("failed to create {}", temporary.display()))?;
+        serde_json::to_writer_pretty(&mut file, index)?;
+        file.write_all(b"\n")?;
+        file.sync_all()?;
+        fs::rename(&temporary, &path)
+            .with_context(|| format!("failed to store bundle version index {}", path.display()))
+    }
+
+    fn create_root(&self) -> Result<()> {
+        fs::create_dir_all(&self.root)
+            .with_context(|| format!("failed to create {}", self.root.display()))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&self.root, fs::Permissions::from_mode(0o700))?;
+        }
+        Ok(())
+    }
+
+    /// Returns every recorded event, oldest first. Corrupt lines fail loudly.
+    pub fn events(&self) -> Result<Vec<DriftEvent>> {
+        let path = self.path();
+        let file = match fs::File::open(&path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => {
+                return Err(error).with_context(|| format!("failed to open {}", path.display()))
+            }
+        };
+        let mut events = Vec::new();
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.with_context(|| format!("failed to read {}", path.display()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event: DriftEvent = serde_json::from_str(&line).with_context(|| {
+                format!(
+                    "invalid drift event on line {} of {}",
+                    index + 1,
+                    path.display()
+                )
+            })?;
+            if event.schema_version != DRIFT_SCHEMA_VERSION {
+                bail!(
+                    "unsupported drift event schema version {} on line {}",
+                    event.schema_version,
+                    index + 1
+                );
+            }
+            events.push(event);
+        }
+        Ok(events)
+    }
+}
+
+/// Turns successive drift reports for one configuration into transition
+/// events. It resumes from the last recorded event so a restarted watcher
+/// does not record the same drift twice.
+#[derive(Debug)]
+pub struct DriftRecorder {
+    config: String,
+    previous: Option<DriftEvent>,
+}
+
+impl DriftRecorder {
+    pub fn resume(config: &Path, history: &[DriftEvent]) -> Self {
+        let config = config.display().to_string();
+        let previous = history
+            .iter()
+            .rev()
+            .find(|event| event.config == config)
+            .cloned();
+        Self { config, previous }
+    }
+
+    pub fn observe(&mut self, report: &DriftReport) -> Option<DriftEvent> {
+        let previous = self.previous.as_ref();
+        let previous_event = previous.map(|event| event.event);
+        let (event, entries) = match report.state {
+            DriftState::InSync => match previous_event {
+                Some(DriftEventKind::Detected | DriftEventKind::Unresolved) => {
+                    let entries = previous
+                        .into_iter()
+                        .flat_map(|event| &event.entries)
+                        .map(|entry| DriftEventEntry {
+                            previous_bundle_id: entry.current_bundle_id.clone(),
+                            current_bundle_id: entry.previous_bundle_id.clone(),
+                            reason: None,
+                            ..entry.clone()
+                        })
+                        .collect();
+                    (DriftEventKind::Recovered, entries)
+                }
+                _ => return None,
+            },
+            DriftState::DriftDetected | DriftState::Flapping | DriftState::Unresolved => {
+                let event = if report.state == DriftState::Unresolved {
+                    DriftEventKind::Unresolved
+                } else {
+                    DriftEventKind::Detected
+                };
+                if previous.is_some_and(|previous| {
+                    previous.event == event && previous.plan_digest == report.plan_digest
+                }) {
+                    return None;
+                }
+                let offending = if event == DriftEventKind::Detected {
+                    &report.changes
+                } else {
+                    &report.unresolved
+                };
+                (event, offending.iter().map(entry_from_plan).collect())
+            }
+        };
+        self.record(event, report, entries, None)
+    }
+
+    /// When the current drift was first recorded, if drift is ongoing.
+    pub fn drift_started_at(&self) -> Option<OffsetDateTime> {
+        self.previous
+            .as_ref()
+            .filter(|event| event.event == DriftEventKind::Detected)
+            .and_then(|event| OffsetDateTime::parse(&event.timestamp, &Rfc3339).ok())
+    }
+
+    /// Records a remediation that restored every drifted entry.
+    pub fn remediated(
+        &mut self,
+        report: &DriftReport,
+        mutation: &GovernedMutation,
+    ) -> Option<DriftEvent> {
+        if !mutation.report.succeeded() || mutation.report.applied == 0 {
+            return None;
+        }
+        let entries = report
+            .changes
+            .iter()
+            .filter(|entry| {
+                mutation.report.results.iter().any(|result| {
+                    result.status == ApplyStatus::Applied
+                        && result.kind == entry.kind
+                        && result.role == entry.role
+                        && result.extension == entry.extension
+                })
+            })
+            .map(|entry| {
+                let drifted = entry_from_plan(entry);
+                DriftEventEntry {
+                    previous_bundle_id: drifted.current_bundle_id,
+                    current_bundle_id: drifted.previous_bundle_id,
+                    ..drifted
+                }
+            })
+            .collect();
+        self.record(
+            DriftEventKind::Remediated,
+            report,
+            entries,
+            Some(mutation.audit_id.clone()),
+        )
+    }
+
+    fn record(
+        &mut self,
+        event: DriftEventKind,
+        report: &DriftReport,
+        entries: Vec<DriftEventEntry>,
+        audit_id: Option<String>,
+    ) -> Option<DriftEvent> {
+        let event = DriftEvent {
+            schema_version: DRIFT_SCHEMA_VERSION,
+            timestamp: report.checked_at.clone(),
+            event,
+            config: self.config.clone(),
+            plan_digest: report.plan_digest.clone(),
+            entries,
+            audit_id,
+        };
+        self.previous = Some(event.clone());
+        Some(event)
+    }
+}
+
+fn entry_from_plan(entry: &PlanEntry) -> DriftEventEntry {
+    DriftEventEntry {
+        kind: entry.kind,
+        role: entry.role,
+        identifier: entry.extension.clone(),
+        previous_bundle_id: entry.target.as_ref().map(|target| target.bundle_id.clone()),
+        current_bundle_id: entry
+            .current
+            .as_ref()
+            .map(|current| current.bundle_id.clone()),
+        reason: entry.reason.clone(),
+    }
+}
+
+/// How often one association drifted and to which handlers.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct DriftAssociationStats {
+    pub kind: AssociationKind,
+    pub role: HandlerRole,
+    pub identifier: String,
+    pub detections: usize,
+    pub remediations: usize,
+    pub first_detected_at: String,
+    pub last_detected_at: String,
+    /// Count of detections per handler the association drifted to.
+    pub drifted_to: BTreeMap<String, usize>,
+}
+
+impl DriftAssociationStats {
+    pub fn association(&self) -> AssociationTarget {
+        AssociationTarget {
+            kind: self.kind,
+            identifier: self.identifier.clone(),
+            role: self.role,
+        }
+    }
+}
+
+/// Aggregates detections and remediations per association, most frequently
+/// drifted first. `events` must be in chronological order. An association
+/// that stays drifted to the same handler across several detection events,
+/// because other entries changed, counts once.
+pub fn drift_statistics(events: &[DriftEvent]) -> Vec<DriftAssociationStats> {
+    type Key = (AssociationKind, HandlerRole, String);
+    let mut statistics = BTreeMap::<Key, DriftAssociationStats>::new();
+    let mut active = BTreeMap::<(&str, Key), Option<&str>>::new();
+    for event in events {
+        let still_drifted = event
+            .entries
+            .iter()
+            .map(|entry| (entry.kind, entry.role, entry.identifier.clone()))
+            .collect::<Vec<_>>();
+        match event.event {
+            DriftEventKind::Detected => active
+                .retain(|(config, key), _| *config != event.config || still_drifted.contains(key)),
+            DriftEventKind::Recovered | DriftEventKind::Remediated => {
+                active.retain(|(config, _), _| *config != event.config)
+            }
+            DriftEventKind::Unresolved => {}
+        }
+        for entry in &event.entries {
+            let key = (entry.kind, entry.role, entry.identifier.clone());
+            match event.event {
+                DriftEventKind::Detected => {
+                    let current = entry.current_bundle_id.as_deref();
+                    if active.insert((event.config.as_str(), key.clone()), current) == Some(current)
+                    {
+                        continue;
+                    }
+                    let stats = statistics
+                        .entry(key)
+                        .or_insert_with(|| DriftAssociationStats {
+                            kind: entry.kind,
+                            role: entry.role,
+                            identifier: entry.identifier.clone(),
+                            detections: 0,
+                            remediations: 0,
+                            first_detected_at: event.timestamp.clone(),
+                            last_detected_at: event.timestamp.clone(),
+                            drifted_to: BTreeMap::new(),
+                        });
+                    stats.detections += 1;
+                    stats.last_detected_at = event.timestamp.clone();
+                    *stats
+                        .drifted_to
+                        .entry(
+                            entry
+                                .current_bundle_id
+                                .clone()
+                                .unwrap_or_else(|| "<no default>".to_owned()),
+                        )
+                        .or_default() += 1;
+                }
+                DriftEventKind::Remediated => {
+                    if let Some(stats) = statistics.get_mut(&key) {
+                        stats.remediations += 1;
+                    }
+                }
+                DriftEventKind::Unresolved | DriftEventKind::Recovered => {}
+            }
+        }
+    }
+    let mut statistics = statistics.into_values().collect::<Vec<_>>();
+    statistics.sort_by(|left, right| {
+        right
+            .detections
+            .cmp(&left.detections)
+            .then_with(|| left.identifier.cmp(&right.identifier))
+    });
+    statistics
+}
+
 pub fn send_macos_notification(notification: &DriftNotification) -> Result<()> {
     if !cfg!(target_os = "macos") {
         bail!("macOS notifications are unavailable on this platform");
@@ -162,8 +1002,7 @@ mod tests {
     use super::*;
     use crate::governance::{ApprovalMode, PolicyAssessment, PolicySummary};
     use crate::planner::{assemble_plan, PlanEntry};
-    use std::collections::BTreeMap;
-    use std::path::PathBuf;
+    use crate::system::DefaultApplication;
 
     fn policy() -> (PolicySummary, PolicyAssessment) {
         (
@@ -179,6 +1018,10 @@ mod tests {
                 allowed_applications: None,
                 protected_associations: BTreeMap::new(),
                 protected_handlers: Vec::new(),
+                retention: Default::default(),
+                verification: Default::default(),
+                atomic_apply: false,
+                notification_sinks: Vec::new(),
             },
             PolicyAssessment {
                 allowed: true,
@@ -214,6 +1057,30 @@ mod tests {
         assert_eq!(report(PlanAction::Unresolved).state, DriftState::Unresolved);
     }
 
+    #[test]
+    fn restricted_reports_assess_only_their_own_entries() {
+        let mut full = report(PlanAction::Change);
+        let mut entries = full.plan.entries.clone();
+        entries.push(PlanEntry {
+            extension: "txt".to_owned(),
+            action: PlanAction::Unresolved,
+            reason: Some("missing app".to_owned()),
+            ..entries[0].clone()
+        });
+        full.plan = assemble_plan(1, entries).unwrap();
+        full.assessment = Policy::default().assess(&full.plan);
+        assert!(!full.assessment.allowed);
+
+        let changes = full
+            .restricted(&Policy::default(), |entry| {
+                entry.action == PlanAction::Change
+            })
+            .unwrap();
+        assert_eq!(changes.state, DriftState::DriftDetected);
+        assert!(changes.assessment.allowed);
+        assert!(changes.assessment.violations.is_empty());
+    }
+
     #[test]
     fn tracker_deduplicates_unchanged_drift_and_notifies_on_recovery() {
         let clean = report(PlanAction::Unchanged);
@@ -225,6 +1092,307 @@ mod tests {
         assert!(tracker.should_notify(&clean));
     }
 
+    fn detected(timestamp: &str, drifted: &[(&str, &str)]) -> DriftEvent {
+        DriftEvent {
+            schema_version: DRIFT_SCHEMA_VERSION,
+            timestamp: timestamp.to_owned(),
+            event: DriftEventKind::Detected,
+            config: "/tmp/dutis.toml".to_owned(),
+            plan_digest: timestamp.to_owned(),
+            entries: drifted
+                .iter()
+                .map(|(identifier, bundle_id)| DriftEventEntry {
+                    kind: AssociationKind::UrlScheme,
+                    role: HandlerRole::All,
+                    identifier: (*identifier).to_owned(),
+                    previous_bundle_id: Some("com.apple.Safari".to_owned()),
+                    current_bundle_id: Some((*bundle_id).to_owned()),
+                    reason: None,
+                })
+                .collect(),
+            audit_id: None,
+        }
+    }
+
+    #[test]
+    fn recorder_records_transitions_once_and_resumes_from_history() {
+        let root = std::env::temp_dir().join(format!(
+            "dutis-drift-history-{}-{}",
+            std::process::id(),
+            OffsetDateTime::now_utc().unix_timestamp_nanos()
+        ));
+        let history = DriftHistory::new(&root);
+        let config = PathBuf::from("/tmp/dutis.toml");
+        let mut recorder = DriftRecorder::resume(&config, &[]);
+        assert!(recorder.observe(&report(PlanAction::Unchanged)).is_none());
+        let event = recorder.observe(&report(PlanAction::Change)).unwrap();
+        assert_eq!(event.event, DriftEventKind::Detected);
+        assert_eq!(event.entries[0].identifier, "md");
+        history.append(&event).unwrap();
+        assert!(recorder.observe(&report(PlanAction::Change)).is_none());
+
+        let mut resumed = DriftRecorder::resume(&config, &history.events().unwrap());
+        assert!(resumed.observe(&report(PlanAction::Change)).is_none());
+        let recovered = resumed.observe(&report(PlanAction::Unchanged)).unwrap();
+        assert_eq!(recovered.event, DriftEventKind::Recovered);
+        assert_eq!(recovered.entries.len(), 1);
+        history.append(&recovered).unwrap();
+        assert_eq!(history.events().unwrap().len(), 2);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn history_drops_its_oldest_events_past_the_size_limit() {
+        let root = std::env::temp_dir().join(format!(
+            "dutis-drift-history-limit-{}-{}",
+            std::process::id(),
+            OffsetDateTime::now_utc().unix_timestamp_nanos()
+        ));
+        let event = |index: usize| {
+            detected(
+                &format!("2026-08-03T00:00:{index:02}Z"),
+                &[("http", "com.example.Browser")],
+            )
+        };
+        let line_bytes = serde_json::to_string(&event(0)).unwrap().len() as u64 + 1;
+        let history = DriftHistory {
+            root: root.clone(),
+            max_bytes: line_bytes * 4,
+        };
+        for index in 0..5 {
+            history.append(&event(index)).unwrap();
+        }
+        let timestamps = history
+            .events()
+            .unwrap()
+            .into_iter()
+            .map(|event| event.timestamp)
+            .collect::<Vec<_>>();
+        assert_eq!(timestamps, ["2026-08-03T00:00:03Z", "2026-08-03T00:00:04Z"]);
+        history.append(&event(5)).unwrap();
+        assert_eq!(history.events().unwrap().len(), 3);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn statistics_count_each_hijack_once_per_drift() {
+        let mut recovered = detected("2026-08-03T00:00:00Z", &[]);
+        recovered.event = DriftEventKind::Recovered;
+        let mut remediated = detected("2026-08-05T00:00:00Z", &[("https", "com.apple.Safari")]);
+        remediated.event = DriftEventKind::Remediated;
+        let events = [
+            detected("2026-08-01T00:00:00Z", &[("https", "com.google.Chrome")]),
+            // https is still drifted to the same browser when mailto joins.
+            detected(
+                "2026-08-02T00:00:00Z",
+                &[
+                    ("https", "com.google.Chrome"),
+                    ("mailto", "com.example.Mail"),
+                ],
+            ),
+            recovered,
+            detected("2026-08-04T00:00:00Z", &[("https", "com.google.Chrome")]),
+            remediated,
+        ];
+
+        let statistics = drift_statistics(&events);
+        assert_eq!(statistics[0].identifier, "https");
+        assert_eq!(statistics[0].detections, 2);
+        assert_eq!(statistics[0].remediations, 1);
+        assert_eq!(statistics[0].first_detected_at, "2026-08-01T00:00:00Z");
+        assert_eq!(statistics[0].last_detected_at, "2026-08-04T00:00:00Z");
+        assert_eq!(
+            statistics[0].drifted_to,
+            BTreeMap::from([("com.google.Chrome".to_owned(), 2)])
+        );
+        assert_eq!(statistics[1].identifier, "mailto");
+        assert_eq!(statistics[1].detections, 1);
+    }
+
+    fn application(bundle_id: &str, path: &Path, version: &str) -> Application {
+        Application {
+            name: "Browser".to_owned(),
+            path: path.to_path_buf(),
+            bundle_id: Some(bundle_id.to_owned()),
+            version: Some(version.to_owned()),
+            extensions: Vec::new(),
+            utis: Vec::new(),
+            mime_types: Vec::new(),
+            url_schemes: vec!["https".to_owned()],
+        }
+    }
+
+    #[test]
+    fn bundle_version_index_records_updates() {
+        let path = PathBuf::from("/Applications/Browser.app");
+        let mut index = BundleVersionIndex::default();
+        assert!(index.observe(&[application("com.example.Browser", &path, "1")], "t1"));
+        assert!(!index.observe(&[application("com.example.Browser", &path, "1")], "t2"));
+        assert!(index.observe(&[application("com.example.Browser", &path, "2")], "t3"));
+        assert_eq!(
+            index.bundles["com.example.Browser"],
+            BundleVersion {
+                version: "2".to_owned(),
+                previous_version: Some("1".to_owned()),
+                changed_at: Some("t3".to_owned()),
+            }
+        );
+    }
+
+    #[test]
+    fn attributes_drift_to_a_recently_updated_new_handler() {
+        let root = std::env::temp_dir().join(format!(
+            "dutis-drift-cause-{}-{}",
+            std::process::id(),
+            OffsetDateTime::now_utc().unix_timestamp_nanos()
+        ));
+        let bundle = root.join("Browser.app");
+        fs::create_dir_all(bundle.join("Contents")).unwrap();
+        fs::write(bundle.join("Contents/Info.plist"), "").unwrap();
+        let mut drifted = report(PlanAction::Change);
+        drifted.changes[0].current = Some(DefaultApplication {
+            kind: AssociationKind::Extension,
+            role: HandlerRole::All,
+            extension: "md".to_owned(),
+            name: Some("Browser".to_owned()),
+            path: Some(bundle.display().to_string()),
+            bundle_id: "com.example.Browser".to_owned(),
+        });
+        let applications = [application("com.example.Browser", &bundle, "2")];
+        let now = OffsetDateTime::now_utc();
+        let mut versions = BundleVersionIndex::default();
+        versions.observe(&[application("com.example.Browser", &bundle, "1")], "t1");
+        versions.observe(&applications, &now.format(&Rfc3339).unwrap());
+
+        attribute_drift(&mut drifted, &applications, &versions, now);
+        let cause = drifted.suspected_cause.clone().unwrap();
+        assert_eq!(cause.bundle_id, "com.example.Browser");
+        assert_eq!(cause.associations[0].identifier, "md");
+        assert!(matches!(
+            cause.evidence[0],
+            CauseEvidence::BundleModified { .. }
+        ));
+        assert!(matches!(
+            &cause.evidence[1],
+            CauseEvidence::VersionChanged { previous_version, version, .. }
+                if previous_version == "1" && version == "2"
+        ));
+
+        // Nothing changed near a drift that started long ago.
+        attribute_drift(
+            &mut drifted,
+            &applications,
+            &BundleVersionIndex::default(),
+            now - Duration::days(30),
+        );
+        assert!(drifted.suspected_cause.is_none());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn repeated_drift_is_flapping_with_doubling_remediation_backoff() {
+        let event = |kind: DriftEventKind, minute: i64| DriftEvent {
+            schema_version: DRIFT_SCHEMA_VERSION,
+            timestamp: (OffsetDateTime::UNIX_EPOCH + Duration::minutes(minute))
+                .format(&Rfc3339)
+                .unwrap(),
+            event: kind,
+            config: "/tmp/dutis.toml".to_owned(),
+            plan_digest: minute.to_string(),
+            entries: vec![DriftEventEntry {
+                kind: AssociationKind::Extension,
+                role: HandlerRole::All,
+                identifier: "md".to_owned(),
+                previous_bundle_id: None,
+                current_bundle_id: None,
+                reason: None,
+            }],
+            audit_id: None,
+        };
+        let mut history = Vec::new();
+        for minute in [0, 2, 4] {
+            history.push(event(DriftEventKind::Detected, minute));
+            history.push(event(DriftEventKind::Remediated, minute + 1));
+        }
+        let policy = FlapPolicy::default();
+        let at = |minute| OffsetDateTime::UNIX_EPOCH + Duration::minutes(minute);
+        let mut drifted = report(PlanAction::Change);
+        assert!(detect_flapping(&drifted, "/tmp/dutis.toml", &history, &policy, at(6)).is_empty());
+
+        history.push(event(DriftEventKind::Detected, 6));
+        let flapping = detect_flapping(&drifted, "/tmp/dutis.toml", &history, &policy, at(6));
+        assert_eq!(flapping[0].detections, 4);
+        assert_eq!(
+            flapping[0].remediation_suspended_until.as_deref(),
+            Some("1970-01-01T00:10:00Z")
+        );
+        assert!(detect_flapping(&drifted, "/tmp/other.toml", &history, &policy, at(6)).is_empty());
+        drifted.mark_flapping(flapping);
+        assert_eq!(drifted.state, DriftState::Flapping);
+        assert!(drifted.remediation_suspended(&drifted.changes[0], at(9)));
+        assert!(!drifted.remediation_suspended(&drifted.changes[0], at(10)));
+        assert_eq!(
+            drifted
+                .restricted(&Policy::default(), |_| true)
+                .unwrap()
+                .state,
+            DriftState::Flapping
+        );
+
+        history.push(event(DriftEventKind::Remediated, 11));
+        history.push(event(DriftEventKind::Detected, 12));
+        let flapping = detect_flapping(&drifted, "/tmp/dutis.toml", &history, &policy, at(12));
+        assert_eq!(
+            flapping[0].remediation_suspended_until.as_deref(),
+            Some("1970-01-01T00:21:00Z")
+        );
+        // Outside the window the earlier detections no longer count.
+        assert!(
+            detect_flapping(&drifted, "/tmp/dutis.toml", &history, &policy, at(120)).is_empty()
+        );
+    }
+
+    #[test]
+    fn digest_changes_of_a_persistent_drift_are_not_flapping() {
+        let event = |kind: DriftEventKind, minute: i64, current: &str| DriftEvent {
+            schema_version: DRIFT_SCHEMA_VERSION,
+            timestamp: (OffsetDateTime::UNIX_EPOCH + Duration::minutes(minute))
+                .format(&Rfc3339)
+                .unwrap(),
+            event: kind,
+            config: "/tmp/dutis.toml".to_owned(),
+            plan_digest: minute.to_string(),
+            entries: vec![DriftEventEntry {
+                kind: AssociationKind::Extension,
+                role: HandlerRole::All,
+                identifier: "md".to_owned(),
+                previous_bundle_id: None,
+                current_bundle_id: Some(current.to_owned()),
+                reason: None,
+            }],
+            audit_id: None,
+        };
+        let policy = FlapPolicy::default();
+        let at = OffsetDateTime::UNIX_EPOCH + Duration::minutes(10);
+        let drifted = report(PlanAction::Change);
+        // Other entries drifting or being remediated change the digest, so
+        // the same hijack is recorded again and again.
+        let mut history = (0..6)
+            .map(|minute| event(DriftEventKind::Detected, minute, "com.example.Hijacker"))
+            .collect::<Vec<_>>();
+        assert!(detect_flapping(&drifted, "/tmp/dutis.toml", &history, &policy, at).is_empty());
+
+        // A different hijacker is a new detection; three of them are not yet
+        // above the threshold, a fourth is.
+        history.push(event(DriftEventKind::Detected, 6, "com.example.Other"));
+        history.push(event(DriftEventKind::Detected, 7, "com.example.Hijacker"));
+        assert!(detect_flapping(&drifted, "/tmp/dutis.toml", &history, &policy, at).is_empty());
+        history.push(event(DriftEventKind::Remediated, 8, "com.example.Editor"));
+        history.push(event(DriftEventKind::Detected, 9, "com.example.Hijacker"));
+        let flapping = detect_flapping(&drifted, "/tmp/dutis.toml", &history, &policy, at);
+        assert_eq!(flapping[0].detections, 4);
+    }
+
     #[test]
     fn escapes_notification_text_for_applescript() {
         assert_eq!(
@@ -0,0 +1,194 @@
+use anyhow::{bail, Context, Result};
+use notify::event::{AccessKind, AccessMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+/// A burst of changes is rechecked once, but never later than this many
+/// debounce periods after its first event.
+const MAX_DEBOUNCE_PERIODS: u32 = 10;
+
+/// Why an event-driven watcher woke up.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Wake {
+    Changed,
+    /// Nothing changed before the fallback interval elapsed.
+    Timeout,
+}
+
+/// A source of filesystem change notifications for `watch --events`.
+pub trait ChangeSource {
+    /// Blocks until a relevant change is reported, returning `false` when
+    /// `timeout` elapses first.
+    fn wait(&mut self, timeout: Duration) -> Result<bool>;
+}
+
+/// Uses the platform notification backend: FSEvents on macOS and inotify on
+/// Linux.
+pub struct NotifyChangeSource {
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+}
+
+impl NotifyChangeSource {
+    /// Watches each existing path without descending into it. Missing paths
+    /// are skipped; at least one path must be watchable.
+    pub fn watch(paths: &[PathBuf]) -> Result<Self> {
+        let (sender, events) = mpsc::channel();
+        let mut watcher =
+            notify::recommended_watcher(sender).context("failed to start file notifications")?;
+        let mut watched = 0;
+        for path in paths.iter().filter(|path| path.exists()) {
+            watcher
+                .watch(path, RecursiveMode::NonRecursive)
+                .with_context(|| format!("failed to watch {}", path.display()))?;
+            watched += 1;
+        }
+        if watched == 0 {
+            bail!("none of the watched paths exist");
+        }
+        Ok(Self {
+            _watcher: watcher,
+            events,
+        })
+    }
+}
+
+impl ChangeSource for NotifyChangeSource {
+    fn wait(&mut self, timeout: Duration) -> Result<bool> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.events.recv_timeout(remaining) {
+                Ok(event) => {
+                    if is_change(&event.context("file notification failed")?.kind) {
+                        return Ok(true);
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => return Ok(false),
+                Err(RecvTimeoutError::Disconnected) => {
+                    bail!("file notifications stopped unexpectedly")
+                }
+            }
+        }
+    }
+}
+
+/// Reports no changes and simply waits, giving fixed-interval polling.
+#[derive(Debug, Default)]
+pub struct PollingChangeSource;
+
+impl ChangeSource for PollingChangeSource {
+    fn wait(&mut self, timeout: Duration) -> Result<bool> {
+        std::thread::sleep(timeout);
+        Ok(false)
+    }
+}
+
+/// Reads and opens do not change associations; everything else might.
+fn is_change(kind: &EventKind) -> bool {
+    match kind {
+        EventKind::Access(AccessKind::Close(AccessMode::Write)) => true,
+        EventKind::Access(_) => false,
+        _ => true,
+    }
+}
+
+/// Waits for the next change, then for `debounce` of quiet so a burst of
+/// writes from one install or preference update triggers a single recheck.
+pub fn wait_for_change<S: ChangeSource + ?Sized>(
+    source: &mut S,
+    fallback: Duration,
+    debounce: Duration,
+) -> Result<Wake> {
+    if !source.wait(fallback)? {
+        return Ok(Wake::Timeout);
+    }
+    let deadline = Instant::now() + debounce * MAX_DEBOUNCE_PERIODS;
+    while Instant::now() < deadline && source.wait(debounce)? {}
+    Ok(Wake::Changed)
+}
+
+/// Paths whose changes can alter file associations: the Launch Services
+/// preferences directory, the application roots, and the configuration.
+/// Directories are watched because preferences and bundles are replaced
+/// rather than edited in place.
+pub fn association_watch_paths(config: &Path) -> Vec<PathBuf> {
+    let mut paths = vec![
+        PathBuf::from("/Applications"),
+        PathBuf::from("/System/Applications"),
+    ];
+    if let Some(home) = std::env::var_os("HOME").map(PathBuf::from) {
+        paths.push(home.join("Library/Preferences/com.apple.LaunchServices"));
+        paths.push(home.join("Applications"));
+    }
+    if let Some(parent) = config
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        paths.push(parent.to_path_buf());
+    } else {
+        paths.push(PathBuf::from("."));
+    }
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::fs;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    struct ScriptedSource(VecDeque<bool>);
+
+    impl ChangeSource for ScriptedSource {
+        fn wait(&mut self, _timeout: Duration) -> Result<bool> {
+            Ok(self.0.pop_front().unwrap_or(false))
+        }
+    }
+
+    #[test]
+    fn debounces_a_burst_into_one_wake_and_times_out_when_quiet() {
+        let mut source = ScriptedSource(VecDeque::from([true, true, true, false]));
+        assert_eq!(
+            wait_for_change(
+                &mut source,
+                Duration::from_secs(60),
+                Duration::from_millis(100)
+            )
+            .unwrap(),
+            Wake::Changed
+        );
+        assert!(source.0.is_empty());
+        assert_eq!(
+            wait_for_change(
+                &mut source,
+                Duration::from_secs(60),
+                Duration::from_millis(100)
+            )
+            .unwrap(),
+            Wake::Timeout
+        );
+    }
+
+    #[test]
+    fn platform_notifications_report_replaced_files() {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let root =
+            std::env::temp_dir().join(format!("dutis-file-events-{}-{unique}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let mut source = NotifyChangeSource::watch(&[root.clone(), root.join("missing")]).unwrap();
+        assert!(!source.wait(Duration::from_millis(50)).unwrap());
+
+        let temporary = root.join("prefs.plist.tmp");
+        fs::write(&temporary, "updated").unwrap();
+        fs::rename(&temporary, root.join("prefs.plist")).unwrap();
+        assert!(source.wait(Duration::from_secs(5)).unwrap());
+        fs::remove_dir_all(root).unwrap();
+    }
+}
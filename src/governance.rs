@@ -1,8 +1,18 @@
-use crate::application::normalize_extension;
-use crate::association::{AssociationKind, AssociationTarget, HandlerRole};
-use crate::planner::{ApplyReport, AssociationPlan, PlanAction};
-use crate::snapshot::{apply_plan_with_snapshot, SnapshotReason, SnapshotStore};
+use crate::application::{normalize_extension, Application};
+use crate::association::{normalize_identifier, AssociationKind, AssociationTarget, HandlerRole};
+use crate::notification::{NotificationSink, NotificationSinkConfig};
+use crate::planner::{ApplyEntryResult, ApplyReport, ApplyStatus, AssociationPlan, PlanAction};
+use crate::retention::{archive_bundles, read_archive, write_archive, RetentionPolicy};
+use crate::snapshot::{
+    apply_plan_with_snapshot, build_rollback_plan, revert_applied_entries, RollbackSelection,
+    Snapshot, SnapshotReason, SnapshotStore,
+};
+use crate::state_lock::{lock_timeout_from_environment, LockError, LockHolder, StateLock};
+use crate::system::DefaultApplication;
+use crate::verification::{verify_applied, EntryVerification, VerificationPolicy};
 use anyhow::{anyhow, bail, Context, Result};
+use clap::ValueEnum;
+use hmac::{Hmac, Mac};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::{BTreeMap, BTreeSet};
@@ -11,11 +21,13 @@ use std::fs::{self, OpenOptions};
 use std::io::{BufReader, BufWriter, Write};
 use std::path::{Path, PathBuf};
 use time::format_description::well_known::Rfc3339;
-use time::OffsetDateTime;
+use time::{Date, Duration, Month, OffsetDateTime};
 
 pub const POLICY_VERSION: u32 = 1;
 pub const AUDIT_SCHEMA_VERSION: u32 = 1;
 const POLICY_FILE_ENV: &str = "DUTIS_POLICY_FILE";
+const AUDIT_KEY_FILE_ENV: &str = "DUTIS_AUDIT_KEY_FILE";
+const MIN_AUDIT_KEY_BYTES: usize = 32;
 
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -38,6 +50,16 @@ pub struct Policy {
     pub allowed_applications: Option<BTreeSet<String>>,
     pub protected_associations: BTreeMap<String, String>,
     pub protected_handlers: Vec<ProtectedHandler>,
+    #[serde(skip_serializing_if = "RetentionPolicy::is_default")]
+    pub retention: RetentionPolicy,
+    /// Revert every applied entry when any entry of a mutation fails.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub atomic_apply: bool,
+    #[serde(skip_serializing_if = "VerificationPolicy::is_default")]
+    pub verification: VerificationPolicy,
+    /// Where `dutis watch` delivers drift notifications.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub notification_sinks: Vec<NotificationSinkConfig>,
     #[serde(skip_serializing)]
     approval_token_sha256: Option<String>,
 }
@@ -55,6 +77,14 @@ struct RawPolicy {
     protected_associations: BTreeMap<String, String>,
     #[serde(default)]
     protected_handlers: Vec<ProtectedHandler>,
+    #[serde(default)]
+    retention: RetentionPolicy,
+    #[serde(default)]
+    atomic_apply: bool,
+    #[serde(default)]
+    verification: VerificationPolicy,
+    #[serde(default)]
+    notification_sinks: Vec<NotificationSinkConfig>,
     approval_token_sha256: Option<String>,
 }
 
@@ -78,6 +108,10 @@ impl Default for Policy {
             allowed_applications: None,
             protected_associations: BTreeMap::new(),
             protected_handlers: Vec::new(),
+            retention: RetentionPolicy::default(),
+            atomic_apply: false,
+            verification: VerificationPolicy::default(),
+            notification_sinks: Vec::new(),
             approval_token_sha256: None,
         }
     }
@@ -158,6 +192,11 @@ impl Policy {
                 bail!("approval_token_sha256 must be a 64-character SHA-256 hex digest");
             }
         }
+        raw.retention.validate()?;
+        raw.verification.validate()?;
+        for sink in &raw.notification_sinks {
+            sink.validate()?;
+        }
         Ok(Self {
             version: raw.version,
             approval_mode: raw.approval_mode,
@@ -166,6 +205,10 @@ impl Policy {
             allowed_applications,
             protected_associations,
             protected_handlers,
+            retention: raw.retention,
+            atomic_apply: raw.atomic_apply,
+            verification: raw.verification,
+            notification_sinks: raw.notification_sinks,
             approval_token_sha256: raw
                 .approval_token_sha256
                 .map(|digest| digest.to_ascii_lowercase()),
@@ -340,6 +383,15 @@ impl LoadedPolicy {
             allowed_applications: self.policy.allowed_applications.clone(),
             protected_associations: self.policy.protected_associations.clone(),
             protected_handlers: self.policy.protected_handlers.clone(),
+            retention: self.policy.retention.clone(),
+            atomic_apply: self.policy.atomic_apply,
+            verification: self.policy.verification,
+            notification_sinks: self
+                .policy
+                .notification_sinks
+                .iter()
+                .map(NotificationSink::describe)
+                .collect(),
         }
     }
 }
@@ -357,9 +409,14 @@ pub struct PolicySummary {
     pub allowed_applications: Option<BTreeSet<String>>,
     pub protected_associations: BTreeMap<String, String>,
     pub protected_handlers: Vec<ProtectedHandler>,
+    pub retention: RetentionPolicy,
+    pub atomic_apply: bool,
+    pub verification: VerificationPolicy,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub notification_sinks: Vec<String>,
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, ValueEnum)]
 #[serde(rename_all = "snake_case")]
 pub enum MutationChannel {
     Cli,
@@ -368,7 +425,7 @@ pub enum MutationChannel {
     Watcher,
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, ValueEnum)]
 #[serde(rename_all = "snake_case")]
 pub enum MutationOperation {
     Set,
@@ -384,14 +441,20 @@ pub struct MutationRequest {
     pub operation: MutationOperation,
     pub explicit_approval: bool,
     pub approval_token: Option<String>,
+    /// Revert applied entries if any entry fails, regardless of policy.
+    pub atomic: bool,
+    /// The audit record this mutation undoes, if it is an undo.
+    pub undoes: Option<String>,
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, ValueEnum)]
 #[serde(rename_all = "snake_case")]
 pub enum AuditOutcome {
     Pending,
     Succeeded,
     PartialFailure,
+    /// An atomic mutation failed and every applied entry was reverted.
+    RolledBack,
     Denied,
     FailedBeforeMutation,
 }
@@ -402,6 +465,10 @@ pub struct VerificationSummary {
     pub applied: usize,
     pub skipped: usize,
     pub failed: usize,
+    /// Handlers observed by the post-apply verification pass. Absent on
+    /// records written before that pass existed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub entries: Vec<EntryVerification>,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -423,31 +490,244 @@ pub struct MutationAuditRecord {
     pub result: Option<ApplyReport>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub verification: Option<VerificationSummary>,
+    /// Compensating changes made by an atomic mutation after a failure.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compensation: Option<ApplyReport>,
+    /// The audit record an undo reverted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub undoes: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// Absent on records written before hash chaining was introduced.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain: Option<AuditChainLink>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AuditChainLink {
+    pub sequence: u64,
+    pub previous_hash: Option<String>,
+    pub record_hash: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signing_key_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// Set once the store also records the chain head, so a missing head
+    /// file means the newest records were removed with it.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub head_recorded: bool,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditChainProblem {
+    Unreadable,
+    Missing,
+    /// Records after the last one in the chain, according to the chain head.
+    Truncated,
+    Reordered,
+    Modified,
+    BrokenLink,
+    Unsigned,
+    InvalidSignature,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct AuditBrokenLink {
+    pub problem: AuditChainProblem,
+    pub sequence: Option<u64>,
+    pub record_id: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct AuditChainHead {
+    pub sequence: u64,
+    pub record_id: String,
+    pub record_hash: String,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct AuditVerification {
+    pub verified: bool,
+    pub records: usize,
+    pub archived: usize,
+    pub chained: usize,
+    pub unchained: usize,
+    pub signed: usize,
+    pub signatures_checked: bool,
+    /// Records before and including this sequence were pruned from the chain.
+    pub pruned_through: Option<u64>,
+    pub head: Option<AuditChainHead>,
+    pub first_broken_link: Option<AuditBrokenLink>,
+}
+
+/// A signed pointer to one record of the chain: the last pruned record, kept
+/// so the remaining chain can still be verified, or the newest record, kept so
+/// deleting the newest records is detected.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+struct AuditChainAnchor {
+    schema_version: u32,
+    sequence: u64,
+    record_id: String,
+    record_hash: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signing_key_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signature: Option<String>,
+}
+
+impl AuditChainAnchor {
+    fn signed_material(&self) -> String {
+        format!("{}:{}:{}", self.sequence, self.record_id, self.record_hash)
+    }
+}
+
+/// Filters applied by [`AuditStore::query`]; every populated field must match.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct AuditQuery {
+    pub since: Option<OffsetDateTime>,
+    pub until: Option<OffsetDateTime>,
+    pub requester: Option<String>,
+    pub channel: Option<MutationChannel>,
+    pub operation: Option<MutationOperation>,
+    pub outcome: Option<AuditOutcome>,
+    pub target: Option<String>,
+    /// Narrows `target` to one association kind.
+    pub kind: Option<AssociationKind>,
+}
+
+impl AuditQuery {
+    pub fn matches(&self, record: &MutationAuditRecord) -> bool {
+        self.matches_time(&record.id)
+            && self
+                .requester
+                .as_ref()
+                .is_none_or(|requester| record.requester.eq_ignore_ascii_case(requester.trim()))
+            && self.channel.is_none_or(|channel| record.channel == channel)
+            && self
+                .operation
+                .is_none_or(|operation| record.operation == operation)
+            && self.outcome.is_none_or(|outcome| record.outcome == outcome)
+            && self.target.as_ref().is_none_or(|target| {
+                record.plan.entries.iter().any(|entry| {
+                    entry.action == PlanAction::Change
+                        && self.kind.is_none_or(|kind| entry.kind == kind)
+                        && normalize_identifier(entry.kind, target)
+                            .is_ok_and(|identifier| identifier == entry.extension)
+                })
+            })
+    }
+
+    fn matches_time(&self, id: &str) -> bool {
+        let nanos = record_nanos(id);
+        self.since
+            .is_none_or(|since| nanos >= since.unix_timestamp_nanos())
+            && self
+                .until
+                .is_none_or(|until| nanos <= until.unix_timestamp_nanos())
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct AuditPage {
+    pub total: usize,
+    pub offset: usize,
+    pub limit: Option<usize>,
+    pub next_offset: Option<usize>,
+    pub records: Vec<MutationAuditRecord>,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum)]
+pub enum AuditExportFormat {
+    /// One compact JSON record per line
+    Jsonl,
+    /// One summary row per record with a header
+    Csv,
+    /// RFC 5424 syslog lines with structured data
+    Syslog,
+}
+
+#[derive(Clone)]
+struct AuditSigningKey(Vec<u8>);
+
+impl AuditSigningKey {
+    fn id(&self) -> String {
+        Sha256::digest(&self.0)
+            .iter()
+            .take(8)
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+
+    fn sign(&self, record_hash: &str) -> String {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.0).expect("HMAC accepts keys of any length");
+        mac.update(record_hash.as_bytes());
+        mac.finalize()
+            .into_bytes()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+}
+
+impl fmt::Debug for AuditSigningKey {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "AuditSigningKey({})", self.id())
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct AuditStore {
     root: PathBuf,
+    signing_key: Option<AuditSigningKey>,
 }
 
 impl AuditStore {
     pub fn from_environment() -> Result<Self> {
-        Ok(Self::new(
-            SnapshotStore::from_environment()?.root().join("audit"),
-        ))
+        let state = SnapshotStore::from_environment()?;
+        let store = Self::new(state.root().join("audit"));
+        let key_path = match std::env::var_os(AUDIT_KEY_FILE_ENV).filter(|value| !value.is_empty())
+        {
+            Some(path) => PathBuf::from(path),
+            None => {
+                let default = state.root().join("audit.key");
+                if !default.exists() {
+                    return Ok(store);
+                }
+                default
+            }
+        };
+        let key = fs::read(&key_path)
+            .with_context(|| format!("failed to read audit signing key {}", key_path.display()))?;
+        store
+            .with_signing_key(key)
+            .with_context(|| format!("invalid audit signing key {}", key_path.display()))
     }
 
     pub fn new(root: impl Into<PathBuf>) -> Self {
-        Self { root: root.into() }
+        Self {
+            root: root.into(),
+            signing_key: None,
+        }
+    }
+
+    pub fn with_signing_key(mut self, key: Vec<u8>) -> Result<Self> {
+        if key.len() < MIN_AUDIT_KEY_BYTES {
+            bail!("audit signing keys must contain at least {MIN_AUDIT_KEY_BYTES} bytes");
+        }
+        self.signing_key = Some(AuditSigningKey(key));
+        Ok(self)
     }
 
     pub fn root(&self) -> &Path {
         &self.root
     }
 
-    pub fn save(&self, record: &MutationAuditRecord) -> Result<PathBuf> {
+    /// Stores a record, linking it to the current chain head on its first save.
+    /// Later saves of the same record keep its position and only reseal it.
+    pub fn save(&self, record: &mut MutationAuditRecord) -> Result<PathBuf> {
         validate_record_id(&record.id)?;
         fs::create_dir_all(&self.root)
             .with_context(|| format!("failed to create {}", self.root.display()))?;
@@ -456,6 +736,18 @@ impl AuditStore {
             use std::os::unix::fs::PermissionsExt;
             fs::set_permissions(&self.root, fs::Permissions::from_mode(0o700))?;
         }
+        if record.chain.is_none() {
+            let head = self.chain_head()?;
+            record.chain = Some(AuditChainLink {
+                sequence: head.as_ref().map_or(1, |head| head.sequence + 1),
+                previous_hash: head.map(|head| head.record_hash),
+                record_hash: String::new(),
+                signing_key_id: None,
+                signature: None,
+                head_recorded: true,
+            });
+        }
+        self.seal(record)?;
         let destination = self.root.join(format!("{}.json", record.id));
         let temporary = self.root.join(format!(
             ".{}.{}.{}.tmp",
@@ -480,37 +772,1002 @@ impl AuditStore {
         writer.get_ref().sync_all()?;
         fs::rename(&temporary, &destination)
             .with_context(|| format!("failed to store audit record {}", destination.display()))?;
+        let link = record.chain.as_ref().expect("saved records are linked");
+        if self
+            .load_marker(&self.head_path(), "audit chain head")
+            .ok()
+            .flatten()
+            .is_none_or(|head| link.sequence >= head.sequence)
+        {
+            self.save_marker(&self.head_path(), record, link)?;
+        }
         Ok(destination)
     }
 
     pub fn history(&self) -> Result<Vec<MutationAuditRecord>> {
+        let mut records = self
+            .read_records()?
+            .into_iter()
+            .map(|(_, record)| record)
+            .collect::<Result<Vec<_>>>()?;
+        records.sort_by(|left: &MutationAuditRecord, right| right.id.cmp(&left.id));
+        Ok(records)
+    }
+
+    /// Reads every record from the archive bundles, failing on a corrupt one.
+    pub fn archived_history(&self) -> Result<Vec<MutationAuditRecord>> {
+        self.read_archived_records()?
+            .into_iter()
+            .map(|(_, record)| record)
+            .collect()
+    }
+
+    /// Loads one record by ID, or `None` when no live record has that ID.
+    pub fn load(&self, id: &str) -> Result<Option<MutationAuditRecord>> {
+        validate_record_id(id)?;
+        self.read_records_where(|candidate| candidate == id)?
+            .into_iter()
+            .next()
+            .map(|(_, record)| record)
+            .transpose()
+    }
+
+    /// Returns matching records newest first. Time bounds are checked against
+    /// the record ID before a file is parsed.
+    pub fn query(
+        &self,
+        query: &AuditQuery,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Result<AuditPage> {
+        let mut records = Vec::new();
+        for (_, record) in self.read_records_where(|id| query.matches_time(id))? {
+            let record = record?;
+            if query.matches(&record) {
+                records.push(record);
+            }
+        }
+        records.sort_by(|left: &MutationAuditRecord, right| right.id.cmp(&left.id));
+        let total = records.len();
+        let records = records
+            .into_iter()
+            .skip(offset)
+            .take(limit.unwrap_or(usize::MAX))
+            .collect::<Vec<_>>();
+        let end = offset.saturating_add(records.len());
+        Ok(AuditPage {
+            total,
+            offset,
+            limit,
+            next_offset: (end < total).then_some(end),
+            records,
+        })
+    }
+
+    /// Walks the hash chain in sequence order, including archived records, and
+    /// reports the first record that is missing, out of order, modified, or
+    /// carries an invalid signature.
+    pub fn verify(&self) -> Result<AuditVerification> {
+        let mut verification = AuditVerification {
+            verified: true,
+            records: 0,
+            archived: 0,
+            chained: 0,
+            unchained: 0,
+            signed: 0,
+            signatures_checked: self.signing_key.is_some(),
+            pruned_through: None,
+            head: None,
+            first_broken_link: None,
+        };
+        let mut chained = Vec::new();
+        let live = self.read_records()?;
+        let archived = self.read_archived_records()?;
+        verification.archived = archived.len();
+        for (path, parsed) in live.into_iter().chain(archived) {
+            verification.records += 1;
+            let record = match parsed {
+                Ok(record) => record,
+                Err(error) => {
+                    verification
+                        .first_broken_link
+                        .get_or_insert(AuditBrokenLink {
+                            problem: AuditChainProblem::Unreadable,
+                            sequence: None,
+                            record_id: path
+                                .file_stem()
+                                .and_then(|value| value.to_str())
+                                .map(str::to_owned),
+                            message: format!("{error:#}"),
+                        });
+                    continue;
+                }
+            };
+            if record.chain.is_some() {
+                chained.push(record);
+            } else {
+                verification.unchained += 1;
+            }
+        }
+        chained.sort_by_key(|record| record.chain.as_ref().map(|link| link.sequence));
+        // An interrupted prune can leave a record both archived and live.
+        chained.dedup_by(|left, right| left == right);
+        verification.chained = chained.len();
+        verification.signed = chained
+            .iter()
+            .filter(|record| {
+                record
+                    .chain
+                    .as_ref()
+                    .is_some_and(|link| link.signature.is_some())
+            })
+            .count();
+
+        let anchor = match self.load_anchor() {
+            Ok(anchor) => anchor,
+            Err(error) => {
+                verification
+                    .first_broken_link
+                    .get_or_insert(AuditBrokenLink {
+                        problem: AuditChainProblem::Unreadable,
+                        sequence: None,
+                        record_id: None,
+                        message: format!("{error:#}"),
+                    });
+                None
+            }
+        };
+        verification.pruned_through = anchor.as_ref().map(|anchor| anchor.sequence);
+        let starts_after_anchor = chained
+            .first()
+            .is_none_or(|record| record.chain.as_ref().is_some_and(|link| link.sequence > 1));
+        let mut previous = None;
+        let mut signing_started = false;
+        if let Some(anchor) = anchor.as_ref().filter(|_| starts_after_anchor) {
+            if let Some(problem) = self.check_anchor(anchor, chained.first()) {
+                verification.first_broken_link.get_or_insert(problem);
+            }
+            signing_started = anchor.signature.is_some();
+            previous = Some(AuditChainHead {
+                sequence: anchor.sequence,
+                record_id: anchor.record_id.clone(),
+                record_hash: anchor.record_hash.clone(),
+            });
+        }
+        for record in &chained {
+            let link = record.chain.as_ref().expect("chained records have links");
+            if let Some(problem) = self.check_link(record, link, previous.as_ref(), signing_started)
+            {
+                verification.first_broken_link.get_or_insert(problem);
+                break;
+            }
+            signing_started |= link.signature.is_some();
+            previous = Some(AuditChainHead {
+                sequence: link.sequence,
+                record_id: record.id.clone(),
+                record_hash: link.record_hash.clone(),
+            });
+        }
+        if verification.first_broken_link.is_none() {
+            let head_recorded = chained
+                .iter()
+                .any(|record| record.chain.as_ref().is_some_and(|link| link.head_recorded));
+            match self.load_marker(&self.head_path(), "audit chain head") {
+                Ok(head) => {
+                    verification.first_broken_link = self.check_head(
+                        head.as_ref(),
+                        previous.as_ref(),
+                        head_recorded,
+                        signing_started,
+                    );
+                }
+                Err(error) => {
+                    verification.first_broken_link = Some(AuditBrokenLink {
+                        problem: AuditChainProblem::Unreadable,
+                        sequence: None,
+                        record_id: None,
+                        message: format!("{error:#}"),
+                    });
+                }
+            }
+        }
+        verification.head = previous;
+        verification.verified = verification.first_broken_link.is_none();
+        Ok(verification)
+    }
+
+    /// Removes records from the live audit directory. Chained records must form
+    /// the oldest contiguous part of the chain; the last one becomes the chain
+    /// anchor so later records still verify. With `archive`, the records are
+    /// first written to a compressed bundle that `verify` continues to read.
+    pub fn prune(&self, ids: &[String], archive: bool) -> Result<Option<PathBuf>> {
+        if ids.is_empty() {
+            return Ok(None);
+        }
+        let mut records = Vec::with_capacity(ids.len());
+        for id in ids {
+            validate_record_id(id)?;
+            let path = self.root.join(format!("{id}.json"));
+            let record: MutationAuditRecord =
+                serde_json::from_reader(BufReader::new(fs::File::open(&path)?))
+                    .with_context(|| format!("failed to parse audit record {}", path.display()))?;
+            validate_audit_record(&record, Some(id))?;
+            if record.outcome == AuditOutcome::Pending {
+                bail!("audit record {id} is still pending and cannot be pruned");
+            }
+            records.push(record);
+        }
+        let last_pruned = records
+            .iter()
+            .filter_map(|record| record.chain.as_ref().map(|link| (record, link)))
+            .max_by_key(|(_, link)| link.sequence);
+        if let Some((_, last)) = last_pruned {
+            let remaining_below = self.read_records()?.into_iter().any(|(_, record)| {
+                record.is_ok_and(|record| {
+                    !ids.contains(&record.id)
+                        && record
+                            .chain
+                            .is_some_and(|link| link.sequence < last.sequence)
+                })
+            });
+            if remaining_below {
+                bail!("audit records can only be pruned from the oldest end of the chain");
+            }
+        }
+        let bundle = if archive {
+            Some(write_archive(&self.archive_directory(), "audit", &records)?)
+        } else {
+            None
+        };
+        if let Some((record, link)) = last_pruned {
+            let current = self.load_anchor()?;
+            if current
+                .as_ref()
+                .is_none_or(|anchor| link.sequence > anchor.sequence)
+            {
+                self.save_marker(&self.anchor_path(), record, link)?;
+            }
+        }
+        for id in ids {
+            let path = self.root.join(format!("{id}.json"));
+            fs::remove_file(&path)
+                .with_context(|| format!("failed to remove audit record {}", path.display()))?;
+        }
+        Ok(bundle)
+    }
+
+    pub fn archive_directory(&self) -> PathBuf {
+        self.root.with_file_name("archive")
+    }
+
+    fn anchor_path(&self) -> PathBuf {
+        self.root.with_file_name("audit-anchor.json")
+    }
+
+    fn head_path(&self) -> PathBuf {
+        self.root.with_file_name("audit-head.json")
+    }
+
+    fn load_anchor(&self) -> Result<Option<AuditChainAnchor>> {
+        self.load_marker(&self.anchor_path(), "audit anchor")
+    }
+
+    fn load_marker(&self, path: &Path, label: &str) -> Result<Option<AuditChainAnchor>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let anchor: AuditChainAnchor =
+            serde_json::from_reader(BufReader::new(fs::File::open(path)?))
+                .with_context(|| format!("failed to parse {label} {}", path.display()))?;
+        if anchor.schema_version != AUDIT_SCHEMA_VERSION {
+            bail!(
+                "unsupported {label} schema version {}",
+                anchor.schema_version
+            );
+        }
+        Ok(Some(anchor))
+    }
+
+    fn save_marker(
+        &self,
+        path: &Path,
+        record: &MutationAuditRecord,
+        link: &AuditChainLink,
+    ) -> Result<()> {
+        let mut anchor = AuditChainAnchor {
+            schema_version: AUDIT_SCHEMA_VERSION,
+            sequence: link.sequence,
+            record_id: record.id.clone(),
+            record_hash: link.record_hash.clone(),
+            signing_key_id: self.signing_key.as_ref().map(AuditSigningKey::id),
+            signature: None,
+        };
+        anchor.signature = self
+            .signing_key
+            .as_ref()
+            .map(|key| key.sign(&anchor.signed_material()));
+        let temporary = path.with_extension(format!("{}.tmp", std::process::id()));
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let file = options
+            .open(&temporary)
+            .with_context(|| format!("failed to create {}", temporary.display()))?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut writer, &anchor)?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+        fs::rename(&temporary, path)
+            .with_context(|| format!("failed to store {}", path.display()))?;
+        Ok(())
+    }
+
+    fn check_anchor(
+        &self,
+        anchor: &AuditChainAnchor,
+        first: Option<&MutationAuditRecord>,
+    ) -> Option<AuditBrokenLink> {
+        let key = self.signing_key.as_ref()?;
+        let broken = |problem, message: &str| {
+            Some(AuditBrokenLink {
+                problem,
+                sequence: Some(anchor.sequence),
+                record_id: Some(anchor.record_id.clone()),
+                message: message.to_owned(),
+            })
+        };
+        match &anchor.signature {
+            Some(signature)
+                if anchor.signing_key_id.as_deref() != Some(key.id().as_str())
+                    || !digests_match(signature, &key.sign(&anchor.signed_material())) =>
+            {
+                broken(
+                    AuditChainProblem::InvalidSignature,
+                    "chain anchor signature does not match the configured audit key",
+                )
+            }
+            None if first
+                .and_then(|record| record.chain.as_ref())
+                .is_some_and(|link| link.signature.is_some()) =>
+            {
+                broken(
+                    AuditChainProblem::Unsigned,
+                    "chain anchor is unsigned although the records after it are signed",
+                )
+            }
+            _ => None,
+        }
+    }
+
+    /// Compares the recorded chain head with the last record reached by the
+    /// walk. A head behind the walk is accepted: a crash between writing a
+    /// record and its head leaves exactly that.
+    fn check_head(
+        &self,
+        head: Option<&AuditChainAnchor>,
+        walked: Option<&AuditChainHead>,
+        head_recorded: bool,
+        signing_started: bool,
+    ) -> Option<AuditBrokenLink> {
+        let Some(head) = head else {
+            return head_recorded.then(|| AuditBrokenLink {
+                problem: AuditChainProblem::Truncated,
+                sequence: walked.map(|walked| walked.sequence),
+                record_id: walked.map(|walked| walked.record_id.clone()),
+                message: "chain head is missing although the records expect one".to_owned(),
+            });
+        };
+        let broken = |problem, message: String| {
+            Some(AuditBrokenLink {
+                problem,
+                sequence: Some(head.sequence),
+                record_id: Some(head.record_id.clone()),
+                message,
+            })
+        };
+        if let Some(key) = &self.signing_key {
+            match &head.signature {
+                Some(signature)
+                    if head.signing_key_id.as_deref() != Some(key.id().as_str())
+                        || !digests_match(signature, &key.sign(&head.signed_material())) =>
+                {
+                    return broken(
+                        AuditChainProblem::InvalidSignature,
+                        "chain head signature does not match the configured audit key".to_owned(),
+                    );
+                }
+                None if signing_started => {
+                    return broken(
+                        AuditChainProblem::Unsigned,
+                        "chain head is unsigned although the chain is signed".to_owned(),
+                    );
+                }
+                _ => {}
+            }
+        }
+        let walked_sequence = walked.map_or(0, |walked| walked.sequence);
+        if head.sequence > walked_sequence {
+            return broken(
+                AuditChainProblem::Truncated,
+                format!(
+                    "records {} through {} are missing from the end of the chain",
+                    walked_sequence + 1,
+                    head.sequence
+                ),
+            );
+        }
+        if walked.is_some_and(|walked| {
+            walked.sequence == head.sequence
+                && (walked.record_id != head.record_id || walked.record_hash != head.record_hash)
+        }) {
+            return broken(
+                AuditChainProblem::Modified,
+                "chain head does not match the last record of the chain".to_owned(),
+            );
+        }
+        None
+    }
+
+    fn check_link(
+        &self,
+        record: &MutationAuditRecord,
+        link: &AuditChainLink,
+        previous: Option<&AuditChainHead>,
+        signing_started: bool,
+    ) -> Option<AuditBrokenLink> {
+        let broken = |problem, message: String| {
+            Some(AuditBrokenLink {
+                problem,
+                sequence: Some(link.sequence),
+                record_id: Some(record.id.clone()),
+                message,
+            })
+        };
+        let expected_sequence = previous.map_or(1, |previous| previous.sequence + 1);
+        if link.sequence > expected_sequence {
+            return broken(
+                AuditChainProblem::Missing,
+                format!(
+                    "record(s) {expected_sequence} through {} are missing",
+                    link.sequence - 1
+                ),
+            );
+        }
+        if link.sequence < expected_sequence {
+            return broken(
+                AuditChainProblem::Reordered,
+                format!("sequence {} appears more than once", link.sequence),
+            );
+        }
+        match record_hash(record) {
+            Ok(hash) if hash == link.record_hash => {}
+            Ok(_) => {
+                return broken(
+                    AuditChainProblem::Modified,
+                    "record contents do not match its recorded hash".to_owned(),
+                )
+            }
+            Err(error) => {
+                return broken(
+                    AuditChainProblem::Unreadable,
+                    format!("failed to hash record: {error:#}"),
+                )
+            }
+        }
+        if link.previous_hash.as_deref() != previous.map(|previous| previous.record_hash.as_str()) {
+            return broken(
+                AuditChainProblem::BrokenLink,
+                "previous hash does not match the preceding record".to_owned(),
+            );
+        }
+        if previous
+            .is_some_and(|previous| record_nanos(&record.id) < record_nanos(&previous.record_id))
+        {
+            return broken(
+                AuditChainProblem::Reordered,
+                "record was created before the record it follows".to_owned(),
+            );
+        }
+        if let Some(key) = &self.signing_key {
+            match &link.signature {
+                Some(signature)
+                    if link.signing_key_id.as_deref() != Some(key.id().as_str())
+                        || !digests_match(signature, &key.sign(&link.record_hash)) =>
+                {
+                    return broken(
+                        AuditChainProblem::InvalidSignature,
+                        "signature does not match the configured audit key".to_owned(),
+                    )
+                }
+                None if signing_started => {
+                    return broken(
+                        AuditChainProblem::Unsigned,
+                        "record is unsigned although earlier records are signed".to_owned(),
+                    )
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    fn chain_head(&self) -> Result<Option<AuditChainHead>> {
+        let mut head = self.load_anchor()?.map(|anchor| AuditChainHead {
+            sequence: anchor.sequence,
+            record_id: anchor.record_id,
+            record_hash: anchor.record_hash,
+        });
+        for (path, parsed) in self.read_records()? {
+            let record = parsed
+                .with_context(|| format!("cannot extend audit chain past {}", path.display()))?;
+            if let Some(link) = record.chain {
+                if head
+                    .as_ref()
+                    .is_none_or(|head| link.sequence > head.sequence)
+                {
+                    head = Some(AuditChainHead {
+                        sequence: link.sequence,
+                        record_id: record.id,
+                        record_hash: link.record_hash,
+                    });
+                }
+            }
+        }
+        Ok(head)
+    }
+
+    fn read_archived_records(&self) -> Result<Vec<(PathBuf, Result<MutationAuditRecord>)>> {
+        let mut records = Vec::new();
+        for path in archive_bundles(&self.archive_directory(), "audit")? {
+            for (index, parsed) in read_archive::<MutationAuditRecord>(&path)?
+                .into_iter()
+                .enumerate()
+            {
+                let parsed = parsed.and_then(|record| {
+                    validate_audit_record(&record, None)?;
+                    Ok(record)
+                });
+                records.push((
+                    path.join(format!("line-{}", index + 1)),
+                    parsed.with_context(|| {
+                        format!(
+                            "failed to parse archived audit record in {}",
+                            path.display()
+                        )
+                    }),
+                ));
+            }
+        }
+        Ok(records)
+    }
+
+    fn seal(&self, record: &mut MutationAuditRecord) -> Result<()> {
+        let key_id = self.signing_key.as_ref().map(AuditSigningKey::id);
+        if let Some(link) = record.chain.as_mut() {
+            link.signing_key_id = key_id;
+        }
+        let hash = record_hash(record)?;
+        let signature = self.signing_key.as_ref().map(|key| key.sign(&hash));
+        let link = record
+            .chain
+            .as_mut()
+            .expect("records are linked before sealing");
+        link.record_hash = hash;
+        link.signature = signature;
+        Ok(())
+    }
+
+    fn read_records(&self) -> Result<Vec<(PathBuf, Result<MutationAuditRecord>)>> {
+        self.read_records_where(|_| true)
+    }
+
+    fn read_records_where(
+        &self,
+        include: impl Fn(&str) -> bool,
+    ) -> Result<Vec<(PathBuf, Result<MutationAuditRecord>)>> {
         if !self.root.exists() {
             return Ok(Vec::new());
         }
         let mut records = Vec::new();
         for entry in fs::read_dir(&self.root)? {
             let path = entry?.path();
-            if path.extension().is_none_or(|extension| extension != "json") {
+            if path.extension().is_none_or(|extension| extension != "json")
+                || !path
+                    .file_stem()
+                    .and_then(|value| value.to_str())
+                    .is_some_and(&include)
+            {
                 continue;
             }
-            let record: MutationAuditRecord =
-                serde_json::from_reader(BufReader::new(fs::File::open(&path)?))
-                    .with_context(|| format!("failed to parse audit record {}", path.display()))?;
-            let filename_id = path.file_stem().and_then(|value| value.to_str());
-            validate_audit_record(&record, filename_id)?;
-            records.push(record);
+            let parsed = fs::File::open(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|file| {
+                    serde_json::from_reader::<_, MutationAuditRecord>(BufReader::new(file))
+                        .map_err(anyhow::Error::from)
+                })
+                .and_then(|record| {
+                    validate_audit_record(
+                        &record,
+                        path.file_stem().and_then(|value| value.to_str()),
+                    )?;
+                    Ok(record)
+                })
+                .with_context(|| format!("failed to parse audit record {}", path.display()));
+            records.push((path, parsed));
         }
-        records.sort_by(|left: &MutationAuditRecord, right| right.id.cmp(&left.id));
         Ok(records)
     }
 }
 
+/// Hashes the canonical JSON form of a record with its hash and signature blanked.
+fn record_hash(record: &MutationAuditRecord) -> Result<String> {
+    let mut material = record.clone();
+    if let Some(link) = material.chain.as_mut() {
+        link.record_hash.clear();
+        link.signature = None;
+    }
+    Ok(Sha256::digest(serde_json::to_vec(&material)?)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+/// Parses an RFC 3339 timestamp, a `YYYY-MM-DD` date at midnight UTC, or a
+/// relative age such as `30m`, `12h`, `7d`, or `2w` measured back from `now`.
+pub fn parse_audit_time(value: &str, now: OffsetDateTime) -> Result<OffsetDateTime> {
+    let value = value.trim();
+    if let Ok(timestamp) = OffsetDateTime::parse(value, &Rfc3339) {
+        return Ok(timestamp);
+    }
+    let mut parts = value.splitn(3, '-');
+    if let (Some(year), Some(month), Some(day)) = (parts.next(), parts.next(), parts.next()) {
+        if let (Ok(year), Ok(month), Ok(day)) =
+            (year.parse(), month.parse::<u8>(), day.parse::<u8>())
+        {
+            let month = Month::try_from(month).map_err(|_| anyhow!("invalid date '{value}'"))?;
+            return Ok(Date::from_calendar_date(year, month, day)
+                .map_err(|_| anyhow!("invalid date '{value}'"))?
+                .midnight()
+                .assume_utc());
+        }
+    }
+    let unit_start = value
+        .find(|character: char| !character.is_ascii_digit())
+        .unwrap_or(value.len());
+    let amount = value[..unit_start].parse::<i64>().ok();
+    let unit_seconds = match (amount, &value[unit_start..]) {
+        (Some(_), "m") => 60,
+        (Some(_), "h") => 60 * 60,
+        (Some(_), "d") => 24 * 60 * 60,
+        (Some(_), "w") => 7 * 24 * 60 * 60,
+        _ => bail!("invalid time '{value}'; use RFC 3339, YYYY-MM-DD, or an age such as 7d"),
+    };
+    amount
+        .and_then(|amount| amount.checked_mul(unit_seconds))
+        .and_then(|seconds| now.checked_sub(Duration::seconds(seconds)))
+        .ok_or_else(|| anyhow!("time '{value}' is too far in the past"))
+}
+
+/// Writes records in a log-pipeline format. Records keep the given order.
+pub fn write_audit_export<W: Write>(
+    mut writer: W,
+    records: &[MutationAuditRecord],
+    format: AuditExportFormat,
+) -> Result<()> {
+    if format == AuditExportFormat::Csv {
+        writeln!(
+            writer,
+            "id,timestamp,requester,channel,operation,outcome,targets,plan_digest,safety_snapshot_id,applied,failed,error"
+        )?;
+    }
+    for record in records {
+        match format {
+            AuditExportFormat::Jsonl => {
+                serde_json::to_writer(&mut writer, record)?;
+                writeln!(writer)?;
+            }
+            AuditExportFormat::Csv => {
+                let fields = [
+                    record.id.clone(),
+                    record.timestamp.clone(),
+                    record.requester.clone(),
+                    serialized_name(&record.channel),
+                    serialized_name(&record.operation),
+                    serialized_name(&record.outcome),
+                    changed_targets(record).join("; "),
+                    record.plan_digest.clone(),
+                    record.safety_snapshot_id.clone().unwrap_or_default(),
+                    record
+                        .result
+                        .as_ref()
+                        .map(|result| result.applied.to_string())
+                        .unwrap_or_default(),
+                    record
+                        .result
+                        .as_ref()
+                        .map(|result| result.failed.to_string())
+                        .unwrap_or_default(),
+                    record.error.clone().unwrap_or_default(),
+                ];
+                let row = fields
+                    .iter()
+                    .map(|field| csv_field(field))
+                    .collect::<Vec<_>>()
+                    .join(",");
+                writeln!(writer, "{row}")?;
+            }
+            AuditExportFormat::Syslog => writeln!(writer, "{}", syslog_line(record))?,
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn changed_targets(record: &MutationAuditRecord) -> Vec<String> {
+    record
+        .plan
+        .entries
+        .iter()
+        .filter(|entry| entry.action == PlanAction::Change)
+        .map(|entry| {
+            format!(
+                "{}={}",
+                entry.association(),
+                entry
+                    .target
+                    .as_ref()
+                    .map_or("-", |target| target.bundle_id.as_str())
+            )
+        })
+        .collect()
+}
+
+pub(crate) fn serialized_name<T: Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_owned))
+        .unwrap_or_default()
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+/// Formats one record as RFC 5424 with facility `log audit` (13).
+fn syslog_line(record: &MutationAuditRecord) -> String {
+    let severity = match record.outcome {
+        AuditOutcome::Succeeded => 5,
+        AuditOutcome::Pending => 6,
+        AuditOutcome::PartialFailure | AuditOutcome::FailedBeforeMutation => 3,
+        AuditOutcome::RolledBack | AuditOutcome::Denied => 4,
+    };
+    let parameters = [
+        ("id", record.id.clone()),
+        ("requester", record.requester.clone()),
+        ("channel", serialized_name(&record.channel)),
+        ("operation", serialized_name(&record.operation)),
+        ("outcome", serialized_name(&record.outcome)),
+        ("plan_digest", record.plan_digest.clone()),
+    ]
+    .iter()
+    .map(|(name, value)| format!("{name}=\"{}\"", syslog_parameter(value)))
+    .collect::<Vec<_>>()
+    .join(" ");
+    let targets = changed_targets(record);
+    let mut message = format!(
+        "{} by {} via {} {}",
+        serialized_name(&record.operation),
+        record.requester,
+        serialized_name(&record.channel),
+        serialized_name(&record.outcome)
+    );
+    if !targets.is_empty() {
+        message.push_str(": ");
+        message.push_str(&targets.join(", "));
+    }
+    if let Some(error) = &record.error {
+        message.push_str(&format!(" ({error})"));
+    }
+    format!(
+        "<{}>1 {} - dutis - audit [dutis@32473 {parameters}] {}",
+        13 * 8 + severity,
+        record.timestamp,
+        message.replace(['\n', '\r'], " ")
+    )
+}
+
+fn syslog_parameter(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace(']', "\\]")
+}
+
+/// The creation time encoded in a record or snapshot ID, in Unix nanoseconds.
+pub(crate) fn record_nanos(id: &str) -> i128 {
+    id.split('-')
+        .next()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_default()
+}
+
+fn digests_match(left: &str, right: &str) -> bool {
+    left.len() == right.len()
+        && left
+            .as_bytes()
+            .iter()
+            .zip(right.as_bytes())
+            .fold(0_u8, |difference, (left, right)| {
+                difference | (left ^ right)
+            })
+            == 0
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct GovernedMutation {
+    pub audit_id: String,
+    pub safety_snapshot_id: Option<String>,
+    #[serde(flatten)]
+    pub report: ApplyReport,
+    pub verification: VerificationSummary,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compensation: Option<ApplyReport>,
+}
+
+/// An entry changed by an audited mutation that now has a different handler.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct UndoConflict {
+    pub kind: AssociationKind,
+    pub role: HandlerRole,
+    pub extension: String,
+    pub applied_bundle_id: String,
+    pub current_bundle_id: Option<String>,
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Serialize)]
-pub struct GovernedMutation {
+pub struct UndoPlan {
     pub audit_id: String,
-    pub safety_snapshot_id: Option<String>,
-    #[serde(flatten)]
-    pub report: ApplyReport,
+    pub snapshot_id: String,
+    pub plan: AssociationPlan,
+    pub conflicts: Vec<UndoConflict>,
+}
+
+impl MutationAuditRecord {
+    /// Entries the mutation actually changed, according to its apply report,
+    /// excluding any that an atomic compensation already reverted.
+    pub fn applied_entries(&self) -> Vec<&ApplyEntryResult> {
+        let reverted = self
+            .compensation
+            .iter()
+            .flat_map(|report| &report.results)
+            .filter(|result| result.status == ApplyStatus::Applied)
+            .map(|result| (result.kind, result.role, result.extension.as_str()))
+            .collect::<BTreeSet<_>>();
+        self.result
+            .iter()
+            .flat_map(|report| &report.results)
+            .filter(|result| result.status == ApplyStatus::Applied)
+            .filter(|result| {
+                !reverted.contains(&(result.kind, result.role, result.extension.as_str()))
+            })
+            .collect()
+    }
+
+    pub fn is_undoable(&self) -> bool {
+        self.safety_snapshot_id.is_some() && !self.applied_entries().is_empty()
+    }
+}
+
+/// The newest successful forward mutation (set, apply, or remediation) that
+/// changed associations and has not been undone yet. `history` is newest
+/// first, as returned by `AuditStore::history`.
+pub fn latest_undoable(history: &[MutationAuditRecord]) -> Option<&MutationAuditRecord> {
+    let undone = history
+        .iter()
+        .filter(|record| record.outcome == AuditOutcome::Succeeded)
+        .filter_map(|record| record.undoes.as_deref())
+        .collect::<BTreeSet<_>>();
+    history.iter().find(|record| {
+        record.outcome == AuditOutcome::Succeeded
+            && record.operation != MutationOperation::Rollback
+            && record.undoes.is_none()
+            && !undone.contains(record.id.as_str())
+            && record.is_undoable()
+    })
+}
+
+/// Builds a rollback plan from a mutation's safety snapshot restricted to the
+/// entries that mutation applied. Entries whose current handler is no longer
+/// the one the mutation set are reported as conflicts.
+pub fn build_undo_plan<F>(
+    record: &MutationAuditRecord,
+    snapshot: &Snapshot,
+    applications: &[Application],
+    mut query_default: F,
+) -> Result<UndoPlan>
+where
+    F: FnMut(&AssociationTarget) -> Result<Option<DefaultApplication>>,
+{
+    if record.safety_snapshot_id.as_deref() != Some(snapshot.id.as_str()) {
+        bail!(
+            "snapshot {} is not the safety snapshot of audit record {}",
+            snapshot.id,
+            record.id
+        );
+    }
+    let applied = record
+        .applied_entries()
+        .into_iter()
+        .map(|result| {
+            (
+                (result.kind, result.role, result.extension.clone()),
+                result.bundle_id.clone(),
+            )
+        })
+        .collect::<BTreeMap<_, _>>();
+    if applied.is_empty() {
+        bail!("audit record {} changed no associations", record.id);
+    }
+    let mut restricted = snapshot.clone();
+    restricted.associations.retain(|association| {
+        applied.contains_key(&(
+            association.kind,
+            association.role,
+            association.extension.clone(),
+        ))
+    });
+    if restricted.associations.len() != applied.len() {
+        bail!(
+            "safety snapshot {} does not record every association changed by audit record {}",
+            snapshot.id,
+            record.id
+        );
+    }
+    let plan = build_rollback_plan(
+        &restricted,
+        &RollbackSelection::default(),
+        applications,
+        &mut query_default,
+    )?;
+    let conflicts = plan
+        .entries
+        .iter()
+        .filter_map(|entry| {
+            let applied_bundle_id = applied
+                .get(&(entry.kind, entry.role, entry.extension.clone()))
+                .cloned()
+                .flatten()
+                .unwrap_or_default();
+            let current_bundle_id = entry
+                .current
+                .as_ref()
+                .map(|current| current.bundle_id.clone());
+            (current_bundle_id.as_deref() != Some(applied_bundle_id.as_str())).then(|| {
+                UndoConflict {
+                    kind: entry.kind,
+                    role: entry.role,
+                    extension: entry.extension.clone(),
+                    applied_bundle_id,
+                    current_bundle_id,
+                }
+            })
+        })
+        .collect();
+    Ok(UndoPlan {
+        audit_id: record.id.clone(),
+        snapshot_id: snapshot.id.clone(),
+        plan,
+        conflicts,
+    })
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -518,6 +1775,8 @@ pub enum GovernanceErrorKind {
     PolicyDenied,
     AuditFailed,
     SnapshotFailed,
+    /// Another process held the state directory lock until the timeout.
+    Locked,
 }
 
 #[derive(Debug)]
@@ -550,14 +1809,19 @@ impl fmt::Display for GovernanceError {
 
 impl std::error::Error for GovernanceError {}
 
-pub fn execute_governed_plan<F>(
+/// Applies `plan` under policy, audit, and a safety snapshot. `apply` only
+/// needs to request each change; every applied entry is then read back with
+/// `query_default` in a verification pass timed by the policy.
+pub fn execute_governed_plan<F, Q>(
     plan: &AssociationPlan,
     reason: SnapshotReason,
     request: &MutationRequest,
     apply: F,
+    query_default: Q,
 ) -> std::result::Result<GovernedMutation, GovernanceError>
 where
     F: FnMut(&AssociationTarget, &str) -> Result<()>,
+    Q: FnMut(&AssociationTarget) -> Result<Option<DefaultApplication>>,
 {
     let policy = LoadedPolicy::from_environment().map_err(|error| GovernanceError {
         kind: GovernanceErrorKind::PolicyDenied,
@@ -585,32 +1849,59 @@ where
         reason,
         request,
         apply,
+        query_default,
     )
 }
 
-fn execute_governed_plan_with<F>(
+#[allow(clippy::too_many_arguments)]
+fn execute_governed_plan_with<F, Q>(
     loaded_policy: &LoadedPolicy,
     audit_store: &AuditStore,
     snapshot_store: &SnapshotStore,
     plan: &AssociationPlan,
     reason: SnapshotReason,
     request: &MutationRequest,
-    apply: F,
+    mut apply: F,
+    mut query_default: Q,
 ) -> std::result::Result<GovernedMutation, GovernanceError>
 where
     F: FnMut(&AssociationTarget, &str) -> Result<()>,
+    Q: FnMut(&AssociationTarget) -> Result<Option<DefaultApplication>>,
 {
+    // Held from authorization until the final audit record is stored, so
+    // concurrent mutations cannot interleave snapshots, changes, or records.
+    let _lock = lock_timeout_from_environment()
+        .map_err(LockError::Failed)
+        .and_then(|timeout| {
+            StateLock::acquire(
+                snapshot_store.root(),
+                &LockHolder::current(
+                    request.channel,
+                    &serialized_name(&request.operation),
+                    &request.requester,
+                ),
+                timeout,
+            )
+        })
+        .map_err(|error| GovernanceError {
+            kind: GovernanceErrorKind::Locked,
+            message: format!("{error}; no changes were made"),
+            audit_id: None,
+            violations: Vec::new(),
+        })?;
     let assessment = loaded_policy.policy.authorize(plan, request);
     let mut record = new_audit_record(loaded_policy, plan, request);
     if !assessment.allowed {
         record.outcome = AuditOutcome::Denied;
         record.error = Some(assessment.violations.join("; "));
-        audit_store.save(&record).map_err(|error| GovernanceError {
-            kind: GovernanceErrorKind::AuditFailed,
-            message: format!("policy denied mutation and audit storage failed: {error:#}"),
-            audit_id: Some(record.id.clone()),
-            violations: assessment.violations.clone(),
-        })?;
+        audit_store
+            .save(&mut record)
+            .map_err(|error| GovernanceError {
+                kind: GovernanceErrorKind::AuditFailed,
+                message: format!("policy denied mutation and audit storage failed: {error:#}"),
+                audit_id: Some(record.id.clone()),
+                violations: assessment.violations.clone(),
+            })?;
         return Err(GovernanceError {
             kind: GovernanceErrorKind::PolicyDenied,
             message: format!(
@@ -622,19 +1913,29 @@ where
         });
     }
 
-    audit_store.save(&record).map_err(|error| GovernanceError {
-        kind: GovernanceErrorKind::AuditFailed,
-        message: format!("failed to create pending audit record; no changes were made: {error:#}"),
-        audit_id: Some(record.id.clone()),
-        violations: Vec::new(),
-    })?;
+    audit_store
+        .save(&mut record)
+        .map_err(|error| GovernanceError {
+            kind: GovernanceErrorKind::AuditFailed,
+            message: format!(
+                "failed to create pending audit record; no changes were made: {error:#}"
+            ),
+            audit_id: Some(record.id.clone()),
+            violations: Vec::new(),
+        })?;
 
-    let protected = match apply_plan_with_snapshot(snapshot_store, plan, reason, apply) {
+    let protected = match apply_plan_with_snapshot(
+        snapshot_store,
+        plan,
+        reason,
+        &mut apply,
+        &mut query_default,
+    ) {
         Ok(protected) => protected,
         Err(error) => {
             record.outcome = AuditOutcome::FailedBeforeMutation;
             record.error = Some(format!("{error:#}"));
-            let _ = audit_store.save(&record);
+            let _ = audit_store.save(&mut record);
             return Err(GovernanceError {
                 kind: GovernanceErrorKind::SnapshotFailed,
                 message: format!(
@@ -645,24 +1946,51 @@ where
             });
         }
     };
+    let mut report = protected.report;
+    // Compensation reverts every entry `duti` changed, including entries that
+    // verification later marks as failed.
+    let attempted = report.clone();
+    let verification_policy = &loaded_policy.policy.verification;
+    let entries = verify_applied(
+        &mut report,
+        verification_policy,
+        &mut query_default,
+        std::thread::sleep,
+    );
     let verification = VerificationSummary {
-        succeeded: protected.report.failed == 0,
-        applied: protected.report.applied,
-        skipped: protected.report.skipped,
-        failed: protected.report.failed,
+        succeeded: report.succeeded(),
+        applied: report.applied,
+        skipped: report.skipped,
+        failed: report.failed,
+        entries,
     };
-    record.outcome = if verification.succeeded {
-        AuditOutcome::Succeeded
-    } else {
-        AuditOutcome::PartialFailure
+    let atomic = request.atomic || loaded_policy.policy.atomic_apply;
+    let compensation = match &protected.safety_snapshot {
+        Some(snapshot) if atomic && !report.succeeded() && attempted.applied > 0 => {
+            let mut compensation = revert_applied_entries(snapshot, &attempted, &mut apply);
+            verify_applied(
+                &mut compensation,
+                verification_policy,
+                &mut query_default,
+                std::thread::sleep,
+            );
+            Some(compensation)
+        }
+        _ => None,
     };
+    record.outcome = match &compensation {
+        _ if verification.succeeded => AuditOutcome::Succeeded,
+        Some(compensation) if compensation.failed == 0 => AuditOutcome::RolledBack,
+        _ => AuditOutcome::PartialFailure,
+    };
+    record.compensation = compensation.clone();
     record.safety_snapshot_id = protected
         .safety_snapshot
         .as_ref()
         .map(|snapshot| snapshot.id.clone());
-    record.result = Some(protected.report.clone());
-    record.verification = Some(verification);
-    audit_store.save(&record).map_err(|error| GovernanceError {
+    record.result = Some(report.clone());
+    record.verification = Some(verification.clone());
+    audit_store.save(&mut record).map_err(|error| GovernanceError {
         kind: GovernanceErrorKind::AuditFailed,
         message: format!(
             "mutation completed but final audit record could not be stored; pending record {} remains: {error:#}",
@@ -675,7 +2003,9 @@ where
     Ok(GovernedMutation {
         audit_id: record.id,
         safety_snapshot_id: record.safety_snapshot_id,
-        report: protected.report,
+        report,
+        verification,
+        compensation,
     })
 }
 
@@ -718,7 +2048,10 @@ fn new_audit_record(
         safety_snapshot_id: None,
         result: None,
         verification: None,
+        compensation: None,
+        undoes: request.undoes.clone(),
         error: None,
+        chain: None,
     }
 }
 
@@ -805,6 +2138,7 @@ mod tests {
     use super::*;
     use crate::planner::{assemble_plan, PlanEntry, PlannedApplication};
     use crate::system::DefaultApplication;
+    use std::cell::RefCell;
     use std::time::{SystemTime, UNIX_EPOCH};
 
     fn temp_root(label: &str) -> PathBuf {
@@ -843,7 +2177,51 @@ mod tests {
         .unwrap()
     }
 
-    fn loaded(policy: Policy, root: &Path) -> LoadedPolicy {
+    /// In-memory Launch Services that reports back whatever was last applied.
+    /// Every handler starts on `com.example.Old`, the current state in `plan`.
+    #[derive(Default)]
+    struct FakeLaunchServices {
+        handlers: RefCell<BTreeMap<String, String>>,
+    }
+
+    impl FakeLaunchServices {
+        fn set(&self, target: &AssociationTarget, bundle_id: &str) {
+            self.handlers
+                .borrow_mut()
+                .insert(target.identifier.clone(), bundle_id.to_owned());
+        }
+
+        fn apply(&self) -> impl FnMut(&AssociationTarget, &str) -> Result<()> + '_ {
+            |target, bundle_id| {
+                self.set(target, bundle_id);
+                Ok(())
+            }
+        }
+
+        fn query(
+            &self,
+        ) -> impl FnMut(&AssociationTarget) -> Result<Option<DefaultApplication>> + '_ {
+            |target| {
+                let handlers = self.handlers.borrow();
+                let bundle_id = handlers
+                    .get(&target.identifier)
+                    .map_or("com.example.Old", String::as_str);
+                Ok(Some(DefaultApplication {
+                    kind: target.kind,
+                    role: target.role,
+                    extension: target.identifier.clone(),
+                    name: None,
+                    path: None,
+                    bundle_id: bundle_id.to_owned(),
+                }))
+            }
+        }
+    }
+
+    fn loaded(mut policy: Policy, root: &Path) -> LoadedPolicy {
+        // Tests never wait for Launch Services to settle.
+        policy.verification.settle_delay_ms = 0;
+        policy.verification.retry_delay_ms = 0;
         LoadedPolicy {
             path: root.join("policy.toml"),
             exists: true,
@@ -859,6 +2237,8 @@ mod tests {
             operation: MutationOperation::Apply,
             explicit_approval: true,
             approval_token: token.map(str::to_owned),
+            atomic: false,
+            undoes: None,
         }
     }
 
@@ -880,6 +2260,46 @@ mod tests {
         assert_eq!(policy.protected_associations["pdf"], "com.apple.Preview");
     }
 
+    #[test]
+    fn parses_notification_sinks() {
+        let policy = Policy::parse(
+            r#"
+                version = 1
+
+                [[notification_sinks]]
+                type = "file"
+                path = "/var/log/dutis-drift.jsonl"
+
+                [[notification_sinks]]
+                type = "webhook"
+                url = "https://hooks.example.com/services/secret"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            policy.notification_sinks[1],
+            NotificationSinkConfig::Webhook {
+                url: "https://hooks.example.com/services/secret".to_owned(),
+                timeout_seconds: 10,
+            }
+        );
+        let summary = loaded(policy, Path::new("/tmp")).summary();
+        assert_eq!(
+            summary.notification_sinks,
+            [
+                "file /var/log/dutis-drift.jsonl",
+                "webhook hooks.example.com"
+            ]
+        );
+        for invalid in [
+            "[[notification_sinks]]\ntype = 'command'\ncommand = []\n",
+            "[[notification_sinks]]\ntype = 'pager'\n",
+            "[[notification_sinks]]\ntype = 'syslog'\nfacility = 'local0'\n",
+        ] {
+            assert!(Policy::parse(&format!("version = 1\n{invalid}")).is_err());
+        }
+    }
+
     #[test]
     fn policy_denies_disallowed_targets_before_mutation() {
         let root = temp_root("policy-denial");
@@ -901,6 +2321,7 @@ mod tests {
             SnapshotReason::BeforeApply,
             &request(None),
             |_, _| panic!("policy denial reached mutation"),
+            |_| panic!("policy denial reached verification"),
         );
         assert_eq!(
             result.unwrap_err().kind(),
@@ -977,6 +2398,7 @@ mod tests {
         let policy = Policy::default();
         let audit_store = AuditStore::new(root.join("audit"));
         let snapshot_store = SnapshotStore::new(root.join("state"));
+        let services = FakeLaunchServices::default();
         let result = execute_governed_plan_with(
             &loaded(policy, &root),
             &audit_store,
@@ -984,10 +2406,17 @@ mod tests {
             &plan("md", "com.example.Editor"),
             SnapshotReason::BeforeApply,
             &request(None),
-            |_, _| Ok(()),
+            services.apply(),
+            services.query(),
         )
         .unwrap();
         assert_eq!(result.report.applied, 1);
+        let observed = &result.verification.entries[0];
+        assert!(observed.verified);
+        assert_eq!(
+            observed.observed_bundle_id.as_deref(),
+            Some("com.example.Editor")
+        );
         let records = audit_store.history().unwrap();
         assert_eq!(records.len(), 1);
         assert_eq!(records[0].requester, "test-agent");
@@ -1034,6 +2463,7 @@ mod tests {
             SnapshotReason::BeforeApply,
             &request(None),
             |_, _| bail!("simulated verification failure"),
+            FakeLaunchServices::default().query(),
         )
         .unwrap();
         assert_eq!(result.report.failed, 1);
@@ -1043,6 +2473,103 @@ mod tests {
         fs::remove_dir_all(root).unwrap();
     }
 
+    #[test]
+    fn atomic_policy_reverts_applied_entries_after_a_failure() {
+        let root = temp_root("audit-atomic");
+        let audit_store = AuditStore::new(root.join("audit"));
+        let policy = Policy::parse("version = 1\natomic_apply = true\n").unwrap();
+        assert!(policy.atomic_apply);
+        let mut entries = plan("md", "com.example.Editor").entries;
+        entries.extend(plan("json", "com.example.Editor").entries);
+        let plan = assemble_plan(1, entries).unwrap();
+        let services = FakeLaunchServices::default();
+        let mut calls = Vec::new();
+        let result = execute_governed_plan_with(
+            &loaded(policy, &root),
+            &audit_store,
+            &SnapshotStore::new(root.join("state")),
+            &plan,
+            SnapshotReason::BeforeApply,
+            &request(None),
+            |target, bundle_id| {
+                calls.push(format!("{}={bundle_id}", target.identifier));
+                if target.identifier == "md" {
+                    services.set(target, bundle_id);
+                    Ok(())
+                } else {
+                    bail!("simulated duti failure")
+                }
+            },
+            services.query(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            calls,
+            [
+                "md=com.example.Editor",
+                "json=com.example.Editor",
+                "md=com.example.Old"
+            ]
+        );
+        let compensation = result.compensation.unwrap();
+        assert_eq!((compensation.applied, compensation.failed), (1, 0));
+        let record = audit_store.history().unwrap().pop().unwrap();
+        assert_eq!(record.outcome, AuditOutcome::RolledBack);
+        assert_eq!(record.compensation, Some(compensation));
+        assert!(!record.verification.as_ref().unwrap().succeeded);
+        assert!(!record.is_undoable());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn atomic_compensation_also_reverts_entries_that_failed_verification() {
+        let root = temp_root("audit-atomic-verification");
+        let audit_store = AuditStore::new(root.join("audit"));
+        let mut entries = plan("md", "com.example.Editor").entries;
+        entries.extend(plan("json", "com.example.Editor").entries);
+        let plan = assemble_plan(1, entries).unwrap();
+        let services = FakeLaunchServices::default();
+        let mut calls = Vec::new();
+        let result = execute_governed_plan_with(
+            &loaded(Policy::default(), &root),
+            &audit_store,
+            &SnapshotStore::new(root.join("state")),
+            &plan,
+            SnapshotReason::BeforeApply,
+            &MutationRequest {
+                atomic: true,
+                ..request(None)
+            },
+            |target, bundle_id| {
+                calls.push(format!("{}={bundle_id}", target.identifier));
+                // `duti` succeeds for json, but Launch Services keeps the old handler.
+                if target.identifier == "md" {
+                    services.set(target, bundle_id);
+                }
+                Ok(())
+            },
+            services.query(),
+        )
+        .unwrap();
+
+        assert_eq!(result.report.failed, 1);
+        assert_eq!(
+            calls,
+            [
+                "md=com.example.Editor",
+                "json=com.example.Editor",
+                "md=com.example.Old",
+                "json=com.example.Old"
+            ]
+        );
+        let compensation = result.compensation.unwrap();
+        assert_eq!((compensation.applied, compensation.failed), (2, 0));
+        let record = audit_store.history().unwrap().pop().unwrap();
+        assert_eq!(record.outcome, AuditOutcome::RolledBack);
+        fs::remove_dir_all(root).unwrap();
+    }
+
     #[test]
     fn audit_storage_failure_prevents_mutation() {
         let root = temp_root("audit-failure");
@@ -1055,8 +2582,488 @@ mod tests {
             SnapshotReason::BeforeApply,
             &request(None),
             |_, _| panic!("mutation ran without an audit record"),
+            |_| panic!("verification ran without an audit record"),
         );
         assert_eq!(result.unwrap_err().kind(), GovernanceErrorKind::AuditFailed);
         fs::remove_file(root).unwrap();
     }
+
+    fn governed_records(audit_store: &AuditStore, root: &Path, count: usize) -> Vec<String> {
+        (0..count)
+            .map(|index| {
+                let services = FakeLaunchServices::default();
+                execute_governed_plan_with(
+                    &loaded(Policy::default(), root),
+                    audit_store,
+                    &SnapshotStore::new(root.join("state")),
+                    &plan("md", &format!("com.example.Editor{index}")),
+                    SnapshotReason::BeforeApply,
+                    &request(None),
+                    services.apply(),
+                    services.query(),
+                )
+                .unwrap()
+                .audit_id
+            })
+            .collect()
+    }
+
+    #[test]
+    fn undo_restores_only_applied_entries_and_reports_later_changes() {
+        let root = temp_root("undo");
+        let audit_store = AuditStore::new(root.join("audit"));
+        let ids = governed_records(&audit_store, &root, 1);
+        let record = audit_store.load(&ids[0]).unwrap().unwrap();
+        assert!(record.is_undoable());
+        assert!(audit_store.load("1-missing").unwrap().is_none());
+        let snapshot = SnapshotStore::new(root.join("state"))
+            .load(record.safety_snapshot_id.as_deref().unwrap())
+            .unwrap();
+        let applications = [Application {
+            name: "Old".to_owned(),
+            path: PathBuf::from("/Applications/Old.app"),
+            bundle_id: Some("com.example.Old".to_owned()),
+            version: None,
+            extensions: vec!["md".to_owned()],
+            utis: Vec::new(),
+            mime_types: Vec::new(),
+            url_schemes: Vec::new(),
+        }];
+        let current = |bundle_id: &'static str| {
+            move |target: &AssociationTarget| {
+                Ok(Some(DefaultApplication {
+                    kind: target.kind,
+                    role: target.role,
+                    extension: target.identifier.clone(),
+                    name: None,
+                    path: None,
+                    bundle_id: bundle_id.to_owned(),
+                }))
+            }
+        };
+
+        let undo = build_undo_plan(
+            &record,
+            &snapshot,
+            &applications,
+            current("com.example.Editor0"),
+        )
+        .unwrap();
+        assert!(undo.conflicts.is_empty());
+        assert_eq!(undo.plan.summary.changes, 1);
+        assert_eq!(undo.plan.entries[0].selector, "com.example.Old");
+
+        let undo = build_undo_plan(
+            &record,
+            &snapshot,
+            &applications,
+            current("com.example.Other"),
+        )
+        .unwrap();
+        assert_eq!(undo.conflicts.len(), 1);
+        assert_eq!(undo.conflicts[0].applied_bundle_id, "com.example.Editor0");
+        assert_eq!(
+            undo.conflicts[0].current_bundle_id.as_deref(),
+            Some("com.example.Other")
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn undo_targets_the_latest_successful_mutation_that_was_not_undone() {
+        let root = temp_root("undo-twice");
+        let audit_store = AuditStore::new(root.join("audit"));
+        let ids = governed_records(&audit_store, &root, 2);
+        let undo_latest = || {
+            let history = audit_store.history().unwrap();
+            let target = latest_undoable(&history)?.id.clone();
+            let services = FakeLaunchServices::default();
+            execute_governed_plan_with(
+                &loaded(Policy::default(), &root),
+                &audit_store,
+                &SnapshotStore::new(root.join("state")),
+                &plan("md", "com.example.Old"),
+                SnapshotReason::BeforeRollback,
+                &MutationRequest {
+                    operation: MutationOperation::Rollback,
+                    undoes: Some(target.clone()),
+                    ..request(None)
+                },
+                services.apply(),
+                services.query(),
+            )
+            .unwrap();
+            Some(target)
+        };
+
+        // Each undo moves on to the previous mutation instead of undoing the
+        // undo, and stops once nothing is left.
+        assert_eq!(undo_latest().as_ref(), Some(&ids[1]));
+        assert_eq!(undo_latest().as_ref(), Some(&ids[0]));
+        assert_eq!(undo_latest(), None);
+
+        let mut partial = audit_store.load(&ids[0]).unwrap().unwrap();
+        partial.outcome = AuditOutcome::PartialFailure;
+        assert!(latest_undoable(&[partial]).is_none());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn audit_records_form_a_verifiable_hash_chain() {
+        let root = temp_root("audit-chain");
+        let audit_store = AuditStore::new(root.join("audit"));
+        let ids = governed_records(&audit_store, &root, 3);
+        let records = audit_store.history().unwrap();
+        let newest = records[0].chain.as_ref().unwrap();
+        let middle = records[1].chain.as_ref().unwrap();
+        assert_eq!(records[0].id, ids[2]);
+        assert_eq!(newest.sequence, 3);
+        assert_eq!(newest.previous_hash.as_ref(), Some(&middle.record_hash));
+        assert!(records[2].chain.as_ref().unwrap().previous_hash.is_none());
+
+        let verification = audit_store.verify().unwrap();
+        assert!(verification.verified);
+        assert_eq!(verification.chained, 3);
+        assert_eq!(verification.head.unwrap().record_id, ids[2]);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn audit_verify_reports_modified_and_missing_records() {
+        let root = temp_root("audit-tamper");
+        let audit_store = AuditStore::new(root.join("audit"));
+        let ids = governed_records(&audit_store, &root, 3);
+        let path = audit_store.root().join(format!("{}.json", ids[1]));
+        let original = fs::read_to_string(&path).unwrap();
+        fs::write(&path, original.replace("test-agent", "someone-else")).unwrap();
+        let broken = audit_store.verify().unwrap().first_broken_link.unwrap();
+        assert_eq!(broken.problem, AuditChainProblem::Modified);
+        assert_eq!(broken.record_id.as_deref(), Some(ids[1].as_str()));
+
+        fs::remove_file(&path).unwrap();
+        let verification = audit_store.verify().unwrap();
+        assert!(!verification.verified);
+        let broken = verification.first_broken_link.unwrap();
+        assert_eq!(broken.problem, AuditChainProblem::Missing);
+        assert_eq!(broken.sequence, Some(3));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn signed_audit_records_require_the_matching_key() {
+        let root = temp_root("audit-signed");
+        let audit_store = AuditStore::new(root.join("audit"))
+            .with_signing_key(vec![7; 32])
+            .unwrap();
+        governed_records(&audit_store, &root, 2);
+        let verification = audit_store.verify().unwrap();
+        assert!(verification.verified);
+        assert!(verification.signatures_checked);
+        assert_eq!(verification.signed, 2);
+
+        let other_key = AuditStore::new(root.join("audit"))
+            .with_signing_key(vec![8; 32])
+            .unwrap();
+        let broken = other_key.verify().unwrap().first_broken_link.unwrap();
+        assert_eq!(broken.problem, AuditChainProblem::InvalidSignature);
+        assert!(AuditStore::new(root.join("audit"))
+            .with_signing_key(vec![1; 8])
+            .is_err());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn deleting_the_newest_audit_records_fails_verification() {
+        let root = temp_root("audit-truncate");
+        let audit_store = AuditStore::new(root.join("audit"))
+            .with_signing_key(vec![7; 32])
+            .unwrap();
+        let ids = governed_records(&audit_store, &root, 3);
+        fs::remove_file(audit_store.root().join(format!("{}.json", ids[2]))).unwrap();
+        let broken = audit_store.verify().unwrap().first_broken_link.unwrap();
+        assert_eq!(broken.problem, AuditChainProblem::Truncated);
+        assert_eq!(broken.sequence, Some(3));
+
+        // Removing the head along with the records is caught as well.
+        fs::remove_file(root.join("audit-head.json")).unwrap();
+        let broken = audit_store.verify().unwrap().first_broken_link.unwrap();
+        assert_eq!(broken.problem, AuditChainProblem::Truncated);
+        assert_eq!(broken.record_id.as_deref(), Some(ids[1].as_str()));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn audit_query_filters_and_pages_newest_first() {
+        let root = temp_root("audit-query");
+        let audit_store = AuditStore::new(root.join("audit"));
+        let ids = governed_records(&audit_store, &root, 3);
+        let mut denied = new_audit_record(
+            &loaded(Policy::default(), &root),
+            &plan("pdf", "com.example.Reader"),
+            &MutationRequest {
+                requester: "Alice".to_owned(),
+                channel: MutationChannel::Cli,
+                ..request(None)
+            },
+        );
+        denied.outcome = AuditOutcome::Denied;
+        audit_store.save(&mut denied).unwrap();
+
+        let by_target = audit_store
+            .query(
+                &AuditQuery {
+                    target: Some(".PDF".to_owned()),
+                    ..AuditQuery::default()
+                },
+                0,
+                None,
+            )
+            .unwrap();
+        assert_eq!(by_target.total, 1);
+        assert_eq!(by_target.records[0].id, denied.id);
+
+        // Only changed entries count, and a kind keeps a URL scheme apart from
+        // an extension with the same identifier.
+        let mut entries = plan("https", "com.example.Browser").entries;
+        entries.push(PlanEntry {
+            kind: AssociationKind::UrlScheme,
+            current: None,
+            action: PlanAction::Unchanged,
+            ..entries[0].clone()
+        });
+        let mut unchanged_scheme = new_audit_record(
+            &loaded(Policy::default(), &root),
+            &assemble_plan(1, entries).unwrap(),
+            &MutationRequest {
+                channel: MutationChannel::Cli,
+                ..request(None)
+            },
+        );
+        audit_store.save(&mut unchanged_scheme).unwrap();
+        let by_kind = |kind| {
+            audit_store
+                .query(
+                    &AuditQuery {
+                        target: Some("https".to_owned()),
+                        kind,
+                        ..AuditQuery::default()
+                    },
+                    0,
+                    None,
+                )
+                .unwrap()
+                .total
+        };
+        assert_eq!(by_kind(None), 1);
+        assert_eq!(by_kind(Some(AssociationKind::Extension)), 1);
+        assert_eq!(by_kind(Some(AssociationKind::UrlScheme)), 0);
+        let by_requester = AuditQuery {
+            requester: Some("alice".to_owned()),
+            outcome: Some(AuditOutcome::Denied),
+            ..AuditQuery::default()
+        };
+        assert_eq!(audit_store.query(&by_requester, 0, None).unwrap().total, 1);
+
+        let page = audit_store
+            .query(
+                &AuditQuery {
+                    channel: Some(MutationChannel::Mcp),
+                    ..AuditQuery::default()
+                },
+                1,
+                Some(1),
+            )
+            .unwrap();
+        assert_eq!(page.total, 3);
+        assert_eq!(page.records[0].id, ids[1]);
+        assert_eq!(page.next_offset, Some(2));
+
+        let future = AuditQuery {
+            since: Some(OffsetDateTime::now_utc() + Duration::hours(1)),
+            ..AuditQuery::default()
+        };
+        assert!(audit_store
+            .query(&future, 0, None)
+            .unwrap()
+            .records
+            .is_empty());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn audit_export_writes_jsonl_csv_and_syslog() {
+        let mut record = new_audit_record(
+            &loaded(Policy::default(), Path::new("/policy")),
+            &plan("md", "com.example.Editor"),
+            &request(None),
+        );
+        record.outcome = AuditOutcome::PartialFailure;
+        record.error = Some("one, \"two\"".to_owned());
+        let records = [record];
+
+        let mut jsonl = Vec::new();
+        write_audit_export(&mut jsonl, &records, AuditExportFormat::Jsonl).unwrap();
+        let line: MutationAuditRecord = serde_json::from_slice(&jsonl).unwrap();
+        assert_eq!(line.id, records[0].id);
+
+        let mut csv = Vec::new();
+        write_audit_export(&mut csv, &records, AuditExportFormat::Csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let row = csv.lines().nth(1).unwrap();
+        assert!(csv.starts_with("id,timestamp,requester,"));
+        assert!(row.contains(",mcp,apply,partial_failure,.md=com.example.Editor,"));
+        assert!(row.ends_with(",\"one, \"\"two\"\"\""));
+
+        let mut syslog = Vec::new();
+        write_audit_export(&mut syslog, &records, AuditExportFormat::Syslog).unwrap();
+        let syslog = String::from_utf8(syslog).unwrap();
+        assert!(syslog.starts_with("<107>1 "));
+        assert!(syslog.contains("[dutis@32473 id=\""));
+        assert!(
+            syslog.contains("apply by test-agent via mcp partial_failure: .md=com.example.Editor")
+        );
+    }
+
+    #[test]
+    fn audit_times_accept_timestamps_dates_and_ages() {
+        let now = OffsetDateTime::parse("2026-03-10T12:00:00Z", &Rfc3339).unwrap();
+        assert_eq!(
+            parse_audit_time("2026-03-01T08:30:00+01:00", now).unwrap(),
+            OffsetDateTime::parse("2026-03-01T07:30:00Z", &Rfc3339).unwrap()
+        );
+        assert_eq!(
+            parse_audit_time("2026-03-03", now).unwrap(),
+            OffsetDateTime::parse("2026-03-03T00:00:00Z", &Rfc3339).unwrap()
+        );
+        assert_eq!(
+            parse_audit_time("7d", now).unwrap(),
+            OffsetDateTime::parse("2026-03-03T12:00:00Z", &Rfc3339).unwrap()
+        );
+        assert!(parse_audit_time("2026-02-30", now).is_err());
+        assert!(parse_audit_time("soon", now).is_err());
+        assert!(parse_audit_time("9999999d", now).is_err());
+        assert!(parse_audit_time("99999999999999999w", now).is_err());
+    }
+
+    #[test]
+    fn pruned_audit_chain_still_verifies_from_archive_or_anchor() {
+        let root = temp_root("audit-prune");
+        let audit_store = AuditStore::new(root.join("audit"))
+            .with_signing_key(vec![3; 32])
+            .unwrap();
+        let ids = governed_records(&audit_store, &root, 4);
+        assert!(audit_store.prune(&ids[1..2], false).is_err());
+
+        let bundle = audit_store.prune(&ids[..1], true).unwrap().unwrap();
+        assert!(bundle.starts_with(audit_store.archive_directory()));
+        let verification = audit_store.verify().unwrap();
+        assert!(verification.verified);
+        assert_eq!(verification.archived, 1);
+        assert_eq!(verification.chained, 4);
+
+        fs::remove_file(bundle).unwrap();
+        let verification = audit_store.verify().unwrap();
+        assert!(verification.verified);
+        assert_eq!(verification.pruned_through, Some(1));
+
+        audit_store.prune(&ids[1..], false).unwrap();
+        let next = governed_records(&audit_store, &root, 1);
+        let record = audit_store.history().unwrap().pop().unwrap();
+        assert_eq!(record.id, next[0]);
+        assert_eq!(record.chain.unwrap().sequence, 5);
+        assert!(audit_store.verify().unwrap().verified);
+
+        let tampered = AuditStore::new(root.join("audit"))
+            .with_signing_key(vec![4; 32])
+            .unwrap();
+        let broken = tampered.verify().unwrap().first_broken_link.unwrap();
+        assert_eq!(broken.problem, AuditChainProblem::InvalidSignature);
+        assert_eq!(broken.sequence, Some(4));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn garbage_collection_keeps_snapshots_of_archived_audit_records() {
+        use crate::retention::{collect_garbage, RetentionRule};
+
+        let root = temp_root("gc-archived");
+        let audit_store = AuditStore::new(root.join("audit"));
+        let snapshot_store = SnapshotStore::new(root.join("state"));
+        let ids = governed_records(&audit_store, &root, 2);
+        let oldest_snapshot = audit_store
+            .load(&ids[0])
+            .unwrap()
+            .unwrap()
+            .safety_snapshot_id;
+        let keep_newest = RetentionRule {
+            keep_last: Some(1),
+            keep_days: None,
+        };
+        let mut retention = RetentionPolicy {
+            archive: true,
+            snapshots: keep_newest,
+            audit: keep_newest,
+        };
+        let holder = LockHolder::current(MutationChannel::Cli, "gc", "test-agent");
+        let now = OffsetDateTime::now_utc();
+
+        let report = collect_garbage(
+            &snapshot_store,
+            &audit_store,
+            &retention,
+            &holder,
+            now,
+            false,
+        )
+        .unwrap();
+        assert_eq!(report.audit.removed, vec![ids[0].clone()]);
+        assert_eq!(
+            report.snapshots.protected,
+            Vec::from_iter(oldest_snapshot.clone())
+        );
+        assert!(report.snapshots.removed.is_empty());
+
+        // The archived record still protects its snapshot on later runs.
+        let report = collect_garbage(
+            &snapshot_store,
+            &audit_store,
+            &retention,
+            &holder,
+            now,
+            true,
+        )
+        .unwrap();
+        assert_eq!(report.snapshots.protected, Vec::from_iter(oldest_snapshot));
+
+        fs::remove_dir_all(audit_store.archive_directory()).unwrap();
+        retention.archive = false;
+        let report = collect_garbage(
+            &snapshot_store,
+            &audit_store,
+            &retention,
+            &holder,
+            now,
+            false,
+        )
+        .unwrap();
+        assert_eq!(report.snapshots.removed.len(), 1);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn policy_retention_section_is_validated() {
+        let policy = Policy::parse(
+            "version = 1\n[retention]\narchive = true\n[retention.snapshots]\nkeep_last = 20\n[retention.audit]\nkeep_days = 365\n",
+        )
+        .unwrap();
+        assert!(policy.retention.archive);
+        assert_eq!(policy.retention.snapshots.keep_last, Some(20));
+        assert_eq!(policy.retention.audit.keep_days, Some(365));
+        assert!(Policy::parse("version = 1\n[retention.audit]\nkeep_last = 0\n").is_err());
+        assert!(Policy::parse("version = 1\n[retention]\nkeep = 3\n").is_err());
+        assert_eq!(
+            policy_digest(&Policy::default()).unwrap(),
+            policy_digest(&Policy::parse("version = 1\n").unwrap()).unwrap()
+        );
+    }
 }
@@ -1,43 +1,224 @@
+use crate::watch_status::{WatchStatusReport, WatchStatusStore};
 use anyhow::{anyhow, bail, Context, Result};
-use serde::Serialize;
-use std::collections::BTreeMap;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs::{self, OpenOptions};
 use std::io::Write;
+use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use time::OffsetDateTime;
 
 pub const LAUNCH_AGENT_LABEL: &str = "io.github.tsonglew.dutis.watch";
+const MAX_AGENT_NAME_LENGTH: usize = 64;
 const LAUNCH_AGENT_DIR_ENV: &str = "DUTIS_LAUNCH_AGENT_DIR";
+const WEEKDAYS: [&str; 7] = [
+    "sunday",
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+];
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct LaunchAgentSpec {
+    /// Distinguishes several agents; `None` is the original unnamed agent.
+    pub name: Option<String>,
     pub executable: PathBuf,
     pub config: PathBuf,
     pub interval_seconds: u64,
+    /// Recheck on file changes, with the interval as a fallback.
+    pub events: bool,
     pub notify: bool,
+    /// Serve Prometheus metrics from the watcher on this address.
+    pub metrics_listen: Option<SocketAddr>,
     pub remediation_requester: Option<String>,
+    /// Calendar times that start a single check instead of a continuous
+    /// watcher.
+    pub calendar: Vec<CalendarInterval>,
+    /// Paths whose modification starts a single check.
+    pub watch_paths: Vec<PathBuf>,
+    pub process_type: ProcessType,
+    pub low_priority_io: bool,
+    /// Let the watcher rotate its JSON log instead of writing to an
+    /// unbounded `StandardOutPath`.
+    pub log_rotation: Option<LogRotation>,
     pub state_dir: PathBuf,
     pub environment: BTreeMap<String, String>,
 }
 
+impl LaunchAgentSpec {
+    pub fn label(&self) -> String {
+        launch_agent_label(self.name.as_deref())
+    }
+
+    /// Calendar and path triggers start one check per trigger; otherwise the
+    /// agent keeps a continuous watcher alive.
+    pub fn triggered(&self) -> bool {
+        !self.calendar.is_empty() || !self.watch_paths.is_empty()
+    }
+}
+
+/// The launchd label of the agent called `name`. The unnamed agent keeps the
+/// label it has always had.
+pub fn launch_agent_label(name: Option<&str>) -> String {
+    match name {
+        Some(name) => format!("{LAUNCH_AGENT_LABEL}.{name}"),
+        None => LAUNCH_AGENT_LABEL.to_owned(),
+    }
+}
+
+/// Agent names become part of a label and of log file names.
+pub fn validate_agent_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || name.len() > MAX_AGENT_NAME_LENGTH
+        || !name
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || "-_".contains(character))
+    {
+        bail!(
+            "LaunchAgent name '{name}' must be 1 to {MAX_AGENT_NAME_LENGTH} ASCII letters, digits, '-' or '_'"
+        );
+    }
+    Ok(())
+}
+
+/// One `StartCalendarInterval` entry. Omitted fields match every value, as in
+/// launchd.
+#[derive(Debug, Clone, Copy, Eq, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct CalendarInterval {
+    /// 0 is Sunday, 1 Monday, and so on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weekday: Option<u8>,
+    pub hour: u8,
+    pub minute: u8,
+}
+
+impl CalendarInterval {
+    /// Parses `HH:MM` or `DAYS@HH:MM`, where `DAYS` is `daily`, `weekdays`,
+    /// `weekends`, or a comma-separated list such as `mon,wed,fri`.
+    pub fn parse_schedule(value: &str) -> Result<Vec<Self>> {
+        let (days, time) = value.trim().rsplit_once('@').unwrap_or(("daily", value));
+        let (hour, minute) = time
+            .trim()
+            .split_once(':')
+            .and_then(|(hour, minute)| Some((hour.parse::<u8>().ok()?, minute.parse::<u8>().ok()?)))
+            .filter(|(hour, minute)| *hour < 24 && *minute < 60)
+            .ok_or_else(|| anyhow!("invalid schedule time in '{value}'; expected HH:MM"))?;
+        let weekdays = match days.trim().to_ascii_lowercase().as_str() {
+            "daily" => vec![None],
+            "weekdays" => (1..=5).map(Some).collect(),
+            "weekends" => vec![Some(6), Some(0)],
+            list => list
+                .split(',')
+                .map(|day| {
+                    let index = WEEKDAYS
+                        .iter()
+                        .position(|name| day.trim().len() >= 3 && name.starts_with(day.trim()))
+                        .ok_or_else(|| {
+                            anyhow!("unknown day '{}' in schedule '{value}'", day.trim())
+                        })?;
+                    Ok(Some(index as u8))
+                })
+                .collect::<Result<Vec<_>>>()?,
+        };
+        Ok(weekdays
+            .into_iter()
+            .map(|weekday| Self {
+                weekday,
+                hour,
+                minute,
+            })
+            .collect())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Serialize, ValueEnum)]
+pub enum ProcessType {
+    /// Throttled CPU and I/O, the default for a monitor
+    #[default]
+    Background,
+    /// Throttled only while the system is busy
+    Adaptive,
+    Standard,
+    Interactive,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct LogRotation {
+    pub max_bytes: u64,
+    pub keep: u32,
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Serialize)]
 pub struct LaunchAgentStatus {
-    pub label: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub label: String,
     pub path: PathBuf,
     pub installed: bool,
     pub loaded: Option<bool>,
+    /// The heartbeat of the installed watcher, once it has started.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monitor: Option<WatchStatusReport>,
+}
+
+/// An installed agent as recorded in its plist.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct LaunchAgentSummary {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub label: String,
+    pub path: PathBuf,
+    pub config: Option<String>,
+    /// Seconds between checks of a continuous watcher; scheduled agents have
+    /// none.
+    pub interval_seconds: Option<u64>,
+    pub events: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub calendar: Vec<CalendarInterval>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub watch_paths: Vec<String>,
+    pub loaded: Option<bool>,
+    /// Why the plist could not be read; its settings are then unknown.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The plist an install would write, for review or distribution through
+/// device management.
+#[derive(Debug, Clone, Serialize)]
+pub struct RenderedLaunchAgent {
+    pub label: String,
+    pub path: PathBuf,
+    /// The plist contents with launchd's key names.
+    pub plist: serde_json::Value,
+    /// The exact XML document written by `install`.
+    #[serde(skip)]
+    pub xml: String,
 }
 
 #[derive(Serialize)]
 #[serde(rename_all = "PascalCase")]
 struct LaunchAgentPlist {
-    label: &'static str,
+    label: String,
     program_arguments: Vec<String>,
     run_at_load: bool,
     keep_alive: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    start_calendar_interval: Vec<CalendarInterval>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    watch_paths: Vec<String>,
     throttle_interval: u64,
-    process_type: &'static str,
-    standard_out_path: String,
+    process_type: ProcessType,
+    #[serde(rename = "LowPriorityIO", skip_serializing_if = "std::ops::Not::not")]
+    low_priority_io: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    standard_out_path: Option<String>,
     standard_error_path: String,
     environment_variables: BTreeMap<String, String>,
 }
@@ -62,12 +243,41 @@ impl LaunchAgentManager {
         })
     }
 
-    pub fn path(&self) -> PathBuf {
-        self.directory.join(format!("{LAUNCH_AGENT_LABEL}.plist"))
+    pub fn path(&self, name: Option<&str>) -> PathBuf {
+        self.directory
+            .join(format!("{}.plist", launch_agent_label(name)))
     }
 
-    pub fn install(&self, spec: &LaunchAgentSpec) -> Result<LaunchAgentStatus> {
+    /// Validates the spec and renders its plist without writing anything.
+    pub fn render(&self, spec: &LaunchAgentSpec) -> Result<RenderedLaunchAgent> {
         validate_spec(spec)?;
+        // The LaunchAgents directory is usually case-insensitive, so a name
+        // that differs only in case would overwrite another agent's plist
+        // while launchd still treats the labels as distinct.
+        if let Some(name) = &spec.name {
+            if let Some(existing) = self
+                .installed_names()?
+                .into_iter()
+                .flatten()
+                .find(|existing| existing != name && existing.eq_ignore_ascii_case(name))
+            {
+                bail!("LaunchAgent name '{name}' differs only in case from installed agent '{existing}'");
+            }
+        }
+        let plist = plist(spec);
+        let mut xml = Vec::new();
+        plist::to_writer_xml(&mut xml, &plist)?;
+        xml.push(b'\n');
+        Ok(RenderedLaunchAgent {
+            label: spec.label(),
+            path: self.path(spec.name.as_deref()),
+            plist: serde_json::to_value(&plist)?,
+            xml: String::from_utf8(xml).context("rendered plist is not UTF-8")?,
+        })
+    }
+
+    pub fn install(&self, spec: &LaunchAgentSpec) -> Result<LaunchAgentStatus> {
+        let rendered = self.render(spec)?;
         let log_directory = spec.state_dir.join("logs");
         fs::create_dir_all(&log_directory)
             .with_context(|| format!("failed to create {}", log_directory.display()))?;
@@ -79,10 +289,10 @@ impl LaunchAgentManager {
         fs::create_dir_all(&self.directory)
             .with_context(|| format!("failed to create {}", self.directory.display()))?;
 
-        let path = self.path();
-        let temporary = self
-            .directory
-            .join(format!(".{LAUNCH_AGENT_LABEL}.{}.tmp", std::process::id()));
+        let path = self.path(spec.name.as_deref());
+        let temporary =
+            self.directory
+                .join(format!(".{}.{}.tmp", spec.label(), std::process::id()));
         let mut options = OpenOptions::new();
         options.write(true).create_new(true);
         #[cfg(unix)]
@@ -93,8 +303,7 @@ impl LaunchAgentManager {
         let mut file = options
             .open(&temporary)
             .with_context(|| format!("failed to create {}", temporary.display()))?;
-        plist::to_writer_xml(&mut file, &plist(spec))?;
-        file.write_all(b"\n")?;
+        file.write_all(rendered.xml.as_bytes())?;
         file.sync_all()?;
         fs::rename(&temporary, &path)
             .with_context(|| format!("failed to install {}", path.display()))?;
@@ -104,37 +313,177 @@ impl LaunchAgentManager {
             fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
         }
         bootstrap(&path)?;
-        self.status()
+        self.status(spec.name.as_deref())
     }
 
-    pub fn uninstall(&self) -> Result<LaunchAgentStatus> {
-        let path = self.path();
+    pub fn uninstall(&self, name: Option<&str>) -> Result<LaunchAgentStatus> {
+        let path = self.path(name);
         if path.exists() {
             let _ = bootout(&path);
             fs::remove_file(&path)
                 .with_context(|| format!("failed to remove {}", path.display()))?;
         }
-        self.status()
+        self.status(name)
     }
 
-    pub fn status(&self) -> Result<LaunchAgentStatus> {
-        let path = self.path();
+    pub fn status(&self, name: Option<&str>) -> Result<LaunchAgentStatus> {
+        let label = launch_agent_label(name);
+        let path = self.path(name);
         let installed = path.is_file();
         let loaded = if installed {
-            launchctl_loaded()?
+            launchctl_loaded(&label)?
         } else {
             Some(false)
         };
+        let monitor = if installed {
+            InstalledPlist::read(&path)?.monitor_status()
+        } else {
+            None
+        };
         Ok(LaunchAgentStatus {
-            label: LAUNCH_AGENT_LABEL,
+            name: name.map(str::to_owned),
+            label,
             path,
             installed,
             loaded,
+            monitor,
         })
     }
+
+    /// Every installed Dutis agent, the unnamed one first and the rest by
+    /// name. An agent whose plist cannot be read is listed with unknown
+    /// settings and the error.
+    pub fn list(&self) -> Result<Vec<LaunchAgentSummary>> {
+        self.installed_names()?
+            .into_iter()
+            .map(|name| {
+                let label = launch_agent_label(name.as_deref());
+                let path = self.path(name.as_deref());
+                let loaded = launchctl_loaded(&label)?;
+                let installed = match InstalledPlist::read(&path) {
+                    Ok(installed) => installed,
+                    Err(error) => {
+                        return Ok(LaunchAgentSummary {
+                            name,
+                            label,
+                            path,
+                            config: None,
+                            interval_seconds: None,
+                            events: false,
+                            calendar: Vec::new(),
+                            watch_paths: Vec::new(),
+                            loaded,
+                            error: Some(format!("{error:#}")),
+                        })
+                    }
+                };
+                let arguments = &installed.program_arguments;
+                Ok(LaunchAgentSummary {
+                    name,
+                    config: arguments.get(2).cloned(),
+                    interval_seconds: arguments
+                        .windows(2)
+                        .find(|pair| pair[0] == "--interval-seconds")
+                        .and_then(|pair| pair[1].parse().ok()),
+                    events: arguments.iter().any(|argument| argument == "--events"),
+                    calendar: installed.start_calendar_interval,
+                    watch_paths: installed.watch_paths,
+                    loaded,
+                    label,
+                    path,
+                    error: None,
+                })
+            })
+            .collect()
+    }
+
+    /// Names of the installed agents, `None` for the unnamed one first.
+    fn installed_names(&self) -> Result<Vec<Option<String>>> {
+        let entries = match fs::read_dir(&self.directory) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => {
+                return Err(error)
+                    .with_context(|| format!("failed to read {}", self.directory.display()))
+            }
+        };
+        let mut names = Vec::new();
+        for entry in entries {
+            let file_name = entry?.file_name();
+            let Some(label) = file_name
+                .to_str()
+                .and_then(|file_name| file_name.strip_suffix(".plist"))
+            else {
+                continue;
+            };
+            if label == LAUNCH_AGENT_LABEL {
+                names.push(None);
+            } else if let Some(name) = label
+                .strip_prefix(LAUNCH_AGENT_LABEL)
+                .and_then(|rest| rest.strip_prefix('.'))
+                .filter(|name| validate_agent_name(name).is_ok())
+            {
+                names.push(Some(name.to_owned()));
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+}
+
+/// The parts of an installed plist that status and list report.
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct InstalledPlist {
+    #[serde(default)]
+    program_arguments: Vec<String>,
+    #[serde(default)]
+    start_calendar_interval: Vec<CalendarInterval>,
+    #[serde(default)]
+    watch_paths: Vec<String>,
+    #[serde(default)]
+    environment_variables: BTreeMap<String, String>,
+}
+
+impl InstalledPlist {
+    fn read(path: &Path) -> Result<Self> {
+        plist::from_file(path).with_context(|| format!("failed to read {}", path.display()))
+    }
+
+    /// Finds the watcher status for the configuration and state directory
+    /// recorded in the plist. The watcher keys its status by the canonical
+    /// configuration path, so the recorded path is resolved the same way. An
+    /// unreadable status file reports the monitor as failing.
+    ///
+    /// Scheduled and path-triggered agents run `watch --once`, which keeps no
+    /// heartbeat, so they report no monitor rather than the status a former
+    /// continuous watcher of the same configuration left behind.
+    fn monitor_status(&self) -> Option<WatchStatusReport> {
+        if self
+            .program_arguments
+            .iter()
+            .any(|argument| argument == "--once")
+        {
+            return None;
+        }
+        let (Some(config), Some(state_dir)) = (
+            self.program_arguments.get(2),
+            self.environment_variables.get("DUTIS_STATE_DIR"),
+        ) else {
+            return None;
+        };
+        let config = fs::canonicalize(config).unwrap_or_else(|_| PathBuf::from(config));
+        match WatchStatusStore::new(Path::new(state_dir).join("watch")).load(&config) {
+            Ok(status) => status.map(|status| status.report(OffsetDateTime::now_utc())),
+            Err(error) => Some(WatchStatusReport::unreadable(&error)),
+        }
+    }
 }
 
 fn validate_spec(spec: &LaunchAgentSpec) -> Result<()> {
+    if let Some(name) = &spec.name {
+        validate_agent_name(name)?;
+    }
     if !spec.executable.is_absolute() || !spec.executable.is_file() {
         bail!(
             "LaunchAgent executable must be an existing absolute path: {}",
@@ -150,6 +499,21 @@ fn validate_spec(spec: &LaunchAgentSpec) -> Result<()> {
     if spec.interval_seconds < 10 {
         bail!("LaunchAgent interval must be at least 10 seconds");
     }
+    if spec.triggered() && (spec.events || spec.metrics_listen.is_some()) {
+        bail!("calendar and path triggers run single checks; they cannot be combined with events or metrics");
+    }
+    if let Some(path) = spec.watch_paths.iter().find(|path| !path.is_absolute()) {
+        bail!(
+            "LaunchAgent watch paths must be absolute: {}",
+            path.display()
+        );
+    }
+    if spec
+        .log_rotation
+        .is_some_and(|rotation| rotation.max_bytes == 0)
+    {
+        bail!("LaunchAgent log rotation needs a positive size");
+    }
     if spec
         .remediation_requester
         .as_deref()
@@ -166,12 +530,24 @@ fn plist(spec: &LaunchAgentSpec) -> LaunchAgentPlist {
         "watch".to_owned(),
         spec.config.display().to_string(),
         "--json".to_owned(),
-        "--interval-seconds".to_owned(),
-        spec.interval_seconds.to_string(),
     ];
+    if spec.triggered() {
+        arguments.push("--once".to_owned());
+    } else {
+        arguments.extend([
+            "--interval-seconds".to_owned(),
+            spec.interval_seconds.to_string(),
+        ]);
+    }
+    if spec.events {
+        arguments.push("--events".to_owned());
+    }
     if spec.notify {
         arguments.push("--notify".to_owned());
     }
+    if let Some(address) = spec.metrics_listen {
+        arguments.extend(["--metrics-listen".to_owned(), address.to_string()]);
+    }
     if let Some(requester) = &spec.remediation_requester {
         arguments.extend([
             "--remediate".to_owned(),
@@ -180,21 +556,47 @@ fn plist(spec: &LaunchAgentSpec) -> LaunchAgentPlist {
             requester.clone(),
         ]);
     }
+    let log_stem = match &spec.name {
+        Some(name) => format!("watch-{name}"),
+        None => "watch".to_owned(),
+    };
+    let log_path = spec
+        .state_dir
+        .join(format!("logs/{log_stem}.jsonl"))
+        .display()
+        .to_string();
+    if let Some(rotation) = spec.log_rotation {
+        arguments.extend([
+            "--log-file".to_owned(),
+            log_path.clone(),
+            "--log-max-bytes".to_owned(),
+            rotation.max_bytes.to_string(),
+            "--log-keep".to_owned(),
+            rotation.keep.to_string(),
+        ]);
+    }
+    let mut calendar = spec.calendar.clone();
+    calendar.sort();
+    calendar.dedup();
+    let watch_paths = spec
+        .watch_paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<BTreeSet<_>>();
     LaunchAgentPlist {
-        label: LAUNCH_AGENT_LABEL,
+        label: spec.label(),
         program_arguments: arguments,
         run_at_load: true,
-        keep_alive: true,
+        keep_alive: !spec.triggered(),
+        start_calendar_interval: calendar,
+        watch_paths: watch_paths.into_iter().collect(),
         throttle_interval: 10,
-        process_type: "Background",
-        standard_out_path: spec
-            .state_dir
-            .join("logs/watch.jsonl")
-            .display()
-            .to_string(),
+        process_type: spec.process_type,
+        low_priority_io: spec.low_priority_io,
+        standard_out_path: spec.log_rotation.is_none().then_some(log_path),
         standard_error_path: spec
             .state_dir
-            .join("logs/watch.error.log")
+            .join(format!("logs/{log_stem}.error.log"))
             .display()
             .to_string(),
         environment_variables: spec.environment.clone(),
@@ -251,12 +653,12 @@ fn bootout(path: &Path) -> Result<()> {
     Ok(())
 }
 
-fn launchctl_loaded() -> Result<Option<bool>> {
+fn launchctl_loaded(label: &str) -> Result<Option<bool>> {
     if !cfg!(target_os = "macos") {
         return Ok(None);
     }
     let domain = user_domain()?;
-    let service = format!("{domain}/{LAUNCH_AGENT_LABEL}");
+    let service = format!("{domain}/{label}");
     let output = Command::new("/bin/launchctl")
         .args(["print", &service])
         .output()
@@ -267,15 +669,25 @@ fn launchctl_loaded() -> Result<Option<bool>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::watch_status::WatchStatus;
+    use std::time::{SystemTime, UNIX_EPOCH};
 
     #[test]
     fn renders_notification_only_agent_without_mutation_flags() {
         let spec = LaunchAgentSpec {
+            name: None,
             executable: PathBuf::from("/opt/homebrew/bin/dutis"),
             config: PathBuf::from("/Users/test/dutis.toml"),
             interval_seconds: 300,
+            events: false,
             notify: true,
+            metrics_listen: None,
             remediation_requester: None,
+            calendar: Vec::new(),
+            watch_paths: Vec::new(),
+            process_type: ProcessType::default(),
+            low_priority_io: false,
+            log_rotation: None,
             state_dir: PathBuf::from("/Users/test/Library/Application Support/dutis"),
             environment: BTreeMap::from([(
                 "PATH".to_owned(),
@@ -300,11 +712,19 @@ mod tests {
     #[test]
     fn remediation_agent_records_explicit_opt_in_and_requester() {
         let spec = LaunchAgentSpec {
+            name: None,
             executable: PathBuf::from("/opt/homebrew/bin/dutis"),
             config: PathBuf::from("/Users/test/dutis.toml"),
             interval_seconds: 60,
+            events: true,
             notify: false,
+            metrics_listen: Some(SocketAddr::from(([127, 0, 0, 1], 9464))),
             remediation_requester: Some("launch-agent".to_owned()),
+            calendar: Vec::new(),
+            watch_paths: Vec::new(),
+            process_type: ProcessType::default(),
+            low_priority_io: false,
+            log_rotation: None,
             state_dir: PathBuf::from("/tmp/dutis"),
             environment: BTreeMap::new(),
         };
@@ -312,10 +732,247 @@ mod tests {
         let arguments = value.as_dictionary().unwrap()["ProgramArguments"]
             .as_array()
             .unwrap();
-        for expected in ["--remediate", "--yes", "--requester", "launch-agent"] {
+        for expected in [
+            "--events",
+            "--metrics-listen",
+            "127.0.0.1:9464",
+            "--remediate",
+            "--yes",
+            "--requester",
+            "launch-agent",
+        ] {
             assert!(arguments
                 .iter()
                 .any(|value| value.as_string() == Some(expected)));
         }
     }
+
+    #[test]
+    fn renders_scheduled_agents_deterministically() {
+        let mut calendar = CalendarInterval::parse_schedule("weekdays@09:00").unwrap();
+        calendar.extend(CalendarInterval::parse_schedule("mon@09:00").unwrap());
+        calendar.extend(CalendarInterval::parse_schedule("18:30").unwrap());
+        let spec = LaunchAgentSpec {
+            name: None,
+            executable: PathBuf::from("/opt/homebrew/bin/dutis"),
+            config: PathBuf::from("/Users/test/dutis.toml"),
+            interval_seconds: 300,
+            events: false,
+            notify: true,
+            metrics_listen: None,
+            remediation_requester: None,
+            calendar,
+            watch_paths: vec![
+                PathBuf::from("/Users/test/dutis.toml"),
+                PathBuf::from("/Applications"),
+            ],
+            process_type: ProcessType::Adaptive,
+            low_priority_io: true,
+            log_rotation: Some(LogRotation {
+                max_bytes: 1_048_576,
+                keep: 3,
+            }),
+            state_dir: PathBuf::from("/tmp/dutis"),
+            environment: BTreeMap::new(),
+        };
+        let render = || {
+            let mut xml = Vec::new();
+            plist::to_writer_xml(&mut xml, &plist(&spec)).unwrap();
+            xml
+        };
+        assert_eq!(render(), render());
+        let value = plist::to_value(&plist(&spec)).unwrap();
+        let dictionary = value.as_dictionary().unwrap();
+        assert_eq!(dictionary["KeepAlive"].as_boolean(), Some(false));
+        assert_eq!(dictionary["ProcessType"].as_string(), Some("Adaptive"));
+        assert_eq!(dictionary["LowPriorityIO"].as_boolean(), Some(true));
+        assert!(!dictionary.contains_key("StandardOutPath"));
+        let calendar = dictionary["StartCalendarInterval"].as_array().unwrap();
+        assert_eq!(calendar.len(), 6);
+        let first = calendar[0].as_dictionary().unwrap();
+        assert!(!first.contains_key("Weekday"));
+        assert_eq!(first["Hour"].as_unsigned_integer(), Some(18));
+        let monday = calendar[1].as_dictionary().unwrap();
+        assert_eq!(monday["Weekday"].as_unsigned_integer(), Some(1));
+        assert_eq!(monday["Hour"].as_unsigned_integer(), Some(9));
+        let watch_paths = dictionary["WatchPaths"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|value| value.as_string().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(watch_paths, ["/Applications", "/Users/test/dutis.toml"]);
+        let arguments = dictionary["ProgramArguments"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|value| value.as_string().unwrap())
+            .collect::<Vec<_>>();
+        assert!(arguments.contains(&"--once"));
+        assert!(!arguments.contains(&"--interval-seconds"));
+        assert!(arguments
+            .windows(2)
+            .any(|pair| pair == ["--log-file", "/tmp/dutis/logs/watch.jsonl"]));
+    }
+
+    #[test]
+    fn only_continuous_agents_report_a_monitor() {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let state = std::env::temp_dir().join(format!(
+            "dutis-launch-agent-monitor-{}-{unique}",
+            std::process::id()
+        ));
+        let config = Path::new("/Users/test/dutis.toml");
+        let status = WatchStatus::start(config, 60, None, OffsetDateTime::now_utc()).unwrap();
+        WatchStatusStore::new(state.join("watch"))
+            .save(&status)
+            .unwrap();
+        let mut plist = InstalledPlist {
+            program_arguments: vec![
+                "/usr/local/bin/dutis".to_owned(),
+                "watch".to_owned(),
+                config.display().to_string(),
+            ],
+            start_calendar_interval: Vec::new(),
+            watch_paths: Vec::new(),
+            environment_variables: BTreeMap::from([(
+                "DUTIS_STATE_DIR".to_owned(),
+                state.display().to_string(),
+            )]),
+        };
+        assert_eq!(plist.monitor_status().unwrap().status, Some(status));
+
+        plist.program_arguments.push("--once".to_owned());
+        assert!(plist.monitor_status().is_none());
+        fs::remove_dir_all(state).unwrap();
+    }
+
+    #[test]
+    fn rejects_invalid_schedules() {
+        assert_eq!(
+            CalendarInterval::parse_schedule("Sat,Sunday@07:05").unwrap(),
+            vec![
+                CalendarInterval {
+                    weekday: Some(6),
+                    hour: 7,
+                    minute: 5
+                },
+                CalendarInterval {
+                    weekday: Some(0),
+                    hour: 7,
+                    minute: 5
+                },
+            ]
+        );
+        for invalid in ["24:00", "9am", "someday@09:00", "t@09:00", "mon@09:60"] {
+            assert!(
+                CalendarInterval::parse_schedule(invalid).is_err(),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn named_agents_get_their_own_label_and_logs() {
+        let spec = LaunchAgentSpec {
+            name: Some("work".to_owned()),
+            executable: PathBuf::from("/opt/homebrew/bin/dutis"),
+            config: PathBuf::from("/Users/test/work.toml"),
+            interval_seconds: 300,
+            events: false,
+            notify: false,
+            metrics_listen: None,
+            remediation_requester: None,
+            calendar: Vec::new(),
+            watch_paths: Vec::new(),
+            process_type: ProcessType::default(),
+            low_priority_io: false,
+            log_rotation: None,
+            state_dir: PathBuf::from("/tmp/dutis"),
+            environment: BTreeMap::new(),
+        };
+        let value = plist::to_value(&plist(&spec)).unwrap();
+        let dictionary = value.as_dictionary().unwrap();
+        assert_eq!(
+            dictionary["Label"].as_string(),
+            Some("io.github.tsonglew.dutis.watch.work")
+        );
+        assert_eq!(
+            dictionary["StandardOutPath"].as_string(),
+            Some("/tmp/dutis/logs/watch-work.jsonl")
+        );
+        assert_eq!(
+            dictionary["StandardErrorPath"].as_string(),
+            Some("/tmp/dutis/logs/watch-work.error.log")
+        );
+        for invalid in ["", "../work", "work.toml", "personal browser"] {
+            assert!(validate_agent_name(invalid).is_err(), "{invalid}");
+        }
+        validate_agent_name("personal-browser").unwrap();
+    }
+
+    #[test]
+    fn render_matches_the_installed_plist_without_writing() {
+        let unique = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let root = std::env::temp_dir().join(format!(
+            "dutis-launch-agent-render-{}-{unique}",
+            std::process::id()
+        ));
+        let executable = std::env::current_exe().unwrap();
+        let manager = LaunchAgentManager {
+            directory: root.clone(),
+        };
+        let spec = LaunchAgentSpec {
+            name: Some("work".to_owned()),
+            executable: executable.clone(),
+            config: executable.clone(),
+            interval_seconds: 300,
+            events: false,
+            notify: false,
+            metrics_listen: None,
+            remediation_requester: None,
+            calendar: Vec::new(),
+            watch_paths: Vec::new(),
+            process_type: ProcessType::default(),
+            low_priority_io: false,
+            log_rotation: None,
+            state_dir: root.join("state"),
+            environment: BTreeMap::new(),
+        };
+        let rendered = manager.render(&spec).unwrap();
+        assert!(!root.exists());
+        assert_eq!(rendered.label, "io.github.tsonglew.dutis.watch.work");
+        assert_eq!(
+            rendered.path,
+            root.join("io.github.tsonglew.dutis.watch.work.plist")
+        );
+        assert_eq!(
+            rendered.plist["ProgramArguments"][0],
+            executable.display().to_string()
+        );
+        let mut expected = Vec::new();
+        plist::to_writer_xml(&mut expected, &plist(&spec)).unwrap();
+        expected.push(b'\n');
+        assert_eq!(rendered.xml.as_bytes(), expected);
+
+        fs::create_dir_all(&root).unwrap();
+        fs::write(&rendered.path, &rendered.xml).unwrap();
+        manager.render(&spec).unwrap();
+        let error = manager
+            .render(&LaunchAgentSpec {
+                name: Some("Work".to_owned()),
+                ..spec
+            })
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("differs only in case from installed agent 'work'"));
+        fs::remove_dir_all(root).unwrap();
+    }
 }
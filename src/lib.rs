@@ -3,11 +3,19 @@ pub mod application;
 pub mod association;
 pub mod config;
 pub mod drift;
+pub mod file_events;
 pub mod governance;
 pub mod launch_agent;
+pub mod log_rotation;
 pub mod mcp;
+pub mod metrics;
+pub mod notification;
 pub mod planner;
 pub mod plist_parser;
 pub mod profiles;
+pub mod retention;
 pub mod snapshot;
+pub mod state_lock;
 pub mod system;
+pub mod verification;
+pub mod watch_status;
@@ -0,0 +1,112 @@
+use anyhow::{Context, Result};
+use std::ffi::OsString;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// An append-only log that is renamed to `<path>.1`, `<path>.2`, ... once it
+/// would grow past `max_bytes`. launchd never rotates `StandardOutPath`, so a
+/// long-running agent writes its JSON lines here instead.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RotatingLog {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: u32,
+}
+
+impl RotatingLog {
+    /// Keeps at most `keep` rotated files next to the current one.
+    pub fn new(path: impl Into<PathBuf>, max_bytes: u64, keep: u32) -> Self {
+        Self {
+            path: path.into(),
+            max_bytes,
+            keep,
+        }
+    }
+
+    pub fn append_line(&self, line: &str) -> Result<()> {
+        let incoming = line.len() as u64 + 1;
+        let current = fs::metadata(&self.path).map_or(0, |metadata| metadata.len());
+        if current > 0 && current + incoming > self.max_bytes {
+            self.rotate()?;
+        }
+        let mut options = OpenOptions::new();
+        options.append(true).create(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options
+            .open(&self.path)
+            .with_context(|| format!("failed to open {}", self.path.display()))?;
+        file.write_all(format!("{line}\n").as_bytes())
+            .with_context(|| format!("failed to append to {}", self.path.display()))
+    }
+
+    fn rotate(&self) -> Result<()> {
+        if self.keep == 0 {
+            return remove_if_present(&self.path);
+        }
+        remove_if_present(&self.rotated(self.keep))?;
+        for index in (1..self.keep).rev() {
+            let from = self.rotated(index);
+            if from.exists() {
+                fs::rename(&from, self.rotated(index + 1))
+                    .with_context(|| format!("failed to rotate {}", from.display()))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated(1))
+            .with_context(|| format!("failed to rotate {}", self.path.display()))
+    }
+
+    fn rotated(&self, index: u32) -> PathBuf {
+        let mut name = OsString::from(self.path.as_os_str());
+        name.push(format!(".{index}"));
+        PathBuf::from(name)
+    }
+}
+
+fn remove_if_present(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(error) if error.kind() != std::io::ErrorKind::NotFound => {
+            Err(error).with_context(|| format!("failed to remove {}", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn rotates_before_exceeding_the_limit_and_drops_the_oldest_file() {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let root = std::env::temp_dir().join(format!(
+            "dutis-log-rotation-{}-{unique}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&root).unwrap();
+        let path = root.join("watch.jsonl");
+        let log = RotatingLog::new(&path, 6, 2);
+        for line in ["one", "two", "three", "four", "five"] {
+            log.append_line(line).unwrap();
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap(), "five\n");
+        assert_eq!(
+            fs::read_to_string(root.join("watch.jsonl.1")).unwrap(),
+            "four\n"
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("watch.jsonl.2")).unwrap(),
+            "three\n"
+        );
+        assert!(!root.join("watch.jsonl.3").exists());
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -1,11 +1,13 @@
 use anyhow::{Context, Result};
 use clap::Parser;
 use cli::{
-    ApplyArgs, Cli, CliCommand, ConfigArgs, ExtensionArgs, HandlerArgs, HandlerCommand,
-    HandlerGetArgs, HandlerSetArgs, LaunchAgentArgs, LaunchAgentCommand, LaunchAgentInstallArgs,
+    ApplyArgs, AuditArgs, AuditCommand, Cli, CliCommand, ConfigArgs, DriftArgs, DriftCommand,
+    DriftHistoryArgs, ExtensionArgs, GcArgs, HandlerArgs, HandlerCommand, HandlerGetArgs,
+    HandlerSetArgs, HistoryArgs, LaunchAgentArgs, LaunchAgentCommand, LaunchAgentInstallArgs,
     McpArgs, OutputArgs, PolicyArgs, PolicyCheckArgs, PolicyCommand, ProfileArgs, ProfileCommand,
-    ProfileShowArgs, RecommendArgs, RollbackArgs, SetArgs, SnapshotArgs, SnapshotCommand,
-    SnapshotCreateArgs, WatchArgs,
+    ProfileListArgs, ProfileShowArgs, RecommendArgs, RollbackArgs, SetArgs, SnapshotArgs,
+    SnapshotCommand, SnapshotCreateArgs, SnapshotDiffArgs, SnapshotExportArgs, SnapshotImportArgs,
+    UndoArgs, WatchArgs,
 };
 use colored::*;
 use dutis::application::{
@@ -13,34 +15,61 @@ use dutis::application::{
     ApplicationCatalog,
 };
 use dutis::association::{AssociationKind, AssociationTarget, HandlerRole};
-use dutis::config::DutisConfig;
-use dutis::drift::{send_macos_notification, DriftReport, DriftState, DriftTracker};
+use dutis::config::{DriftAction, DutisConfig};
+use dutis::drift::{
+    attribute_drift, detect_flapping, drift_statistics, DriftAssociationStats, DriftEvent,
+    DriftHistory, DriftRecorder, DriftReport, DriftState, DriftTracker, FlapPolicy,
+};
+use dutis::file_events::{
+    association_watch_paths, wait_for_change, ChangeSource, NotifyChangeSource, PollingChangeSource,
+};
 use dutis::governance::{
-    execute_governed_plan, ApprovalMode, AuditStore, GovernanceErrorKind, GovernedMutation,
-    LoadedPolicy, MutationChannel, MutationOperation, MutationRequest, PolicyAssessment,
+    build_undo_plan, execute_governed_plan, latest_undoable, parse_audit_time, write_audit_export,
+    ApprovalMode, AuditQuery, AuditStore, GovernanceErrorKind, GovernedMutation, LoadedPolicy,
+    MutationChannel, MutationOperation, MutationRequest, PolicyAssessment, UndoPlan,
 };
-use dutis::launch_agent::{LaunchAgentManager, LaunchAgentSpec, LaunchAgentStatus};
+use dutis::launch_agent::{
+    validate_agent_name, CalendarInterval, LaunchAgentManager, LaunchAgentSpec, LaunchAgentStatus,
+    LaunchAgentSummary, LogRotation,
+};
+use dutis::log_rotation::RotatingLog;
+use dutis::metrics::{write_textfile, MetricsServer, WatchMetrics};
+use dutis::notification::{DriftNotificationPayload, MacosNotificationSink, NotificationSink};
 use dutis::planner::{
-    assemble_plan, build_plan, AssociationPlan, PlanAction, PlanEntry, PlanSummary,
+    assemble_plan, build_plan, ApplyStatus, AssociationPlan, PlanAction, PlanEntry, PlanSummary,
     PlannedApplication,
 };
-use dutis::profiles::{find_profile, profiles, recommend_profile, ProfileRecommendation};
+use dutis::profiles::{
+    recommend_profile, ProfileCatalog, ProfileDefinition, ProfileRecommendation,
+};
+use dutis::retention::collect_garbage;
 use dutis::snapshot::{
-    build_rollback_plan, capture_targets, SnapshotReason, SnapshotStore, SnapshotSummary,
+    build_rollback_plan, capture_targets, capture_targets_parallel, declared_targets,
+    diff_associations, missing_applications, snapshot_targets, validate_snapshot_reference,
+    CaptureFailure, MissingApplication, RollbackSelection, Snapshot, SnapshotAssociation,
+    SnapshotBundle, SnapshotChange, SnapshotMetadata, SnapshotNameConflict, SnapshotReason,
+    SnapshotStore, SnapshotSummary,
 };
-use dutis::system;
+use dutis::state_lock::{LockError, LockHolder};
+use dutis::system::{self, DefaultApplication};
+use dutis::watch_status::{WatchStatus, WatchStatusReport, WatchStatusStore};
 use serde::Serialize;
 use serde_json::Value;
 use std::collections::BTreeMap;
 use std::collections::BTreeSet;
-use std::io::{self, Write};
+use std::io::{self, IsTerminal, Write};
 use std::path::{Path, PathBuf};
 use std::process::ExitCode;
+use std::thread;
 use std::time::Duration;
 
 mod cli;
 
 const API_VERSION: &str = "1";
+const SNAPSHOT_CAPTURE_WORKERS: usize = 8;
+/// Drift events a watcher keeps in memory for flap detection; older ones stay
+/// in the history file only.
+const MAX_WATCH_DRIFT_EVENTS: usize = 1024;
 
 #[derive(Debug)]
 struct CliError {
@@ -83,6 +112,14 @@ impl CliError {
         Self::new(9, "policy_denied", message).with_details(details)
     }
 
+    fn integrity(message: impl Into<String>, details: Value) -> Self {
+        Self::new(10, "integrity_failure", message).with_details(details)
+    }
+
+    fn locked(message: impl Into<String>) -> Self {
+        Self::new(11, "locked", message)
+    }
+
     fn new(code: u8, kind: &'static str, message: impl Into<String>) -> Self {
         Self {
             code,
@@ -165,6 +202,8 @@ struct DoctorResult {
     duti_version: Option<String>,
     ready_for_read_only_commands: bool,
     ready_for_changes: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    monitors: Vec<WatchStatusReport>,
 }
 
 #[derive(Serialize)]
@@ -178,11 +217,30 @@ struct DiffResult<'a> {
 struct SnapshotCreated {
     snapshot: SnapshotSummary,
     path: PathBuf,
+    /// Targets left out of an `--all` snapshot because their query failed.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    capture_failures: Vec<CaptureFailure>,
+}
+
+#[derive(Serialize)]
+struct SnapshotExported {
+    snapshot: SnapshotSummary,
+    path: PathBuf,
+    sha256: String,
+}
+
+#[derive(Serialize)]
+struct SnapshotImported {
+    snapshot: SnapshotSummary,
+    path: PathBuf,
+    missing_applications: Vec<MissingApplication>,
 }
 
 #[derive(Serialize)]
 struct RollbackPreview<'a> {
     snapshot_id: &'a str,
+    #[serde(skip_serializing_if = "RollbackSelection::is_empty")]
+    selection: &'a RollbackSelection,
     plan: &'a AssociationPlan,
 }
 
@@ -204,10 +262,19 @@ struct RecommendResult {
 #[derive(Serialize)]
 struct WatchResult {
     report: DriftReport,
+    /// What the watcher does with each drifted or unresolved association.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    drift_actions: Vec<WatchDriftAction>,
     #[serde(skip_serializing_if = "Option::is_none")]
     remediation: Option<WatchRemediation>,
 }
 
+#[derive(Serialize)]
+struct WatchDriftAction {
+    association: AssociationTarget,
+    on_drift: DriftAction,
+}
+
 #[derive(Serialize)]
 struct WatchRemediation {
     status: &'static str,
@@ -268,12 +335,15 @@ fn dispatch(command: Option<CliCommand>) -> Result<(), CliError> {
         Some(CliCommand::Snapshot(args)) => run_snapshot(args),
         Some(CliCommand::History(args)) => run_history(args),
         Some(CliCommand::Rollback(args)) => run_rollback(args),
+        Some(CliCommand::Undo(args)) => run_undo(args),
         Some(CliCommand::Policy(args)) => run_policy(args),
         Some(CliCommand::Audit(args)) => run_audit(args),
+        Some(CliCommand::Gc(args)) => run_gc(args),
         Some(CliCommand::Profile(args)) => run_profile(args),
         Some(CliCommand::Recommend(args)) => run_recommend(args),
         Some(CliCommand::Handler(args)) => run_handler(args),
         Some(CliCommand::Watch(args)) => run_watch(args),
+        Some(CliCommand::Drift(args)) => run_drift(args),
         Some(CliCommand::LaunchAgent(args)) => run_launch_agent(args),
         Some(CliCommand::Mcp(args)) => run_mcp(args),
         Some(CliCommand::Doctor(args)) => run_doctor(args),
@@ -292,12 +362,15 @@ fn command_name(command: &CliCommand) -> &'static str {
         CliCommand::Snapshot(_) => "snapshot",
         CliCommand::History(_) => "history",
         CliCommand::Rollback(_) => "rollback",
+        CliCommand::Undo(_) => "undo",
         CliCommand::Policy(_) => "policy",
         CliCommand::Audit(_) => "audit",
+        CliCommand::Gc(_) => "gc",
         CliCommand::Profile(_) => "profile",
         CliCommand::Recommend(_) => "recommend",
         CliCommand::Handler(_) => "handler",
         CliCommand::Watch(_) => "watch",
+        CliCommand::Drift(_) => "drift",
         CliCommand::LaunchAgent(_) => "launch-agent",
         CliCommand::Mcp(_) => "mcp",
         CliCommand::Doctor(_) => "doctor",
@@ -313,14 +386,22 @@ fn command_uses_json(command: &CliCommand) -> bool {
         CliCommand::Apply(args) => args.json,
         CliCommand::Snapshot(args) => match &args.command {
             SnapshotCommand::Create(args) => args.json,
+            SnapshotCommand::Diff(args) => args.json,
+            SnapshotCommand::Export(args) => args.json,
+            SnapshotCommand::Import(args) => args.json,
         },
         CliCommand::History(args) => args.json,
         CliCommand::Rollback(args) => args.json,
+        CliCommand::Undo(args) => args.json,
         CliCommand::Policy(args) => match &args.command {
             PolicyCommand::Show(args) => args.json,
             PolicyCommand::Check(args) => args.json,
         },
-        CliCommand::Audit(args) => args.json,
+        CliCommand::Audit(args) => match &args.command {
+            Some(AuditCommand::Verify(args)) => args.json,
+            None => args.json,
+        },
+        CliCommand::Gc(args) => args.json,
         CliCommand::Profile(args) => match &args.command {
             ProfileCommand::List(args) => args.json,
             ProfileCommand::Show(args) => args.json,
@@ -331,9 +412,13 @@ fn command_uses_json(command: &CliCommand) -> bool {
             HandlerCommand::Set(args) => args.json,
         },
         CliCommand::Watch(args) => args.json,
+        CliCommand::Drift(args) => match &args.command {
+            DriftCommand::History(args) => args.json,
+        },
         CliCommand::LaunchAgent(args) => match &args.command {
-            LaunchAgentCommand::Install(args) => args.json,
+            LaunchAgentCommand::Install(args) | LaunchAgentCommand::Render(args) => args.json,
             LaunchAgentCommand::Uninstall(args) | LaunchAgentCommand::Status(args) => args.json,
+            LaunchAgentCommand::List(args) => args.json,
         },
         CliCommand::Mcp(_) => false,
     }
@@ -494,14 +579,150 @@ fn run_handler_set(args: HandlerSetArgs) -> Result<(), CliError> {
 fn run_watch(args: WatchArgs) -> Result<(), CliError> {
     validate_remediation_options(args.remediate, args.yes, args.requester.as_deref())?;
     let mut tracker = DriftTracker::default();
+    let history = DriftHistory::from_environment()
+        .map_err(|error| eprintln!("Warning: drift history is unavailable: {error:#}"))
+        .ok();
+    let config_key = std::fs::canonicalize(&args.config).unwrap_or_else(|_| args.config.clone());
+    let mut events = history
+        .as_ref()
+        .and_then(|history| {
+            history
+                .events()
+                .map_err(|error| eprintln!("Warning: failed to read drift history: {error:#}"))
+                .ok()
+        })
+        .unwrap_or_default();
+    let config_name = config_key.display().to_string();
+    events.retain(|event| event.config == config_name);
+    events.drain(..events.len().saturating_sub(MAX_WATCH_DRIFT_EVENTS));
+    let mut recorder = DriftRecorder::resume(&config_key, &events);
+    let sinks = notification_sinks(args.notify)?;
+    let log = args
+        .log_file
+        .as_ref()
+        .map(|path| RotatingLog::new(path, args.log_max_bytes, args.log_keep));
+    let mut metrics = WatchMetrics::default();
+    let metrics_server = args
+        .metrics_listen
+        .map(MetricsServer::bind)
+        .transpose()
+        .map_err(|error| CliError::usage(format!("{error:#}")))?;
+    let flap_policy = FlapPolicy {
+        threshold: usize::try_from(args.flap_threshold).unwrap_or(usize::MAX),
+        window: time::Duration::minutes(args.flap_window_minutes),
+        ..FlapPolicy::default()
+    };
+    let mut bundle_versions = history
+        .as_ref()
+        .and_then(|history| {
+            history
+                .bundle_versions()
+                .map_err(|error| eprintln!("Warning: failed to read bundle versions: {error:#}"))
+                .ok()
+        })
+        .unwrap_or_default();
+    let mut changes: Box<dyn ChangeSource> = if args.events && !args.once {
+        match NotifyChangeSource::watch(&association_watch_paths(&config_key)) {
+            Ok(source) => Box::new(source),
+            Err(error) => {
+                eprintln!(
+                    "Warning: file notifications are unavailable; polling instead: {error:#}"
+                );
+                Box::new(PollingChangeSource)
+            }
+        }
+    } else {
+        Box::new(PollingChangeSource)
+    };
+    let mut heartbeat = if args.once {
+        None
+    } else {
+        start_heartbeat(&config_key, args.interval_seconds)
+    };
+    let mut warned_inactive_remediation = false;
     loop {
-        let report = build_drift_report(&args.config)?;
-        if args.notify && tracker.should_notify(&report) {
-            if let Err(error) = send_macos_notification(&report.notification()) {
-                eprintln!("Warning: failed to send drift notification: {error:#}");
+        let (mut report, catalog, actions, policy) = match build_drift_report(&args.config) {
+            Ok(checked) => checked,
+            Err(error) if args.once => return Err(error),
+            Err(error) => {
+                eprintln!("Error: {}", error.message);
+                beat(heartbeat.as_mut(), |status, now| {
+                    status.failed(&error.message, now)
+                });
+                changes = wait_for_next_check(changes, &args);
+                continue;
+            }
+        };
+        metrics.observe_check(&report, catalog.scan_duration);
+        if !args.remediate
+            && !warned_inactive_remediation
+            && actions
+                .values()
+                .any(|action| *action == DriftAction::Remediate)
+        {
+            eprintln!(
+                "Warning: on_drift = \"remediate\" rules are only notified because watch runs without --remediate"
+            );
+            warned_inactive_remediation = true;
+        }
+        // Without --remediate, remediate rules act, and are reported, as notify.
+        let action_for = |entry: &PlanEntry| match actions.get(&entry.association()).copied() {
+            Some(DriftAction::Remediate) | None if args.remediate => DriftAction::Remediate,
+            Some(DriftAction::Remediate) | None => DriftAction::Notify,
+            Some(action) => action,
+        };
+        if bundle_versions.observe(&catalog.applications, &report.checked_at) {
+            if let Some(Err(error)) = history
+                .as_ref()
+                .map(|history| history.save_bundle_versions(&bundle_versions))
+            {
+                eprintln!("Warning: failed to store bundle versions: {error:#}");
+            }
+        }
+        record_drift_event(history.as_ref(), &mut events, recorder.observe(&report));
+        if let Some(started_at) = recorder.drift_started_at() {
+            attribute_drift(
+                &mut report,
+                &catalog.applications,
+                &bundle_versions,
+                started_at,
+            );
+        }
+        let now = time::OffsetDateTime::now_utc();
+        report.mark_flapping(detect_flapping(
+            &report,
+            &config_key.display().to_string(),
+            &events,
+            &flap_policy,
+            now,
+        ));
+        let restrict = |keep: &dyn Fn(&PlanEntry) -> bool| {
+            report.restricted(&policy.policy, keep).map_err(|error| {
+                CliError::operation(format!("failed to build drift report: {error:#}"))
+            })
+        };
+        let notifiable = restrict(&|entry| action_for(entry) != DriftAction::Record)?;
+        let remediable = restrict(&|entry| {
+            action_for(entry) == DriftAction::Remediate && !report.remediation_suspended(entry, now)
+        })?;
+        if !sinks.is_empty() && tracker.should_notify(&notifiable) {
+            let notification = notifiable.notification();
+            let config = config_key.display().to_string();
+            let payload = DriftNotificationPayload::new(&notification, &config, &notifiable);
+            for sink in &sinks {
+                if let Err(error) = sink.deliver(&payload) {
+                    eprintln!(
+                        "Warning: failed to send drift notification to {}: {error:#}",
+                        sink.describe()
+                    );
+                }
             }
         }
-        let remediation = if args.remediate && report.state == DriftState::DriftDetected {
+        let remediation = if args.remediate
+            && matches!(
+                remediable.state,
+                DriftState::DriftDetected | DriftState::Flapping
+            ) {
             let request = MutationRequest {
                 requester: args
                     .requester
@@ -513,44 +734,258 @@ fn run_watch(args: WatchArgs) -> Result<(), CliError> {
                 operation: MutationOperation::Remediate,
                 explicit_approval: args.yes,
                 approval_token: std::env::var("DUTIS_WATCH_APPROVAL_TOKEN").ok(),
+                atomic: false,
+                undoes: None,
             };
             match execute_governed_plan(
-                &report.plan,
+                &remediable.plan,
                 SnapshotReason::BeforeRemediation,
                 &request,
-                system::set_default_handler,
+                system::write_default_handler,
+                system::query_default_handler,
             ) {
-                Ok(result) => Some(WatchRemediation {
-                    status: if result.report.failed == 0 {
-                        "succeeded"
-                    } else {
-                        "partial_failure"
-                    },
-                    mutation: Some(result),
-                    audit_id: None,
-                    error: None,
-                    violations: Vec::new(),
-                }),
-                Err(error) => Some(WatchRemediation {
-                    status: "blocked",
-                    mutation: None,
-                    audit_id: error.audit_id().map(str::to_owned),
-                    error: Some(error.to_string()),
-                    violations: error.violations().to_vec(),
-                }),
+                Ok(result) => {
+                    metrics.observe_remediation(Some(&result.report));
+                    record_drift_event(
+                        history.as_ref(),
+                        &mut events,
+                        recorder.remediated(&report, &result),
+                    );
+                    Some(WatchRemediation {
+                        status: if result.report.succeeded() {
+                            "succeeded"
+                        } else {
+                            "partial_failure"
+                        },
+                        mutation: Some(result),
+                        audit_id: None,
+                        error: None,
+                        violations: Vec::new(),
+                    })
+                }
+                Err(error) => {
+                    metrics.observe_remediation(None);
+                    Some(WatchRemediation {
+                        status: "blocked",
+                        mutation: None,
+                        audit_id: error.audit_id().map(str::to_owned),
+                        error: Some(error.to_string()),
+                        violations: error.violations().to_vec(),
+                    })
+                }
             }
         } else {
             None
         };
+        let drift_actions = report
+            .changes
+            .iter()
+            .chain(&report.unresolved)
+            .map(|entry| WatchDriftAction {
+                association: entry.association(),
+                on_drift: action_for(entry),
+            })
+            .collect();
         let result = WatchResult {
             report,
+            drift_actions,
             remediation,
         };
-        print_watch_result(&result, args.json)?;
+        print_watch_result(&result, args.json, log.as_ref())?;
+        publish_metrics(
+            &metrics,
+            metrics_server.as_ref(),
+            args.metrics_textfile.as_deref(),
+        );
+        beat(heartbeat.as_mut(), |status, now| {
+            status.succeeded(result.report.state, &result.report.plan_digest, now)
+        });
         if args.once {
             return Ok(());
         }
-        std::thread::sleep(Duration::from_secs(args.interval_seconds));
+        changes = wait_for_next_check(changes, &args);
+    }
+}
+
+fn wait_for_next_check(
+    mut changes: Box<dyn ChangeSource>,
+    args: &WatchArgs,
+) -> Box<dyn ChangeSource> {
+    if let Err(error) = wait_for_change(
+        changes.as_mut(),
+        Duration::from_secs(args.interval_seconds),
+        Duration::from_secs(args.debounce_seconds),
+    ) {
+        eprintln!("Warning: file notifications failed; polling instead: {error:#}");
+        changes = Box::new(PollingChangeSource);
+    }
+    changes
+}
+
+/// Writes the first heartbeat of a continuous watcher. Without a usable state
+/// directory the watcher still runs, only unobserved.
+fn start_heartbeat(
+    config: &Path,
+    interval_seconds: u64,
+) -> Option<(WatchStatusStore, WatchStatus)> {
+    let started = WatchStatusStore::from_environment().and_then(|store| {
+        let previous = store
+            .load(config)
+            .map_err(|error| eprintln!("Warning: ignoring previous watch status: {error:#}"))
+            .ok()
+            .flatten();
+        let status = WatchStatus::start(
+            config,
+            interval_seconds,
+            previous.as_ref(),
+            time::OffsetDateTime::now_utc(),
+        )?;
+        store.save(&status)?;
+        Ok((store, status))
+    });
+    started
+        .map_err(|error| eprintln!("Warning: watch status is unavailable: {error:#}"))
+        .ok()
+}
+
+fn beat(
+    heartbeat: Option<&mut (WatchStatusStore, WatchStatus)>,
+    update: impl FnOnce(&mut WatchStatus, time::OffsetDateTime) -> anyhow::Result<()>,
+) {
+    if let Some((store, status)) = heartbeat {
+        if let Err(error) =
+            update(status, time::OffsetDateTime::now_utc()).and_then(|()| store.save(status))
+        {
+            eprintln!("Warning: failed to update watch status: {error:#}");
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct DriftHistoryResult {
+    path: PathBuf,
+    statistics: Vec<DriftAssociationStats>,
+    events: Vec<DriftEvent>,
+}
+
+fn run_drift(args: DriftArgs) -> Result<(), CliError> {
+    match args.command {
+        DriftCommand::History(args) => run_drift_history(args),
+    }
+}
+
+fn run_drift_history(args: DriftHistoryArgs) -> Result<(), CliError> {
+    let history = DriftHistory::from_environment().map_err(|error| {
+        CliError::operation(format!("failed to resolve drift history: {error:#}"))
+    })?;
+    let since = args
+        .since
+        .as_deref()
+        .map(|value| parse_audit_time(value, time::OffsetDateTime::now_utc()))
+        .transpose()
+        .map_err(|error| CliError::usage(error.to_string()))?;
+    let config = args.config.map(|config| {
+        std::fs::canonicalize(&config)
+            .unwrap_or(config)
+            .display()
+            .to_string()
+    });
+    let events = history
+        .events()
+        .map_err(|error| CliError::operation(format!("failed to read drift history: {error:#}")))?
+        .into_iter()
+        .filter(|event| config.as_ref().is_none_or(|config| &event.config == config))
+        .filter(|event| {
+            since.is_none_or(|since| {
+                time::OffsetDateTime::parse(
+                    &event.timestamp,
+                    &time::format_description::well_known::Rfc3339,
+                )
+                .is_ok_and(|timestamp| timestamp >= since)
+            })
+        })
+        .collect::<Vec<_>>();
+    let statistics = drift_statistics(&events);
+    let limit = args.limit.map_or(usize::MAX, |limit| {
+        usize::try_from(limit).unwrap_or(usize::MAX)
+    });
+    let result = DriftHistoryResult {
+        path: history.path(),
+        statistics,
+        events: events.into_iter().rev().take(limit).collect(),
+    };
+    if args.json {
+        return write_json(&JsonEnvelope {
+            api_version: API_VERSION,
+            command: "drift",
+            data: result,
+        });
+    }
+    if result.events.is_empty() {
+        println!(
+            "No matching drift events found in {}",
+            result.path.display()
+        );
+        return Ok(());
+    }
+    println!("{}", "Drift by association".bold());
+    for stats in &result.statistics {
+        let total = stats.drifted_to.values().sum::<usize>();
+        let (handler, count) = stats
+            .drifted_to
+            .iter()
+            .max_by(|left, right| left.1.cmp(right.1).then_with(|| right.0.cmp(left.0)))
+            .expect("a detection records its handler");
+        let to = if stats.drifted_to.len() == 1 {
+            format!("always to {handler}")
+        } else {
+            format!("most often to {handler} ({count} of {total})")
+        };
+        println!(
+            "  {} drifted {} time(s) since {}, {to}; remediated {} time(s)",
+            stats.association(),
+            stats.detections,
+            stats.first_detected_at,
+            stats.remediations
+        );
+    }
+    println!("{}", "Timeline".bold());
+    for event in &result.events {
+        println!(
+            "  {}\t{}\t{}",
+            event.timestamp,
+            event.event.as_str(),
+            event.config
+        );
+        for entry in &event.entries {
+            println!(
+                "    {}: {} -> {}",
+                entry.association(),
+                entry
+                    .previous_bundle_id
+                    .as_deref()
+                    .unwrap_or("<no default>"),
+                entry.current_bundle_id.as_deref().unwrap_or("<no default>")
+            );
+        }
+    }
+    Ok(())
+}
+
+fn record_drift_event(
+    history: Option<&DriftHistory>,
+    events: &mut Vec<DriftEvent>,
+    event: Option<DriftEvent>,
+) {
+    let Some(event) = event else {
+        return;
+    };
+    if let Some(Err(error)) = history.map(|history| history.append(&event)) {
+        eprintln!("Warning: failed to record drift event: {error:#}");
+    }
+    events.push(event);
+    if events.len() > MAX_WATCH_DRIFT_EVENTS {
+        events.remove(0);
     }
 }
 
@@ -577,16 +1012,76 @@ fn validate_remediation_options(
     Ok(())
 }
 
-fn build_drift_report(config: &Path) -> Result<DriftReport, CliError> {
-    let plan = build_declarative_plan(config)?;
+fn publish_metrics(
+    metrics: &WatchMetrics,
+    server: Option<&MetricsServer>,
+    textfile: Option<&Path>,
+) {
+    if server.is_none() && textfile.is_none() {
+        return;
+    }
+    let exposition = metrics.render();
+    if let Some(path) = textfile {
+        if let Err(error) = write_textfile(path, &exposition) {
+            eprintln!("Warning: failed to write metrics: {error:#}");
+        }
+    }
+    if let Some(server) = server {
+        server.publish(exposition);
+    }
+}
+
+/// The desktop notification when `--notify` is given, followed by the sinks
+/// declared in the policy.
+fn notification_sinks(notify: bool) -> Result<Vec<Box<dyn NotificationSink>>, CliError> {
+    let policy = LoadedPolicy::from_environment()
+        .map_err(|error| CliError::usage(format!("failed to load policy: {error:#}")))?;
+    let mut sinks: Vec<Box<dyn NotificationSink>> = Vec::new();
+    if notify {
+        sinks.push(Box::new(MacosNotificationSink));
+    }
+    sinks.extend(
+        policy
+            .policy
+            .notification_sinks
+            .into_iter()
+            .map(|sink| Box::new(sink) as Box<dyn NotificationSink>),
+    );
+    Ok(sinks)
+}
+
+/// Builds a drift report for the rules whose drift is not ignored and returns
+/// the explicit `on_drift` action of each rule and the policy it was assessed
+/// against.
+fn build_drift_report(
+    path: &Path,
+) -> Result<
+    (
+        DriftReport,
+        ApplicationCatalog,
+        BTreeMap<AssociationTarget, DriftAction>,
+        LoadedPolicy,
+    ),
+    CliError,
+> {
+    let config = DutisConfig::load(path).map_err(|error| CliError::usage(format!("{error:#}")))?;
+    let actions = config
+        .drift_actions()
+        .map_err(|error| CliError::usage(format!("{error:#}")))?;
+    let (plan, catalog) = build_config_plan_with_catalog(&config.without_ignored_drift())?;
     let policy = LoadedPolicy::from_environment()
         .map_err(|error| CliError::usage(format!("failed to load policy: {error:#}")))?;
     let assessment = policy.policy.assess(&plan);
-    DriftReport::new(plan, policy.summary(), assessment)
-        .map_err(|error| CliError::operation(format!("failed to build drift report: {error:#}")))
+    let report = DriftReport::new(plan, policy.summary(), assessment)
+        .map_err(|error| CliError::operation(format!("failed to build drift report: {error:#}")))?;
+    Ok((report, catalog, actions, policy))
 }
 
-fn print_watch_result(result: &WatchResult, json: bool) -> Result<(), CliError> {
+fn print_watch_result(
+    result: &WatchResult,
+    json: bool,
+    log: Option<&RotatingLog>,
+) -> Result<(), CliError> {
     if json {
         let line = serde_json::to_string(&JsonEnvelope {
             api_version: API_VERSION,
@@ -594,7 +1089,14 @@ fn print_watch_result(result: &WatchResult, json: bool) -> Result<(), CliError>
             data: result,
         })
         .map_err(|error| CliError::operation(format!("failed to serialize JSON: {error}")))?;
-        println!("{line}");
+        match log.map(|log| log.append_line(&line)) {
+            Some(Ok(())) => {}
+            Some(Err(error)) => {
+                eprintln!("Warning: failed to write watch log: {error:#}");
+                println!("{line}");
+            }
+            None => println!("{line}"),
+        }
         return Ok(());
     }
     println!("Checked: {}", result.report.checked_at);
@@ -610,7 +1112,31 @@ fn print_watch_result(result: &WatchResult, json: bool) -> Result<(), CliError>
             .as_ref()
             .map(|application| application.bundle_id.as_str())
             .unwrap_or("<unresolved>");
-        println!("DRIFT {}: {} -> {}", entry.association(), current, target);
+        let action = result
+            .drift_actions
+            .iter()
+            .find(|action| action.association == entry.association())
+            .map(|action| format!(" [{}]", action.on_drift.as_str()))
+            .unwrap_or_default();
+        println!(
+            "DRIFT {}: {} -> {}{action}",
+            entry.association(),
+            current,
+            target
+        );
+    }
+    for flapping in &result.report.flapping {
+        println!(
+            "FLAPPING {}: drifted {} time(s) in {} minute(s); {}",
+            flapping.association,
+            flapping.detections,
+            flapping.window_seconds / 60,
+            flapping
+                .remediation_suspended_until
+                .as_deref()
+                .map(|until| format!("remediation paused until {until}"))
+                .unwrap_or_else(|| "one remediation attempt allowed".to_owned())
+        );
     }
     for entry in &result.report.unresolved {
         println!(
@@ -619,6 +1145,20 @@ fn print_watch_result(result: &WatchResult, json: bool) -> Result<(), CliError>
             entry.reason.as_deref().unwrap_or("unknown reason")
         );
     }
+    if let Some(cause) = &result.report.suspected_cause {
+        println!(
+            "Suspected cause: {}{}",
+            cause.bundle_id,
+            cause
+                .name
+                .as_ref()
+                .map(|name| format!(" ({name})"))
+                .unwrap_or_default()
+        );
+        for evidence in &cause.evidence {
+            println!("  {evidence}");
+        }
+    }
     println!("Plan digest: {}", result.report.plan_digest);
     println!(
         "Policy decision: {}",
@@ -648,23 +1188,112 @@ fn run_launch_agent(args: LaunchAgentArgs) -> Result<(), CliError> {
     match args.command {
         LaunchAgentCommand::Install(args) => run_launch_agent_install(args),
         LaunchAgentCommand::Uninstall(args) => {
+            validate_launch_agent_name(args.name.as_deref())?;
             let manager = launch_agent_manager()?;
-            let status = manager.uninstall().map_err(|error| {
+            let status = manager.uninstall(args.name.as_deref()).map_err(|error| {
                 CliError::operation(format!("failed to uninstall LaunchAgent: {error:#}"))
             })?;
             print_launch_agent_status(&status, args.json)
         }
         LaunchAgentCommand::Status(args) => {
+            validate_launch_agent_name(args.name.as_deref())?;
             let manager = launch_agent_manager()?;
-            let status = manager.status().map_err(|error| {
+            let status = manager.status(args.name.as_deref()).map_err(|error| {
                 CliError::operation(format!("failed to inspect LaunchAgent: {error:#}"))
             })?;
             print_launch_agent_status(&status, args.json)
         }
+        LaunchAgentCommand::List(args) => run_launch_agent_list(args),
+        LaunchAgentCommand::Render(args) => run_launch_agent_render(args),
+    }
+}
+
+fn validate_launch_agent_name(name: Option<&str>) -> Result<(), CliError> {
+    name.map(validate_agent_name)
+        .transpose()
+        .map_err(|error| CliError::usage(format!("{error:#}")))?;
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+struct LaunchAgentListResult {
+    agents: Vec<LaunchAgentSummary>,
+}
+
+fn run_launch_agent_list(args: OutputArgs) -> Result<(), CliError> {
+    let agents = launch_agent_manager()?
+        .list()
+        .map_err(|error| CliError::operation(format!("failed to list LaunchAgents: {error:#}")))?;
+    if args.json {
+        return write_json(&JsonEnvelope {
+            api_version: API_VERSION,
+            command: "launch-agent",
+            data: LaunchAgentListResult { agents },
+        });
+    }
+    if agents.is_empty() {
+        println!("No LaunchAgents installed.");
+    }
+    for agent in &agents {
+        let schedule = match agent.interval_seconds {
+            _ if agent.error.is_some() => "unreadable plist".to_owned(),
+            Some(seconds) if agent.events => format!("on change, at least every {seconds}s"),
+            Some(seconds) => format!("every {seconds}s"),
+            None => {
+                let mut triggers = Vec::new();
+                if !agent.calendar.is_empty() {
+                    triggers.push(format!("{} calendar time(s)", agent.calendar.len()));
+                }
+                if !agent.watch_paths.is_empty() {
+                    triggers.push(format!("{} watched path(s)", agent.watch_paths.len()));
+                }
+                triggers.join(", ")
+            }
+        };
+        println!(
+            "{:<20} {:<10} {:<36} {}",
+            agent.name.as_deref().unwrap_or("(default)"),
+            agent
+                .loaded
+                .map(|loaded| if loaded { "loaded" } else { "unloaded" })
+                .unwrap_or("unknown"),
+            schedule,
+            agent.config.as_deref().unwrap_or("<unknown config>")
+        );
     }
+    Ok(())
 }
 
 fn run_launch_agent_install(args: LaunchAgentInstallArgs) -> Result<(), CliError> {
+    let json = args.json;
+    let spec = launch_agent_spec(args)?;
+    let status = launch_agent_manager()?.install(&spec).map_err(|error| {
+        CliError::operation(format!("failed to install LaunchAgent: {error:#}"))
+    })?;
+    print_launch_agent_status(&status, json)
+}
+
+fn run_launch_agent_render(args: LaunchAgentInstallArgs) -> Result<(), CliError> {
+    let json = args.json;
+    let spec = launch_agent_spec(args)?;
+    let rendered = launch_agent_manager()?
+        .render(&spec)
+        .map_err(|error| CliError::operation(format!("failed to render LaunchAgent: {error:#}")))?;
+    if json {
+        return write_json(&JsonEnvelope {
+            api_version: API_VERSION,
+            command: "launch-agent",
+            data: rendered,
+        });
+    }
+    print!("{}", rendered.xml);
+    Ok(())
+}
+
+/// Resolves install options into the exact agent description that is
+/// installed or rendered.
+fn launch_agent_spec(args: LaunchAgentInstallArgs) -> Result<LaunchAgentSpec, CliError> {
+    validate_launch_agent_name(args.name.as_deref())?;
     validate_remediation_options(args.remediate, args.yes, args.requester.as_deref())?;
     DutisConfig::load(&args.config).map_err(|error| CliError::usage(format!("{error:#}")))?;
     let config = std::fs::canonicalize(&args.config).map_err(|error| {
@@ -682,6 +1311,19 @@ fn run_launch_agent_install(args: LaunchAgentInstallArgs) -> Result<(), CliError
             ));
         }
     }
+    let mut calendar = Vec::new();
+    for schedule in &args.schedule {
+        calendar.extend(
+            CalendarInterval::parse_schedule(schedule)
+                .map_err(|error| CliError::usage(format!("{error:#}")))?,
+        );
+    }
+    let watch_paths = args
+        .watch_paths
+        .iter()
+        .map(std::path::absolute)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|error| CliError::usage(format!("invalid watch path: {error}")))?;
     let executable = locate_invoked_executable()?;
     let state_dir = snapshot_store()?.root().to_path_buf();
     let mut environment = BTreeMap::from([(
@@ -698,24 +1340,31 @@ fn run_launch_agent_install(args: LaunchAgentInstallArgs) -> Result<(), CliError
             value.to_string_lossy().into_owned(),
         );
     }
-    let spec = LaunchAgentSpec {
+    Ok(LaunchAgentSpec {
+        name: args.name,
         executable,
         config,
         interval_seconds: args.interval_seconds,
+        events: args.events,
         notify: args.notify,
+        metrics_listen: args.metrics_listen,
         remediation_requester: args.remediate.then(|| {
             args.requester
                 .expect("validated remediation requester")
                 .trim()
                 .to_owned()
         }),
+        calendar,
+        watch_paths,
+        process_type: args.process_type,
+        low_priority_io: args.low_priority_io,
+        log_rotation: args.log_max_bytes.map(|max_bytes| LogRotation {
+            max_bytes,
+            keep: args.log_keep,
+        }),
         state_dir,
         environment,
-    };
-    let status = launch_agent_manager()?.install(&spec).map_err(|error| {
-        CliError::operation(format!("failed to install LaunchAgent: {error:#}"))
-    })?;
-    print_launch_agent_status(&status, args.json)
+    })
 }
 
 fn launch_agent_manager() -> Result<LaunchAgentManager, CliError> {
@@ -756,6 +1405,9 @@ fn print_launch_agent_status(status: &LaunchAgentStatus, json: bool) -> Result<(
             data: status,
         })?;
     } else {
+        if let Some(name) = &status.name {
+            println!("Name: {name}");
+        }
         println!("Label: {}", status.label);
         println!("Path: {}", status.path.display());
         println!("Installed: {}", status.installed);
@@ -766,6 +1418,9 @@ fn print_launch_agent_status(status: &LaunchAgentStatus, json: bool) -> Result<(
                 .map(|value| value.to_string())
                 .unwrap_or_else(|| "unavailable".to_owned())
         );
+        if let Some(monitor) = &status.monitor {
+            println!("Monitor: {}", monitor.summary);
+        }
     }
     Ok(())
 }
@@ -777,25 +1432,44 @@ fn run_profile(args: ProfileArgs) -> Result<(), CliError> {
     }
 }
 
-fn run_profile_list(args: OutputArgs) -> Result<(), CliError> {
-    let available = profiles();
+fn profile_catalog(files: &[PathBuf]) -> Result<ProfileCatalog, CliError> {
+    ProfileCatalog::from_environment(files)
+        .map_err(|error| CliError::usage(format!("failed to load profiles: {error:#}")))
+}
+
+fn find_profile(catalog: &ProfileCatalog, name: &str) -> Result<ProfileDefinition, CliError> {
+    catalog
+        .find(name)
+        .cloned()
+        .ok_or_else(|| CliError::not_found(format!("unknown profile '{name}'")))
+}
+
+fn run_profile_list(args: ProfileListArgs) -> Result<(), CliError> {
+    let catalog = profile_catalog(&args.profile_files)?;
     if args.json {
         write_json(&JsonEnvelope {
             api_version: API_VERSION,
             command: "profile",
-            data: available,
+            data: catalog.profiles(),
         })?;
     } else {
-        for profile in available {
-            println!("{}\t{}", profile.name, profile.description);
+        for profile in catalog.profiles() {
+            match &profile.source {
+                Some(source) => println!(
+                    "{}\t{} ({})",
+                    profile.name,
+                    profile.description,
+                    source.display()
+                ),
+                None => println!("{}\t{}", profile.name, profile.description),
+            }
         }
     }
     Ok(())
 }
 
 fn run_profile_show(args: ProfileShowArgs) -> Result<(), CliError> {
-    let profile = find_profile(&args.name)
-        .ok_or_else(|| CliError::not_found(format!("unknown profile '{}'", args.name)))?;
+    let profile = find_profile(&profile_catalog(&args.profile_files)?, &args.name)?;
     if args.json {
         write_json(&JsonEnvelope {
             api_version: API_VERSION,
@@ -804,8 +1478,11 @@ fn run_profile_show(args: ProfileShowArgs) -> Result<(), CliError> {
         })?;
     } else {
         println!("{}: {}", profile.name, profile.description);
-        for association in profile.associations {
-            println!("\n.{}", association.extension);
+        if let Some(source) = &profile.source {
+            println!("Source: {}", source.display());
+        }
+        for association in &profile.associations {
+            println!("\n{}", association.target());
             for (index, candidate) in association.candidates.iter().enumerate() {
                 println!(
                     "  {}. {} — {}",
@@ -820,15 +1497,16 @@ fn run_profile_show(args: ProfileShowArgs) -> Result<(), CliError> {
 }
 
 fn run_recommend(args: RecommendArgs) -> Result<(), CliError> {
-    let profile = find_profile(&args.profile)
-        .ok_or_else(|| CliError::not_found(format!("unknown profile '{}'", args.profile)))?;
+    let profile = find_profile(&profile_catalog(&args.profile_files)?, &args.profile)?;
     let catalog = scan_catalog()?;
     report_metadata_failures(catalog.metadata_failures);
     system::duti_version().map_err(|error| CliError::dependency(format!("{error:#}")))?;
-    let recommendation =
-        recommend_profile(&profile, &catalog.applications, system::query_default_app).map_err(
-            |error| CliError::operation(format!("failed to build recommendation: {error:#}")),
-        )?;
+    let recommendation = recommend_profile(
+        &profile,
+        &catalog.applications,
+        system::query_default_handler,
+    )
+    .map_err(|error| CliError::operation(format!("failed to build recommendation: {error:#}")))?;
     let policy = LoadedPolicy::from_environment()
         .map_err(|error| CliError::usage(format!("failed to load policy: {error:#}")))?;
     let result = RecommendResult {
@@ -849,8 +1527,10 @@ fn run_recommend(args: RecommendArgs) -> Result<(), CliError> {
         println!("{}", result.recommendation.description);
         for item in &result.recommendation.recommendations {
             println!(
-                "\n{:?} .{}: {}",
-                item.action, item.extension, item.explanation
+                "\n{:?} {}: {}",
+                item.action,
+                item.target_association(),
+                item.explanation
             );
             for candidate in &item.evidence {
                 let status = if candidate.selected {
@@ -977,26 +1657,56 @@ fn run_policy_check(args: PolicyCheckArgs) -> Result<(), CliError> {
     Ok(())
 }
 
-fn run_audit(args: OutputArgs) -> Result<(), CliError> {
+fn run_audit(args: AuditArgs) -> Result<(), CliError> {
     let store = AuditStore::from_environment().map_err(|error| {
         CliError::operation(format!("failed to resolve audit storage: {error:#}"))
     })?;
-    let records = store
-        .history()
+    if let Some(AuditCommand::Verify(args)) = args.command {
+        return run_audit_verify(&store, args);
+    }
+    let now = time::OffsetDateTime::now_utc();
+    let parse_time = |value: Option<&String>| {
+        value
+            .map(|value| parse_audit_time(value, now))
+            .transpose()
+            .map_err(|error| CliError::usage(error.to_string()))
+    };
+    let query = AuditQuery {
+        since: parse_time(args.since.as_ref())?,
+        until: parse_time(args.until.as_ref())?,
+        requester: args.requester,
+        channel: args.channel,
+        operation: args.operation,
+        outcome: args.outcome,
+        target: args.target,
+        kind: args.kind,
+    };
+    let page = store
+        .query(
+            &query,
+            usize::try_from(args.offset).unwrap_or(usize::MAX),
+            args.limit
+                .map(|limit| usize::try_from(limit).unwrap_or(usize::MAX)),
+        )
         .map_err(|error| CliError::operation(format!("failed to read audit history: {error:#}")))?;
+    if let Some(format) = args.format {
+        return write_audit_export(std::io::stdout().lock(), &page.records, format).map_err(
+            |error| CliError::operation(format!("failed to export audit records: {error:#}")),
+        );
+    }
     if args.json {
         write_json(&JsonEnvelope {
             api_version: API_VERSION,
             command: "audit",
-            data: records,
+            data: &page,
         })?;
-    } else if records.is_empty() {
+    } else if page.records.is_empty() {
         println!(
-            "No mutation audit records found in {}",
+            "No matching mutation audit records found in {}",
             store.root().display()
         );
     } else {
-        for record in records {
+        for record in &page.records {
             println!(
                 "{}\t{}\t{:?}\t{:?}\t{}\t{:?}",
                 record.id,
@@ -1007,44 +1717,172 @@ fn run_audit(args: OutputArgs) -> Result<(), CliError> {
                 record.outcome
             );
         }
+        if let Some(next_offset) = page.next_offset {
+            eprintln!(
+                "Showing {} of {} matching records; continue with --offset {next_offset}",
+                page.records.len(),
+                page.total
+            );
+        }
     }
     Ok(())
 }
 
-fn run_list(args: OutputArgs) -> Result<(), CliError> {
-    let catalog = scan_catalog()?;
-    report_metadata_failures(catalog.metadata_failures);
+fn run_audit_verify(store: &AuditStore, args: OutputArgs) -> Result<(), CliError> {
+    let verification = store.verify().map_err(|error| {
+        CliError::operation(format!("failed to verify audit history: {error:#}"))
+    })?;
+    if let Some(broken) = &verification.first_broken_link {
+        let location = match (&broken.record_id, broken.sequence) {
+            (Some(id), Some(sequence)) => format!("record {id} (sequence {sequence})"),
+            (Some(id), None) => format!("record {id}"),
+            _ => "audit history".to_owned(),
+        };
+        return Err(CliError::integrity(
+            format!("audit chain is broken at {location}: {}", broken.message),
+            serde_json::to_value(&verification).map_err(|error| {
+                CliError::operation(format!("failed to serialize verification: {error}"))
+            })?,
+        ));
+    }
     if args.json {
         write_json(&JsonEnvelope {
             api_version: API_VERSION,
-            command: "list",
-            data: ApplicationList {
-                applications: &catalog.applications,
-                metadata_failures: catalog.metadata_failures,
-            },
+            command: "audit",
+            data: &verification,
         })?;
-    } else {
-        for app in &catalog.applications {
-            let bundle_id = app.bundle_id.as_deref().unwrap_or("unknown bundle ID");
-            println!("{}\t{}\t{}", app.name, bundle_id, app.path.display());
-        }
-        println!("\n{} applications", catalog.applications.len());
+        return Ok(());
+    }
+    match &verification.head {
+        Some(head) => println!(
+            "Audit chain verified: {} linked records, head {} (sequence {}, hash {})",
+            verification.chained, head.record_id, head.sequence, head.record_hash
+        ),
+        None => println!(
+            "No chained audit records found in {}",
+            store.root().display()
+        ),
+    }
+    if verification.signatures_checked {
+        println!("Signatures verified: {}", verification.signed);
+    } else if verification.signed > 0 {
+        println!(
+            "{} signed records were not checked because no audit key is configured",
+            verification.signed
+        );
+    }
+    if verification.unchained > 0 {
+        println!(
+            "{} records predate hash chaining and cannot be verified",
+            verification.unchained
+        );
     }
     Ok(())
 }
 
-fn run_query(args: ExtensionArgs) -> Result<(), CliError> {
-    let extension =
-        normalize_extension(&args.extension).map_err(|error| CliError::usage(error.to_string()))?;
-    let catalog = scan_catalog()?;
-    report_metadata_failures(catalog.metadata_failures);
-    let applications = find_apps_for_extension(&catalog.applications, &extension);
-    if applications.is_empty() {
-        return Err(CliError::not_found(format!(
-            "no installed applications declare support for .{extension}"
-        )));
+fn run_gc(args: GcArgs) -> Result<(), CliError> {
+    if !args.dry_run && !args.yes {
+        return Err(CliError::usage(
+            "refusing to prune local history without --yes; use --dry-run to preview",
+        ));
     }
-
+    let policy = LoadedPolicy::from_environment()
+        .map_err(|error| CliError::usage(format!("failed to load policy: {error:#}")))?;
+    let mut retention = policy.policy.retention;
+    for rule in [&mut retention.snapshots, &mut retention.audit] {
+        if let Some(keep_last) = args.keep_last {
+            rule.keep_last = Some(usize::try_from(keep_last).unwrap_or(usize::MAX));
+        }
+        if let Some(keep_days) = args.keep_days {
+            rule.keep_days = Some(keep_days);
+        }
+    }
+    retention.archive |= args.archive;
+    let audit_store = AuditStore::from_environment().map_err(|error| {
+        CliError::operation(format!("failed to resolve audit storage: {error:#}"))
+    })?;
+    let holder = LockHolder::current(MutationChannel::Cli, "gc", &cli_requester(None));
+    let report = collect_garbage(
+        &snapshot_store()?,
+        &audit_store,
+        &retention,
+        &holder,
+        time::OffsetDateTime::now_utc(),
+        args.dry_run,
+    )
+    .map_err(|error| match error.downcast_ref::<LockError>() {
+        Some(LockError::Busy { .. }) => CliError::locked(format!("{error}; nothing was pruned")),
+        _ => CliError::operation(format!("failed to prune local history: {error:#}")),
+    })?;
+    if args.json {
+        return write_json(&JsonEnvelope {
+            api_version: API_VERSION,
+            command: "gc",
+            data: report,
+        });
+    }
+    if retention.is_unbounded() {
+        println!("No retention limits are configured; nothing to prune.");
+        return Ok(());
+    }
+    let verb = match (report.dry_run, report.archive) {
+        (true, true) => "Would archive",
+        (true, false) => "Would delete",
+        (false, true) => "Archived",
+        (false, false) => "Deleted",
+    };
+    for (name, section) in [
+        ("snapshots", &report.snapshots),
+        ("audit records", &report.audit),
+    ] {
+        println!(
+            "{verb} {} of {} {name}; {} retained ({} protected)",
+            section.removed.len(),
+            section.examined,
+            section.retained,
+            section.protected.len()
+        );
+    }
+    for archive in &report.archives {
+        println!("Archive: {}", archive.display());
+    }
+    Ok(())
+}
+
+fn run_list(args: OutputArgs) -> Result<(), CliError> {
+    let catalog = scan_catalog()?;
+    report_metadata_failures(catalog.metadata_failures);
+    if args.json {
+        write_json(&JsonEnvelope {
+            api_version: API_VERSION,
+            command: "list",
+            data: ApplicationList {
+                applications: &catalog.applications,
+                metadata_failures: catalog.metadata_failures,
+            },
+        })?;
+    } else {
+        for app in &catalog.applications {
+            let bundle_id = app.bundle_id.as_deref().unwrap_or("unknown bundle ID");
+            println!("{}\t{}\t{}", app.name, bundle_id, app.path.display());
+        }
+        println!("\n{} applications", catalog.applications.len());
+    }
+    Ok(())
+}
+
+fn run_query(args: ExtensionArgs) -> Result<(), CliError> {
+    let extension =
+        normalize_extension(&args.extension).map_err(|error| CliError::usage(error.to_string()))?;
+    let catalog = scan_catalog()?;
+    report_metadata_failures(catalog.metadata_failures);
+    let applications = find_apps_for_extension(&catalog.applications, &extension);
+    if applications.is_empty() {
+        return Err(CliError::not_found(format!(
+            "no installed applications declare support for .{extension}"
+        )));
+    }
+
     if args.json {
         write_json(&JsonEnvelope {
             api_version: API_VERSION,
@@ -1310,7 +2148,10 @@ fn run_apply(args: ApplyArgs) -> Result<(), CliError> {
         ));
     }
 
-    let request = cli_mutation_request(args.requester.as_deref(), MutationOperation::Apply);
+    let request = MutationRequest {
+        atomic: args.atomic,
+        ..cli_mutation_request(args.requester.as_deref(), MutationOperation::Apply)
+    };
     let result = execute_governed_cli_plan(&plan, SnapshotReason::BeforeApply, &request)?;
     if result.report.failed > 0 {
         let details = serde_json::to_value(&result)
@@ -1318,13 +2159,23 @@ fn run_apply(args: ApplyArgs) -> Result<(), CliError> {
         if !args.json {
             print_mutation_result(&result);
         }
-        return Err(CliError::partial_failure(
-            format!(
+        let message = match &result.compensation {
+            Some(compensation) if compensation.failed == 0 => format!(
+                "{} association(s) failed; all {} applied change(s) were reverted",
+                result.report.failed, compensation.applied
+            ),
+            Some(compensation) => format!(
+                "{} association(s) failed; {} of {} applied change(s) could not be reverted",
+                result.report.failed,
+                compensation.failed,
+                compensation.results.len()
+            ),
+            None => format!(
                 "{} association(s) failed; {} applied and {} skipped",
                 result.report.failed, result.report.applied, result.report.skipped
             ),
-            details,
-        ));
+        };
+        return Err(CliError::partial_failure(message, details));
     }
 
     if args.json {
@@ -1342,10 +2193,204 @@ fn run_apply(args: ApplyArgs) -> Result<(), CliError> {
 fn run_snapshot(args: SnapshotArgs) -> Result<(), CliError> {
     match args.command {
         SnapshotCommand::Create(args) => run_snapshot_create(args),
+        SnapshotCommand::Diff(args) => run_snapshot_diff(args),
+        SnapshotCommand::Export(args) => run_snapshot_export(args),
+        SnapshotCommand::Import(args) => run_snapshot_import(args),
+    }
+}
+
+fn run_snapshot_export(args: SnapshotExportArgs) -> Result<(), CliError> {
+    let snapshot = load_snapshot(&snapshot_store()?, &args.snapshot_id)?;
+    let bundle = SnapshotBundle::new(&snapshot)
+        .map_err(|error| CliError::operation(format!("failed to export snapshot: {error:#}")))?;
+    let mut contents = serde_json::to_vec_pretty(&bundle)
+        .map_err(|error| CliError::operation(format!("failed to serialize bundle: {error}")))?;
+    contents.push(b'\n');
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&args.out)
+        .map_err(|error| {
+            if error.kind() == io::ErrorKind::AlreadyExists {
+                CliError::usage(format!("{} already exists", args.out.display()))
+            } else {
+                CliError::operation(format!("failed to create {}: {error}", args.out.display()))
+            }
+        })?;
+    file.write_all(&contents)
+        .and_then(|()| file.sync_all())
+        .map_err(|error| {
+            CliError::operation(format!("failed to write {}: {error}", args.out.display()))
+        })?;
+    let exported = SnapshotExported {
+        snapshot: SnapshotSummary::from(&bundle.snapshot),
+        path: args.out,
+        sha256: bundle.sha256,
+    };
+    if args.json {
+        write_json(&JsonEnvelope {
+            api_version: API_VERSION,
+            command: "snapshot",
+            data: exported,
+        })?;
+    } else {
+        println!("Exported snapshot: {}", exported.snapshot.id);
+        println!("Associations: {}", exported.snapshot.associations);
+        println!("SHA-256: {}", exported.sha256);
+        println!("Written to: {}", exported.path.display());
+    }
+    Ok(())
+}
+
+/// A name conflict is the caller's to resolve and a busy lock is reported as
+/// such; anything else is an operation failure.
+fn snapshot_store_error(context: &str, error: anyhow::Error) -> CliError {
+    if error.downcast_ref::<SnapshotNameConflict>().is_some() {
+        CliError::usage(format!("{error}"))
+    } else if let Some(LockError::Busy { .. }) = error.downcast_ref::<LockError>() {
+        CliError::locked(format!("{error}; no snapshot was stored"))
+    } else {
+        CliError::operation(format!("{context}: {error:#}"))
+    }
+}
+
+fn run_snapshot_import(args: SnapshotImportArgs) -> Result<(), CliError> {
+    let contents = std::fs::read(&args.file).map_err(|error| {
+        if error.kind() == io::ErrorKind::NotFound {
+            CliError::not_found(format!("{} was not found", args.file.display()))
+        } else {
+            CliError::operation(format!("failed to read {}: {error}", args.file.display()))
+        }
+    })?;
+    let bundle: SnapshotBundle = serde_json::from_slice(&contents).map_err(|error| {
+        CliError::usage(format!(
+            "{} is not a dutis snapshot bundle: {error}",
+            args.file.display()
+        ))
+    })?;
+    bundle.verify().map_err(|error| {
+        CliError::integrity(
+            format!("snapshot bundle failed verification: {error:#}"),
+            serde_json::json!({"file": args.file, "sha256": bundle.sha256}),
+        )
+    })?;
+    if let Some(name) = &args.name {
+        SnapshotMetadata {
+            name: Some(name.clone()),
+            ..SnapshotMetadata::default()
+        }
+        .normalized()
+        .map_err(|error| CliError::usage(format!("{error:#}")))?;
     }
+    let store = snapshot_store()?;
+    let snapshot = store
+        .import(
+            &bundle,
+            args.name,
+            &LockHolder::current(MutationChannel::Cli, "snapshot", &cli_requester(None)),
+        )
+        .map_err(|error| snapshot_store_error("failed to import snapshot", error))?;
+    let catalog = scan_catalog()?;
+    report_metadata_failures(catalog.metadata_failures);
+    let missing_applications = missing_applications(&snapshot, &catalog.applications)
+        .map_err(|error| CliError::operation(format!("invalid snapshot: {error:#}")))?;
+    let path = store
+        .snapshot_path(&snapshot.id)
+        .map_err(|error| CliError::operation(format!("{error:#}")))?;
+    let imported = SnapshotImported {
+        snapshot: SnapshotSummary::from(&snapshot),
+        path,
+        missing_applications,
+    };
+    if args.json {
+        return write_json(&JsonEnvelope {
+            api_version: API_VERSION,
+            command: "snapshot",
+            data: imported,
+        });
+    }
+    println!("Imported snapshot: {}", imported.snapshot.id);
+    if let Some(name) = &imported.snapshot.name {
+        println!("Name: {name}");
+    }
+    println!("Associations: {}", imported.snapshot.associations);
+    println!("Stored at: {}", imported.path.display());
+    if imported.missing_applications.is_empty() {
+        println!("All referenced applications are installed.");
+    } else {
+        println!("\nApplications missing on this Mac:");
+        for missing in &imported.missing_applications {
+            let associations = missing
+                .associations
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            match &missing.name {
+                Some(name) => println!("  {} ({name}): {associations}", missing.bundle_id),
+                None => println!("  {}: {associations}", missing.bundle_id),
+            }
+        }
+        println!(
+            "Install them before rolling back, or skip their entries with `dutis rollback {} --exclude ...`.",
+            imported.snapshot.id
+        );
+    }
+    Ok(())
 }
 
 fn run_snapshot_create(args: SnapshotCreateArgs) -> Result<(), CliError> {
+    let metadata = SnapshotMetadata {
+        name: args.name,
+        note: args.note,
+        tags: args.tags,
+    }
+    .normalized()
+    .map_err(|error| CliError::usage(format!("{error:#}")))?;
+    if let Some(name) = &metadata.name {
+        let existing = snapshot_store()?
+            .resolve(name)
+            .map_err(|error| CliError::operation(format!("{error:#}")))?;
+        if existing.is_some() {
+            return Err(CliError::usage(format!(
+                "a snapshot named '{name}' already exists"
+            )));
+        }
+    }
+    if args.all {
+        let catalog = scan_catalog()?;
+        report_metadata_failures(catalog.metadata_failures);
+        let (targets, invalid) = declared_targets(&catalog.applications);
+        if invalid > 0 {
+            eprintln!("Warning: skipped {invalid} declared type(s) that are not valid identifiers");
+        }
+        system::duti_version().map_err(|error| CliError::dependency(format!("{error:#}")))?;
+        let show_progress = io::stderr().is_terminal();
+        let workers = thread::available_parallelism()
+            .map(usize::from)
+            .unwrap_or(1)
+            .min(SNAPSHOT_CAPTURE_WORKERS);
+        let capture = capture_targets_parallel(
+            targets,
+            workers,
+            system::query_default_handler,
+            |completed, total| {
+                if show_progress {
+                    eprint!("\rCapturing associations: {completed}/{total}");
+                    if completed == total {
+                        eprintln!();
+                    }
+                }
+            },
+        )
+        .map_err(|error| {
+            if show_progress {
+                eprintln!();
+            }
+            CliError::operation(format!("failed to capture associations: {error:#}"))
+        })?;
+        return store_manual_snapshot(capture.associations, capture.failures, metadata, args.json);
+    }
     let targets = if let Some(path) = args.config {
         DutisConfig::load(&path)
             .map_err(|error| CliError::usage(format!("{error:#}")))?
@@ -1375,18 +2420,34 @@ fn run_snapshot_create(args: SnapshotCreateArgs) -> Result<(), CliError> {
         capture_targets(targets, system::query_default_handler).map_err(|error| {
             CliError::operation(format!("failed to capture associations: {error:#}"))
         })?;
+    store_manual_snapshot(associations, Vec::new(), metadata, args.json)
+}
+
+fn store_manual_snapshot(
+    associations: Vec<SnapshotAssociation>,
+    capture_failures: Vec<CaptureFailure>,
+    metadata: SnapshotMetadata,
+    json: bool,
+) -> Result<(), CliError> {
     let store = snapshot_store()?;
     let snapshot = store
-        .create(SnapshotReason::Manual, None, associations)
-        .map_err(|error| CliError::operation(format!("failed to store snapshot: {error:#}")))?;
+        .create_with_metadata(
+            SnapshotReason::Manual,
+            None,
+            associations,
+            metadata,
+            &LockHolder::current(MutationChannel::Cli, "snapshot", &cli_requester(None)),
+        )
+        .map_err(|error| snapshot_store_error("failed to store snapshot", error))?;
     let path = store
         .snapshot_path(&snapshot.id)
         .map_err(|error| CliError::operation(format!("{error:#}")))?;
     let created = SnapshotCreated {
         snapshot: SnapshotSummary::from(&snapshot),
         path,
+        capture_failures,
     };
-    if args.json {
+    if json {
         write_json(&JsonEnvelope {
             api_version: API_VERSION,
             command: "snapshot",
@@ -1394,17 +2455,315 @@ fn run_snapshot_create(args: SnapshotCreateArgs) -> Result<(), CliError> {
         })?;
     } else {
         println!("Snapshot: {}", created.snapshot.id);
+        if let Some(name) = &created.snapshot.name {
+            println!("Name: {name}");
+        }
         println!("Associations: {}", created.snapshot.associations);
         println!("Stored at: {}", created.path.display());
+        for failure in &created.capture_failures {
+            eprintln!(
+                "Warning: {} was not captured: {}",
+                failure.target, failure.error
+            );
+        }
     }
     Ok(())
 }
 
-fn run_history(args: OutputArgs) -> Result<(), CliError> {
+fn run_snapshot_diff(args: SnapshotDiffArgs) -> Result<(), CliError> {
     let store = snapshot_store()?;
-    let history = store.history().map_err(|error| {
+    let from = load_snapshot(&store, &args.from)?;
+    let diff = match args.to.as_deref() {
+        Some(to) => {
+            let to = load_snapshot(&store, to)?;
+            diff_associations(&from.id, &from.associations, &to.id, &to.associations)
+        }
+        None => {
+            let targets = snapshot_targets(&from)
+                .map_err(|error| CliError::operation(format!("invalid snapshot: {error:#}")))?;
+            system::duti_version().map_err(|error| CliError::dependency(format!("{error:#}")))?;
+            let live =
+                capture_targets(targets, system::query_default_handler).map_err(|error| {
+                    CliError::operation(format!("failed to capture associations: {error:#}"))
+                })?;
+            diff_associations(&from.id, &from.associations, "live", &live)
+        }
+    };
+    if args.json {
+        return write_json(&JsonEnvelope {
+            api_version: API_VERSION,
+            command: "snapshot",
+            data: diff,
+        });
+    }
+    println!("--- {} ({})", from.id, from.created_at);
+    println!("+++ {}", diff.to);
+    let describe = |default: &DefaultApplication| match &default.name {
+        Some(name) => format!("{} ({name})", default.bundle_id),
+        None => default.bundle_id.clone(),
+    };
+    for entry in &diff.entries {
+        let association = entry.association();
+        let missing = match entry.change {
+            SnapshotChange::NotCapturedBefore => Some(&diff.from),
+            SnapshotChange::NotCapturedAfter => Some(&diff.to),
+            _ => None,
+        };
+        if let Some(missing) = missing {
+            println!("?{association}\tnot captured in {missing}");
+            continue;
+        }
+        if let Some(before) = &entry.before {
+            println!("-{association}\t{}", describe(before));
+        }
+        if let Some(after) = &entry.after {
+            println!("+{association}\t{}", describe(after));
+        }
+    }
+    println!(
+        "{} changed, {} added, {} removed, {} unchanged, {} captured on one side only",
+        diff.changed, diff.added, diff.removed, diff.unchanged, diff.not_captured
+    );
+    Ok(())
+}
+
+fn run_undo(args: UndoArgs) -> Result<(), CliError> {
+    if !args.dry_run && !args.yes {
+        return Err(CliError::usage(
+            "refusing to undo without --yes; use --dry-run to preview",
+        ));
+    }
+
+    let audit_store = AuditStore::from_environment().map_err(|error| {
+        CliError::operation(format!("failed to resolve audit storage: {error:#}"))
+    })?;
+    let record = match args.audit_id.as_deref() {
+        Some(id) => {
+            let record = audit_store
+                .load(id)
+                .map_err(|error| CliError::operation(format!("{error:#}")))?
+                .ok_or_else(|| CliError::not_found(format!("audit record '{id}' was not found")))?;
+            if !record.is_undoable() {
+                return Err(CliError::usage(format!(
+                    "audit record '{id}' applied no changes with a safety snapshot; nothing to undo"
+                )));
+            }
+            record
+        }
+        None => {
+            let history = audit_store.history().map_err(|error| {
+                CliError::operation(format!("failed to read audit history: {error:#}"))
+            })?;
+            latest_undoable(&history)
+                .cloned()
+                .ok_or_else(|| CliError::not_found("no audited mutation with changes to undo"))?
+        }
+    };
+    let snapshot_id = record.safety_snapshot_id.as_deref().unwrap_or_default();
+    let store = snapshot_store()?;
+    let snapshot_path = store
+        .snapshot_path(snapshot_id)
+        .map_err(|error| CliError::operation(format!("{error:#}")))?;
+    if !snapshot_path.is_file() {
+        return Err(CliError::not_found(format!(
+            "safety snapshot '{snapshot_id}' for audit record '{}' was not found",
+            record.id
+        )));
+    }
+    let snapshot = store
+        .load(snapshot_id)
+        .map_err(|error| CliError::operation(format!("failed to load snapshot: {error:#}")))?;
+    let catalog = scan_catalog()?;
+    report_metadata_failures(catalog.metadata_failures);
+    system::duti_version().map_err(|error| CliError::dependency(format!("{error:#}")))?;
+    let undo = build_undo_plan(
+        &record,
+        &snapshot,
+        &catalog.applications,
+        system::query_default_handler,
+    )
+    .map_err(|error| CliError::operation(format!("failed to build undo plan: {error:#}")))?;
+
+    if args.dry_run {
+        if args.json {
+            write_json(&JsonEnvelope {
+                api_version: API_VERSION,
+                command: "undo",
+                data: &undo,
+            })?;
+        } else {
+            print_undo_plan(&undo);
+        }
+        return Ok(());
+    }
+
+    if !undo.conflicts.is_empty() && !args.force {
+        let details = serde_json::to_value(&undo)
+            .map_err(|error| CliError::operation(format!("failed to serialize plan: {error}")))?;
+        if !args.json {
+            print_undo_plan(&undo);
+        }
+        return Err(CliError::stale_plan(
+            format!(
+                "{} association(s) changed again after audit record {}; rerun with --force to undo anyway",
+                undo.conflicts.len(),
+                undo.audit_id
+            ),
+            details,
+        ));
+    }
+    if undo.plan.has_unresolved() {
+        let details = serde_json::to_value(&undo)
+            .map_err(|error| CliError::operation(format!("failed to serialize plan: {error}")))?;
+        if !args.json {
+            print_undo_plan(&undo);
+        }
+        return Err(CliError::not_found(format!(
+            "undo contains {} unresolved association(s); no changes were made",
+            undo.plan.summary.unresolved
+        ))
+        .with_details(details));
+    }
+
+    let request = MutationRequest {
+        undoes: Some(undo.audit_id.clone()),
+        ..cli_mutation_request(args.requester.as_deref(), MutationOperation::Rollback)
+    };
+    let result = execute_governed_cli_plan(&undo.plan, SnapshotReason::BeforeRollback, &request)?;
+    if result.report.failed > 0 {
+        let details = serde_json::to_value(&result)
+            .map_err(|error| CliError::operation(format!("failed to serialize report: {error}")))?;
+        if !args.json {
+            print_mutation_result(&result);
+        }
+        return Err(CliError::partial_failure(
+            format!(
+                "undo failed for {} association(s); safety snapshot retained",
+                result.report.failed
+            ),
+            details,
+        ));
+    }
+
+    if args.json {
+        write_json(&JsonEnvelope {
+            api_version: API_VERSION,
+            command: "undo",
+            data: result,
+        })?;
+    } else {
+        print_mutation_result(&result);
+    }
+    Ok(())
+}
+
+fn print_undo_plan(undo: &UndoPlan) {
+    println!(
+        "Undo audit record: {} (snapshot {})\n",
+        undo.audit_id, undo.snapshot_id
+    );
+    if !undo.conflicts.is_empty() {
+        println!("Changed again since this mutation:");
+        for conflict in &undo.conflicts {
+            let association = AssociationTarget {
+                kind: conflict.kind,
+                identifier: conflict.extension.clone(),
+                role: conflict.role,
+            };
+            println!(
+                "  {association}: set to {}, now {}",
+                conflict.applied_bundle_id,
+                conflict
+                    .current_bundle_id
+                    .as_deref()
+                    .unwrap_or("<no default>")
+            );
+        }
+        println!();
+    }
+    print_plan(&undo.plan, false);
+}
+
+fn pick_rollback_entries(plan: AssociationPlan) -> Result<AssociationPlan, CliError> {
+    let candidates = plan
+        .entries
+        .iter()
+        .filter(|entry| entry.action != PlanAction::Unchanged)
+        .collect::<Vec<_>>();
+    if candidates.is_empty() {
+        return Ok(plan);
+    }
+    println!("Associations that differ from the snapshot:");
+    for (index, entry) in candidates.iter().enumerate() {
+        let association = AssociationTarget {
+            kind: entry.kind,
+            identifier: entry.extension.clone(),
+            role: entry.role,
+        };
+        let current = entry
+            .current
+            .as_ref()
+            .map_or("<no default>", |current| current.bundle_id.as_str());
+        println!(
+            "  {}. {association}\t{current} -> {}",
+            index + 1,
+            entry.selector
+        );
+    }
+    let input = read_prompt("Select associations to roll back (for example 1,3-4 or all): ")
+        .map_err(|error| CliError::operation(format!("{error:#}")))?
+        .unwrap_or_default();
+    let picked = parse_picks(&input, candidates.len()).map_err(CliError::usage)?;
+    let entries = candidates
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| picked.contains(index))
+        .map(|(_, entry)| entry.clone())
+        .collect();
+    assemble_plan(plan.config_version, entries)
+        .map_err(|error| CliError::operation(format!("failed to build rollback plan: {error:#}")))
+}
+
+/// Parses a comma-separated list of 1-based numbers and ranges into indexes.
+fn parse_picks(input: &str, count: usize) -> Result<BTreeSet<usize>, String> {
+    let input = input.trim();
+    if input.eq_ignore_ascii_case("all") {
+        return Ok((0..count).collect());
+    }
+    let mut picked = BTreeSet::new();
+    for part in input
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+    {
+        let (start, end) = part.split_once('-').unwrap_or((part, part));
+        let bounds = start
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .zip(end.trim().parse::<usize>().ok())
+            .filter(|(start, end)| 1 <= *start && start <= end && *end <= count);
+        let Some((start, end)) = bounds else {
+            return Err(format!(
+                "invalid selection '{part}'; enter numbers between 1 and {count}"
+            ));
+        };
+        picked.extend(start - 1..end);
+    }
+    if picked.is_empty() {
+        return Err("no associations selected; no changes were made".to_owned());
+    }
+    Ok(picked)
+}
+
+fn run_history(args: HistoryArgs) -> Result<(), CliError> {
+    let store = snapshot_store()?;
+    let mut history = store.history().map_err(|error| {
         CliError::operation(format!("failed to read snapshot history: {error:#}"))
     })?;
+    if let Some(tag) = &args.tag {
+        history.retain(|snapshot| snapshot.tags.contains(tag));
+    }
     if args.json {
         write_json(&JsonEnvelope {
             api_version: API_VERSION,
@@ -1415,10 +2774,23 @@ fn run_history(args: OutputArgs) -> Result<(), CliError> {
         println!("No snapshots found in {}", store.root().display());
     } else {
         for snapshot in history {
+            let mut labels = snapshot.name.clone().into_iter().collect::<Vec<_>>();
+            labels.extend(snapshot.tags.iter().map(|tag| format!("#{tag}")));
             println!(
-                "{}\t{}\t{:?}\t{} association(s)",
-                snapshot.id, snapshot.created_at, snapshot.reason, snapshot.associations
+                "{}\t{}\t{:?}\t{} association(s){}",
+                snapshot.id,
+                snapshot.created_at,
+                snapshot.reason,
+                snapshot.associations,
+                if labels.is_empty() {
+                    String::new()
+                } else {
+                    format!("\t{}", labels.join(" "))
+                }
             );
+            if let Some(note) = &snapshot.note {
+                println!("\t{note}");
+            }
         }
     }
     Ok(())
@@ -1431,28 +2803,32 @@ fn run_rollback(args: RollbackArgs) -> Result<(), CliError> {
         ));
     }
 
-    let store = snapshot_store()?;
-    let snapshot_path = store
-        .snapshot_path(&args.snapshot_id)
+    let selection = RollbackSelection::new(args.only, args.kinds, args.exclude)
         .map_err(|error| CliError::usage(format!("{error:#}")))?;
-    if !snapshot_path.is_file() {
-        return Err(CliError::not_found(format!(
-            "snapshot '{}' was not found",
-            args.snapshot_id
+    let snapshot = load_snapshot(&snapshot_store()?, &args.snapshot_id)?;
+    if !snapshot
+        .associations
+        .iter()
+        .any(|association| selection.includes(association))
+    {
+        return Err(CliError::usage(format!(
+            "the rollback filters match no associations in snapshot '{}'",
+            snapshot.id
         )));
     }
-    let snapshot = store
-        .load(&args.snapshot_id)
-        .map_err(|error| CliError::operation(format!("failed to load snapshot: {error:#}")))?;
     let catalog = scan_catalog()?;
     report_metadata_failures(catalog.metadata_failures);
     system::duti_version().map_err(|error| CliError::dependency(format!("{error:#}")))?;
-    let plan = build_rollback_plan(
+    let mut plan = build_rollback_plan(
         &snapshot,
+        &selection,
         &catalog.applications,
         system::query_default_handler,
     )
     .map_err(|error| CliError::operation(format!("failed to build rollback plan: {error:#}")))?;
+    if args.pick {
+        plan = pick_rollback_entries(plan)?;
+    }
 
     if args.dry_run {
         if args.json {
@@ -1461,6 +2837,7 @@ fn run_rollback(args: RollbackArgs) -> Result<(), CliError> {
                 command: "rollback",
                 data: RollbackPreview {
                     snapshot_id: &snapshot.id,
+                    selection: &selection,
                     plan: &plan,
                 },
             })?;
@@ -1474,6 +2851,7 @@ fn run_rollback(args: RollbackArgs) -> Result<(), CliError> {
     if plan.has_unresolved() {
         let details = serde_json::to_value(RollbackPreview {
             snapshot_id: &snapshot.id,
+            selection: &selection,
             plan: &plan,
         })
         .map_err(|error| CliError::operation(format!("failed to serialize plan: {error}")))?;
@@ -1516,13 +2894,41 @@ fn run_rollback(args: RollbackArgs) -> Result<(), CliError> {
     Ok(())
 }
 
+/// Runs a governed mutation and rejects a result with conflicts.
 fn execute_governed_cli_plan(
     plan: &AssociationPlan,
     reason: SnapshotReason,
     request: &MutationRequest,
 ) -> Result<GovernedMutation, CliError> {
-    execute_governed_plan(plan, reason, request, system::set_default_handler)
-        .map_err(governance_cli_error)
+    execute_governed_plan(
+        plan,
+        reason,
+        request,
+        system::write_default_handler,
+        system::query_default_handler,
+    )
+    .map_err(governance_cli_error)
+    .and_then(reject_conflicts)
+}
+
+/// Entries that changed after review and were left alone make the whole
+/// result a stale plan unless something also failed or other entries were
+/// already changed, which is a partial failure.
+fn reject_conflicts(result: GovernedMutation) -> Result<GovernedMutation, CliError> {
+    if result.report.conflicts > 0 && result.report.failed == 0 {
+        let details = serde_json::to_value(&result)
+            .map_err(|error| CliError::operation(format!("failed to serialize report: {error}")))?;
+        let message = format!(
+            "{} association(s) changed after the plan was reviewed and were not overwritten; {} applied (audit record {})",
+            result.report.conflicts, result.report.applied, result.audit_id
+        );
+        return Err(if result.report.applied > 0 {
+            CliError::partial_failure(message, details)
+        } else {
+            CliError::stale_plan(message, details)
+        });
+    }
+    Ok(result)
 }
 
 fn governance_cli_error(error: dutis::governance::GovernanceError) -> CliError {
@@ -1530,10 +2936,10 @@ fn governance_cli_error(error: dutis::governance::GovernanceError) -> CliError {
         "audit_id": error.audit_id(),
         "violations": error.violations(),
     });
-    if error.kind() == GovernanceErrorKind::PolicyDenied {
-        CliError::policy_denied(error.to_string(), details)
-    } else {
-        CliError::operation(error.to_string()).with_details(details)
+    match error.kind() {
+        GovernanceErrorKind::PolicyDenied => CliError::policy_denied(error.to_string(), details),
+        GovernanceErrorKind::Locked => CliError::locked(error.to_string()).with_details(details),
+        _ => CliError::operation(error.to_string()).with_details(details),
     }
 }
 
@@ -1541,7 +2947,20 @@ fn cli_mutation_request(
     requested_identity: Option<&str>,
     operation: MutationOperation,
 ) -> MutationRequest {
-    let requester = requested_identity
+    MutationRequest {
+        requester: cli_requester(requested_identity),
+        channel: MutationChannel::Cli,
+        operation,
+        explicit_approval: true,
+        approval_token: std::env::var("DUTIS_APPROVAL_TOKEN").ok(),
+        atomic: false,
+        undoes: None,
+    }
+}
+
+/// The requested identity, else `DUTIS_REQUESTER`, else the login name.
+fn cli_requester(requested_identity: Option<&str>) -> String {
+    requested_identity
         .map(str::trim)
         .filter(|value| !value.is_empty())
         .map(str::to_owned)
@@ -1555,14 +2974,7 @@ fn cli_mutation_request(
                 .ok()
                 .filter(|value| !value.trim().is_empty())
         })
-        .unwrap_or_else(|| "local-user".to_owned());
-    MutationRequest {
-        requester,
-        channel: MutationChannel::Cli,
-        operation,
-        explicit_approval: true,
-        approval_token: std::env::var("DUTIS_APPROVAL_TOKEN").ok(),
-    }
+        .unwrap_or_else(|| "local-user".to_owned())
 }
 
 fn snapshot_store() -> Result<SnapshotStore, CliError> {
@@ -1571,17 +2983,40 @@ fn snapshot_store() -> Result<SnapshotStore, CliError> {
     })
 }
 
+/// Loads a snapshot by ID or name, mapping a missing snapshot to `not_found`.
+fn load_snapshot(store: &SnapshotStore, reference: &str) -> Result<Snapshot, CliError> {
+    validate_snapshot_reference(reference)
+        .map_err(|error| CliError::usage(format!("{error:#}")))?;
+    let id = store
+        .resolve(reference)
+        .map_err(|error| CliError::operation(format!("failed to read snapshots: {error:#}")))?
+        .ok_or_else(|| CliError::not_found(format!("snapshot '{reference}' was not found")))?;
+    store
+        .load(&id)
+        .map_err(|error| CliError::operation(format!("failed to load snapshot: {error:#}")))
+}
+
 fn build_declarative_plan(path: &Path) -> Result<AssociationPlan, CliError> {
+    build_declarative_plan_with_catalog(path).map(|(plan, _)| plan)
+}
+
+fn build_declarative_plan_with_catalog(
+    path: &Path,
+) -> Result<(AssociationPlan, ApplicationCatalog), CliError> {
     let config = DutisConfig::load(path).map_err(|error| CliError::usage(format!("{error:#}")))?;
+    build_config_plan_with_catalog(&config)
+}
+
+fn build_config_plan_with_catalog(
+    config: &DutisConfig,
+) -> Result<(AssociationPlan, ApplicationCatalog), CliError> {
     let catalog = scan_catalog()?;
     report_metadata_failures(catalog.metadata_failures);
     system::duti_version().map_err(|error| CliError::dependency(format!("{error:#}")))?;
-    build_plan(
-        &config,
-        &catalog.applications,
-        system::query_default_handler,
-    )
-    .map_err(|error| CliError::operation(format!("failed to inspect current state: {error:#}")))
+    let plan = build_plan(config, &catalog.applications, system::query_default_handler).map_err(
+        |error| CliError::operation(format!("failed to inspect current state: {error:#}")),
+    )?;
+    Ok((plan, catalog))
 }
 
 fn print_plan(plan: &AssociationPlan, changes_only: bool) {
@@ -1655,6 +3090,35 @@ fn print_mutation_result(result: &GovernedMutation) {
         "\nApplied: {}, skipped: {}, failed: {}",
         result.report.applied, result.report.skipped, result.report.failed
     );
+    if result.report.conflicts > 0 {
+        println!(
+            "Conflicts: {} (changed after review; not overwritten)",
+            result.report.conflicts
+        );
+    }
+    if let Some(compensation) = &result.compensation {
+        println!("\nAtomic apply reverted the applied changes:");
+        for entry in &compensation.results {
+            println!(
+                "{} {}{}",
+                if entry.status == ApplyStatus::Applied {
+                    "Reverted"
+                } else {
+                    "NotReverted"
+                },
+                AssociationTarget {
+                    kind: entry.kind,
+                    identifier: entry.extension.clone(),
+                    role: entry.role,
+                },
+                entry
+                    .error
+                    .as_ref()
+                    .map(|error| format!(": {error}"))
+                    .unwrap_or_default()
+            );
+        }
+    }
 }
 
 fn run_doctor(args: OutputArgs) -> Result<(), CliError> {
@@ -1666,6 +3130,7 @@ fn run_doctor(args: OutputArgs) -> Result<(), CliError> {
         duti_version: duti.ok(),
         ready_for_read_only_commands: cfg!(target_os = "macos"),
         ready_for_changes: cfg!(target_os = "macos") && duti_available,
+        monitors: watch_monitors(),
     };
     if args.json {
         write_json(&JsonEnvelope {
@@ -1684,10 +3149,33 @@ fn run_doctor(args: OutputArgs) -> Result<(), CliError> {
             result.ready_for_read_only_commands
         );
         println!("Changes ready: {}", result.ready_for_changes);
+        for monitor in &result.monitors {
+            let config = monitor
+                .status
+                .as_ref()
+                .map_or("unknown configuration", |status| status.config.as_str());
+            println!("Monitor {config}: {}", monitor.summary);
+        }
     }
     Ok(())
 }
 
+fn watch_monitors() -> Vec<WatchStatusReport> {
+    let now = time::OffsetDateTime::now_utc();
+    WatchStatusStore::from_environment()
+        .and_then(|store| store.all())
+        .map(|statuses| {
+            statuses
+                .into_iter()
+                .map(|status| status.report(now))
+                .collect()
+        })
+        .unwrap_or_else(|error| {
+            eprintln!("Warning: failed to read watch status: {error:#}");
+            Vec::new()
+        })
+}
+
 fn scan_catalog() -> Result<ApplicationCatalog, CliError> {
     ApplicationCatalog::scan().map_err(|error| CliError::operation(format!("{error:#}")))
 }
@@ -1920,11 +3408,22 @@ fn set_default_and_report(extension: &str, app: &Application) {
                 &plan,
                 SnapshotReason::BeforeApply,
                 &request,
-                system::set_default_handler,
+                system::write_default_handler,
+                system::query_default_handler,
             )
             .map_err(anyhow::Error::from)
         });
     match result {
+        Ok(result) if !result.report.succeeded() => {
+            let reason = result
+                .report
+                .results
+                .iter()
+                .find_map(|entry| entry.error.as_deref())
+                .unwrap_or("the change could not be verified");
+            println!("❌ Failed to set default application: {reason}");
+            println!("Audit record: {}", result.audit_id);
+        }
         Ok(result) => {
             println!(
                 "✅ Successfully set {} as the default application for .{} files!",
@@ -2006,10 +3505,40 @@ mod tests {
             name: name.to_owned(),
             path: PathBuf::from(format!("/Applications/{name}.app")),
             bundle_id: Some(format!("example.{name}")),
+            version: None,
             extensions: extensions.iter().map(|value| (*value).to_owned()).collect(),
+            utis: Vec::new(),
+            mime_types: Vec::new(),
+            url_schemes: Vec::new(),
         }
     }
 
+    #[test]
+    fn conflicts_after_other_writes_are_a_partial_failure() {
+        let result = |applied: usize| GovernedMutation {
+            audit_id: "1-audit".to_owned(),
+            safety_snapshot_id: None,
+            report: dutis::planner::ApplyReport {
+                plan_digest: "digest".to_owned(),
+                applied,
+                skipped: 0,
+                failed: 0,
+                conflicts: 1,
+                results: Vec::new(),
+            },
+            verification: dutis::governance::VerificationSummary {
+                succeeded: true,
+                applied,
+                skipped: 0,
+                failed: 0,
+                entries: Vec::new(),
+            },
+            compensation: None,
+        };
+        assert_eq!(reject_conflicts(result(0)).unwrap_err().code, 7);
+        assert_eq!(reject_conflicts(result(1)).unwrap_err().code, 8);
+    }
+
     #[test]
     fn searches_extensions_case_insensitively() {
         let applications = vec![app("Editor", &["txt", "MD"]), app("Viewer", &["pdf"])];
@@ -2025,6 +3554,22 @@ mod tests {
         assert_eq!(find_fuzzy_matches(&applications, "pd").len(), 1);
     }
 
+    #[test]
+    fn parses_rollback_picks_as_numbers_and_ranges() {
+        assert_eq!(
+            parse_picks(" 1, 3-4 ", 4)
+                .unwrap()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec![0, 2, 3]
+        );
+        assert_eq!(parse_picks("ALL", 2).unwrap().len(), 2);
+        assert!(parse_picks("", 2).is_err());
+        assert!(parse_picks("0", 2).is_err());
+        assert!(parse_picks("2-1", 2).is_err());
+        assert!(parse_picks("3", 2).is_err());
+    }
+
     #[test]
     fn json_envelope_includes_api_version() {
         let value = JsonEnvelope {
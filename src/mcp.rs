@@ -1,14 +1,14 @@
 use crate::application::{find_apps_for_extension, normalize_extension, ApplicationCatalog};
 use crate::association::{AssociationKind, AssociationTarget, HandlerRole};
 use crate::config::DutisConfig;
-use crate::drift::DriftReport;
+use crate::drift::{attribute_drift, DriftHistory, DriftReport};
 use crate::governance::{
-    execute_governed_plan, AuditStore, GovernanceError, GovernanceErrorKind, LoadedPolicy,
-    MutationChannel, MutationOperation, MutationRequest,
+    execute_governed_plan, parse_audit_time, AuditQuery, AuditStore, GovernanceError,
+    GovernanceErrorKind, LoadedPolicy, MutationChannel, MutationOperation, MutationRequest,
 };
 use crate::planner::{build_plan, AssociationPlan};
-use crate::profiles::{find_profile, profiles, recommend_profile};
-use crate::snapshot::{build_rollback_plan, SnapshotReason, SnapshotStore};
+use crate::profiles::{recommend_profile, ProfileCatalog, ProfileDefinition};
+use crate::snapshot::{build_rollback_plan, RollbackSelection, SnapshotReason, SnapshotStore};
 use crate::system;
 use anyhow::{anyhow, Context, Result};
 use serde::Serialize;
@@ -23,6 +23,8 @@ pub const MCP_AUDIT_SCHEMA_VERSION: u32 = 1;
 const TOOL_API_VERSION: &str = "1";
 const APPROVAL_TOKEN_ENV: &str = "DUTIS_MCP_APPROVAL_TOKEN";
 const MAX_CONFIG_BYTES: usize = 1_048_576;
+const DEFAULT_AUDIT_PAGE_SIZE: usize = 50;
+const MAX_AUDIT_PAGE_SIZE: usize = 500;
 
 pub struct McpOptions {
     allow_writes: bool,
@@ -78,9 +80,8 @@ pub struct McpAuditEvent {
 
 trait McpBackend {
     fn list(&mut self) -> Result<Value>;
-    fn profiles(&mut self) -> Result<Value>;
-    fn profile(&mut self, name: &str) -> Result<Value>;
-    fn recommend(&mut self, name: &str) -> Result<Value>;
+    fn profiles(&mut self) -> Result<ProfileCatalog>;
+    fn recommend(&mut self, profile: &ProfileDefinition) -> Result<Value>;
     fn drift(&mut self, config: &DutisConfig) -> Result<Value>;
     fn query(&mut self, extension: &str) -> Result<Value>;
     fn get(&mut self, extension: &str) -> Result<Value>;
@@ -93,10 +94,15 @@ trait McpBackend {
         request: &MutationRequest,
     ) -> Result<Value>;
     fn history(&mut self) -> Result<Value>;
-    fn rollback_plan(&mut self, snapshot_id: &str) -> Result<AssociationPlan>;
+    fn rollback_plan(
+        &mut self,
+        snapshot_id: &str,
+        selection: &RollbackSelection,
+    ) -> Result<AssociationPlan>;
     fn policy(&mut self) -> Result<Value>;
     fn policy_check(&mut self, plan: &AssociationPlan) -> Result<Value>;
     fn audit(&mut self) -> Result<Value>;
+    fn audit_query(&mut self, query: &AuditQuery, offset: usize, limit: usize) -> Result<Value>;
 }
 
 struct SystemBackend;
@@ -110,21 +116,18 @@ impl McpBackend for SystemBackend {
         }))
     }
 
-    fn profiles(&mut self) -> Result<Value> {
-        serde_json::to_value(profiles()).context("failed to serialize built-in profiles")
-    }
-
-    fn profile(&mut self, name: &str) -> Result<Value> {
-        let profile = find_profile(name).ok_or_else(|| anyhow!("unknown profile '{name}'"))?;
-        serde_json::to_value(profile).context("failed to serialize profile")
+    fn profiles(&mut self) -> Result<ProfileCatalog> {
+        ProfileCatalog::from_environment(&[])
     }
 
-    fn recommend(&mut self, name: &str) -> Result<Value> {
-        let profile = find_profile(name).ok_or_else(|| anyhow!("unknown profile '{name}'"))?;
+    fn recommend(&mut self, profile: &ProfileDefinition) -> Result<Value> {
         let catalog = ApplicationCatalog::scan()?;
         system::duti_version()?;
-        let recommendation =
-            recommend_profile(&profile, &catalog.applications, system::query_default_app)?;
+        let recommendation = recommend_profile(
+            profile,
+            &catalog.applications,
+            system::query_default_handler,
+        )?;
         let policy = LoadedPolicy::from_environment()?;
         Ok(json!({
             "metadata_failures": catalog.metadata_failures,
@@ -140,8 +143,19 @@ impl McpBackend for SystemBackend {
         let plan = build_plan(config, &catalog.applications, system::query_default_handler)?;
         let policy = LoadedPolicy::from_environment()?;
         let assessment = policy.policy.assess(&plan);
-        serde_json::to_value(DriftReport::new(plan, policy.summary(), assessment)?)
-            .context("failed to serialize drift report")
+        let mut report = DriftReport::new(plan, policy.summary(), assessment)?;
+        // Inline configurations have no recorded history, so the drift is
+        // attributed as if it started now, using versions a watcher recorded.
+        let versions = DriftHistory::from_environment()
+            .and_then(|history| history.bundle_versions())
+            .unwrap_or_default();
+        attribute_drift(
+            &mut report,
+            &catalog.applications,
+            &versions,
+            OffsetDateTime::now_utc(),
+        );
+        serde_json::to_value(report).context("failed to serialize drift report")
     }
 
     fn query(&mut self, extension: &str) -> Result<Value> {
@@ -176,8 +190,14 @@ impl McpBackend for SystemBackend {
         reason: SnapshotReason,
         request: &MutationRequest,
     ) -> Result<Value> {
-        let result = execute_governed_plan(plan, reason, request, system::set_default_handler)
-            .map_err(anyhow::Error::from)?;
+        let result = execute_governed_plan(
+            plan,
+            reason,
+            request,
+            system::write_default_handler,
+            system::query_default_handler,
+        )
+        .map_err(anyhow::Error::from)?;
         serde_json::to_value(result).context("failed to serialize mutation result")
     }
 
@@ -186,13 +206,21 @@ impl McpBackend for SystemBackend {
         serde_json::to_value(history).context("failed to serialize snapshot history")
     }
 
-    fn rollback_plan(&mut self, snapshot_id: &str) -> Result<AssociationPlan> {
+    fn rollback_plan(
+        &mut self,
+        snapshot_id: &str,
+        selection: &RollbackSelection,
+    ) -> Result<AssociationPlan> {
         let store = SnapshotStore::from_environment()?;
-        let snapshot = store.load(snapshot_id)?;
+        let id = store
+            .resolve(snapshot_id)?
+            .ok_or_else(|| anyhow!("snapshot '{snapshot_id}' was not found"))?;
+        let snapshot = store.load(&id)?;
         system::duti_version()?;
         let catalog = ApplicationCatalog::scan()?;
         build_rollback_plan(
             &snapshot,
+            selection,
             &catalog.applications,
             system::query_default_handler,
         )
@@ -216,6 +244,11 @@ impl McpBackend for SystemBackend {
         let records = AuditStore::from_environment()?.history()?;
         serde_json::to_value(records).context("failed to serialize mutation audit history")
     }
+
+    fn audit_query(&mut self, query: &AuditQuery, offset: usize, limit: usize) -> Result<Value> {
+        let page = AuditStore::from_environment()?.query(query, offset, Some(limit))?;
+        serde_json::to_value(page).context("failed to serialize mutation audit page")
+    }
 }
 
 struct ToolError {
@@ -331,6 +364,18 @@ impl<B: McpBackend> McpServer<B> {
         }
     }
 
+    fn find_profile(
+        &mut self,
+        arguments: &Map<String, Value>,
+    ) -> std::result::Result<ProfileDefinition, ToolError> {
+        let name = argument_string(arguments, "profile")?;
+        let catalog = self.backend.profiles().map_err(operation_error)?;
+        catalog
+            .find(name)
+            .cloned()
+            .ok_or_else(|| ToolError::new("not_found", format!("unknown profile '{name}'")))
+    }
+
     fn dispatch_tool(
         &mut self,
         name: &str,
@@ -338,26 +383,21 @@ impl<B: McpBackend> McpServer<B> {
     ) -> std::result::Result<Value, ToolError> {
         match name {
             "dutis_list" => self.backend.list().map_err(operation_error),
-            "dutis_profiles" => self.backend.profiles().map_err(operation_error),
+            "dutis_profiles" => {
+                let catalog = self.backend.profiles().map_err(operation_error)?;
+                serde_json::to_value(catalog.profiles())
+                    .context("failed to serialize profiles")
+                    .map_err(operation_error)
+            }
             "dutis_profile" => {
-                let profile = argument_string(arguments, "profile")?;
-                if find_profile(profile).is_none() {
-                    return Err(ToolError::new(
-                        "not_found",
-                        format!("unknown profile '{profile}'"),
-                    ));
-                }
-                self.backend.profile(profile).map_err(operation_error)
+                let profile = self.find_profile(arguments)?;
+                serde_json::to_value(profile)
+                    .context("failed to serialize profile")
+                    .map_err(operation_error)
             }
             "dutis_recommend" => {
-                let profile = argument_string(arguments, "profile")?;
-                if find_profile(profile).is_none() {
-                    return Err(ToolError::new(
-                        "not_found",
-                        format!("unknown profile '{profile}'"),
-                    ));
-                }
-                self.backend.recommend(profile).map_err(operation_error)
+                let profile = self.find_profile(arguments)?;
+                self.backend.recommend(&profile).map_err(operation_error)
             }
             "dutis_drift" => {
                 let config = parse_config(arguments)?;
@@ -394,11 +434,18 @@ impl<B: McpBackend> McpServer<B> {
                 self.backend.policy_check(&plan).map_err(operation_error)
             }
             "dutis_audit" => self.backend.audit().map_err(operation_error),
+            "dutis_audit_query" => {
+                let (query, offset, limit) = parse_audit_query(arguments)?;
+                self.backend
+                    .audit_query(&query, offset, limit)
+                    .map_err(operation_error)
+            }
             "dutis_rollback_plan" => {
                 let snapshot_id = argument_string(arguments, "snapshot_id")?;
+                let selection = parse_rollback_selection(arguments)?;
                 let plan = self
                     .backend
-                    .rollback_plan(snapshot_id)
+                    .rollback_plan(snapshot_id, &selection)
                     .map_err(operation_error)?;
                 serde_json::to_value(plan).map_err(|error| operation_error(error.into()))
             }
@@ -415,6 +462,8 @@ impl<B: McpBackend> McpServer<B> {
                     operation: MutationOperation::Apply,
                     explicit_approval: true,
                     approval_token: Some(approval_token),
+                    atomic: optional(arguments, "atomic")?.unwrap_or(false),
+                    undoes: None,
                 };
                 let result = self
                     .backend
@@ -426,10 +475,11 @@ impl<B: McpBackend> McpServer<B> {
                 let approval_token = self.authorize_write(arguments)?;
                 let requester = argument_string(arguments, "requester")?;
                 let snapshot_id = argument_string(arguments, "snapshot_id")?;
+                let selection = parse_rollback_selection(arguments)?;
                 let expected_digest = argument_string(arguments, "plan_digest")?;
                 let plan = self
                     .backend
-                    .rollback_plan(snapshot_id)
+                    .rollback_plan(snapshot_id, &selection)
                     .map_err(operation_error)?;
                 validate_mutation_plan(&plan, expected_digest)?;
                 let request = MutationRequest {
@@ -438,6 +488,8 @@ impl<B: McpBackend> McpServer<B> {
                     operation: MutationOperation::Rollback,
                     explicit_approval: true,
                     approval_token: Some(approval_token),
+                    atomic: false,
+                    undoes: None,
                 };
                 let result = self
                     .backend
@@ -508,9 +560,40 @@ fn validate_mutation_plan(
 fn validate_apply_result(result: Value) -> std::result::Result<Value, ToolError> {
     let failed = result.get("failed").and_then(Value::as_u64).unwrap_or(0);
     if failed > 0 {
+        let unreverted = result.get("compensation").map(|compensation| {
+            compensation
+                .get("failed")
+                .and_then(Value::as_u64)
+                .unwrap_or(0)
+        });
+        let error = match unreverted {
+            Some(0) => ToolError::new(
+                "rolled_back",
+                format!("{failed} association(s) failed; all applied changes were reverted"),
+            ),
+            Some(unreverted) => ToolError::new(
+                "partial_failure",
+                format!(
+                    "{failed} association(s) failed; {unreverted} applied change(s) could not be reverted"
+                ),
+            ),
+            None => ToolError::new(
+                "partial_failure",
+                format!("{failed} association(s) failed; safety snapshot retained"),
+            ),
+        };
+        return Err(error.with_details(result));
+    }
+    let conflicts = result.get("conflicts").and_then(Value::as_u64).unwrap_or(0);
+    if conflicts > 0 {
+        let applied = result.get("applied").and_then(Value::as_u64).unwrap_or(0);
         return Err(ToolError::new(
-            "partial_failure",
-            format!("{failed} association(s) failed; safety snapshot retained"),
+            if applied > 0 {
+                "partial_failure"
+            } else {
+                "stale_plan"
+            },
+            format!("{conflicts} association(s) changed after review and were not overwritten"),
         )
         .with_details(result));
     }
@@ -568,6 +651,59 @@ fn parse_association(
         .map_err(|error| ToolError::new("invalid_arguments", error.to_string()))
 }
 
+fn optional<T: serde::de::DeserializeOwned>(
+    arguments: &Map<String, Value>,
+    name: &str,
+) -> std::result::Result<Option<T>, ToolError> {
+    arguments
+        .get(name)
+        .map(|value| serde_json::from_value(value.clone()))
+        .transpose()
+        .map_err(|error| ToolError::new("invalid_arguments", format!("invalid '{name}': {error}")))
+}
+
+fn parse_rollback_selection(
+    arguments: &Map<String, Value>,
+) -> std::result::Result<RollbackSelection, ToolError> {
+    RollbackSelection::new(
+        optional(arguments, "only")?.unwrap_or_default(),
+        optional(arguments, "kinds")?.unwrap_or_default(),
+        optional(arguments, "exclude")?.unwrap_or_default(),
+    )
+    .map_err(|error| ToolError::new("invalid_arguments", error.to_string()))
+}
+
+fn parse_audit_query(
+    arguments: &Map<String, Value>,
+) -> std::result::Result<(AuditQuery, usize, usize), ToolError> {
+    let now = OffsetDateTime::now_utc();
+    let time = |name: &str| {
+        optional::<String>(arguments, name)?
+            .map(|value| parse_audit_time(&value, now))
+            .transpose()
+            .map_err(|error| ToolError::new("invalid_arguments", error.to_string()))
+    };
+    let query = AuditQuery {
+        since: time("since")?,
+        until: time("until")?,
+        requester: optional(arguments, "requester")?,
+        channel: optional(arguments, "channel")?,
+        operation: optional(arguments, "operation")?,
+        outcome: optional(arguments, "outcome")?,
+        target: optional(arguments, "target")?,
+        kind: optional(arguments, "kind")?,
+    };
+    let offset = optional(arguments, "offset")?.unwrap_or(0);
+    let limit = optional(arguments, "limit")?.unwrap_or(DEFAULT_AUDIT_PAGE_SIZE);
+    if !(1..=MAX_AUDIT_PAGE_SIZE).contains(&limit) {
+        return Err(ToolError::new(
+            "invalid_arguments",
+            format!("'limit' must be between 1 and {MAX_AUDIT_PAGE_SIZE}"),
+        ));
+    }
+    Ok((query, offset, limit))
+}
+
 fn parse_config(arguments: &Map<String, Value>) -> std::result::Result<DutisConfig, ToolError> {
     let contents = argument_string(arguments, "config_toml")?;
     if contents.len() > MAX_CONFIG_BYTES {
@@ -586,6 +722,7 @@ fn operation_error(error: anyhow::Error) -> ToolError {
             GovernanceErrorKind::PolicyDenied => "policy_denied",
             GovernanceErrorKind::AuditFailed => "audit_failed",
             GovernanceErrorKind::SnapshotFailed => "snapshot_failed",
+            GovernanceErrorKind::Locked => "locked",
         };
         return ToolError::new(kind, governance.to_string()).with_details(json!({
             "audit_id": governance.audit_id(),
@@ -698,9 +835,23 @@ fn tool_definitions(allow_writes: bool) -> Vec<Value> {
         "required": ["config_toml"],
         "additionalProperties": false,
     });
+    let selector_schema = json!({
+        "type": "array",
+        "items": {"type": "string", "minLength": 1},
+        "description": "Extensions, UTIs, MIME types, or URL schemes",
+    });
+    let kinds_schema = json!({
+        "type": "array",
+        "items": {"type": "string", "enum": ["extension", "uti", "mime", "url_scheme"]},
+    });
     let snapshot_schema = json!({
         "type": "object",
-        "properties": {"snapshot_id": {"type": "string", "minLength": 1}},
+        "properties": {
+            "snapshot_id": {"type": "string", "minLength": 1},
+            "only": selector_schema,
+            "kinds": kinds_schema,
+            "exclude": selector_schema
+        },
         "required": ["snapshot_id"],
         "additionalProperties": false,
     });
@@ -725,13 +876,13 @@ fn tool_definitions(allow_writes: bool) -> Vec<Value> {
         ),
         tool_definition(
             "dutis_profiles",
-            "List built-in association profiles and their ordered application candidates.",
+            "List built-in and user-defined association profiles and their ordered application candidates.",
             empty_schema.clone(),
             read_annotations.clone(),
         ),
         tool_definition(
             "dutis_profile",
-            "Inspect one built-in association profile without changing the system.",
+            "Inspect one association profile without changing the system.",
             profile_schema.clone(),
             read_annotations.clone(),
         ),
@@ -799,6 +950,27 @@ fn tool_definitions(allow_writes: bool) -> Vec<Value> {
             "dutis_audit",
             "List persistent local mutation audit records in newest-first order.",
             empty_schema,
+            read_annotations.clone(),
+        ),
+        tool_definition(
+            "dutis_audit_query",
+            "Filter and page mutation audit records newest first, for example changes to https in the last 7d.",
+            json!({
+                "type": "object",
+                "properties": {
+                    "since": {"type": "string", "minLength": 1, "description": "RFC 3339, YYYY-MM-DD, or an age such as 7d"},
+                    "until": {"type": "string", "minLength": 1, "description": "RFC 3339, YYYY-MM-DD, or an age such as 7d"},
+                    "requester": {"type": "string", "minLength": 1},
+                    "channel": {"type": "string", "enum": ["cli", "interactive", "mcp", "watcher"]},
+                    "operation": {"type": "string", "enum": ["set", "apply", "rollback", "remediate"]},
+                    "outcome": {"type": "string", "enum": ["pending", "succeeded", "partial_failure", "rolled_back", "denied", "failed_before_mutation"]},
+                    "target": {"type": "string", "minLength": 1, "description": "Extension, UTI, MIME type, or URL scheme the mutation changed"},
+                    "kind": {"type": "string", "enum": ["extension", "uti", "mime", "url_scheme"], "description": "Only match 'target' as this association kind"},
+                    "offset": {"type": "integer", "minimum": 0, "default": 0},
+                    "limit": {"type": "integer", "minimum": 1, "maximum": MAX_AUDIT_PAGE_SIZE, "default": DEFAULT_AUDIT_PAGE_SIZE}
+                },
+                "additionalProperties": false,
+            }),
             read_annotations,
         ),
     ];
@@ -818,7 +990,8 @@ fn tool_definitions(allow_writes: bool) -> Vec<Value> {
                     "config_toml": {"type": "string", "minLength": 1},
                     "plan_digest": {"type": "string", "minLength": 1},
                     "approval_token": {"type": "string", "minLength": 1},
-                    "requester": {"type": "string", "minLength": 1}
+                    "requester": {"type": "string", "minLength": 1},
+                    "atomic": {"type": "boolean", "description": "Revert applied entries if any entry fails"}
                 },
                 "required": ["config_toml", "plan_digest", "approval_token", "requester"],
                 "additionalProperties": false,
@@ -832,6 +1005,9 @@ fn tool_definitions(allow_writes: bool) -> Vec<Value> {
                 "type": "object",
                 "properties": {
                     "snapshot_id": {"type": "string", "minLength": 1},
+                    "only": selector_schema,
+                    "kinds": kinds_schema,
+                    "exclude": selector_schema,
                     "plan_digest": {"type": "string", "minLength": 1},
                     "approval_token": {"type": "string", "minLength": 1},
                     "requester": {"type": "string", "minLength": 1}
@@ -920,6 +1096,7 @@ mod tests {
     struct FakeBackend {
         plan: AssociationPlan,
         apply_calls: usize,
+        rollback_selection: Option<RollbackSelection>,
     }
 
     impl FakeBackend {
@@ -938,6 +1115,7 @@ mod tests {
                     entries: Vec::new(),
                 },
                 apply_calls: 0,
+                rollback_selection: None,
             }
         }
     }
@@ -947,16 +1125,12 @@ mod tests {
             Ok(json!({"applications": []}))
         }
 
-        fn profiles(&mut self) -> Result<Value> {
-            serde_json::to_value(profiles()).map_err(Into::into)
+        fn profiles(&mut self) -> Result<ProfileCatalog> {
+            Ok(ProfileCatalog::builtin())
         }
 
-        fn profile(&mut self, name: &str) -> Result<Value> {
-            serde_json::to_value(find_profile(name)).map_err(Into::into)
-        }
-
-        fn recommend(&mut self, name: &str) -> Result<Value> {
-            Ok(json!({"profile": name, "proposal_only": true}))
+        fn recommend(&mut self, profile: &ProfileDefinition) -> Result<Value> {
+            Ok(json!({"profile": profile.name, "proposal_only": true}))
         }
 
         fn drift(&mut self, _config: &DutisConfig) -> Result<Value> {
@@ -1005,7 +1179,12 @@ mod tests {
             Ok(json!([]))
         }
 
-        fn rollback_plan(&mut self, _snapshot_id: &str) -> Result<AssociationPlan> {
+        fn rollback_plan(
+            &mut self,
+            _snapshot_id: &str,
+            selection: &RollbackSelection,
+        ) -> Result<AssociationPlan> {
+            self.rollback_selection = Some(selection.clone());
             Ok(self.plan.clone())
         }
 
@@ -1023,6 +1202,22 @@ mod tests {
         fn audit(&mut self) -> Result<Value> {
             Ok(json!([]))
         }
+
+        fn audit_query(
+            &mut self,
+            query: &AuditQuery,
+            offset: usize,
+            limit: usize,
+        ) -> Result<Value> {
+            Ok(json!({
+                "target": query.target,
+                "channel": query.channel,
+                "filtered_by_time": query.since.is_some(),
+                "offset": offset,
+                "limit": limit,
+                "records": []
+            }))
+        }
     }
 
     fn request(id: u64, method: &str, params: Value) -> Value {
@@ -1042,6 +1237,34 @@ mod tests {
         assert_eq!(response["result"]["serverInfo"]["name"], "dutis");
     }
 
+    #[test]
+    fn apply_results_map_failures_and_conflicts_to_error_kinds() {
+        let failure = |compensation: Value| {
+            validate_apply_result(json!({"failed": 1, "compensation": compensation}))
+                .unwrap_err()
+                .kind
+        };
+        assert_eq!(failure(json!({"applied": 2, "failed": 0})), "rolled_back");
+        assert_eq!(
+            failure(json!({"applied": 1, "failed": 1})),
+            "partial_failure"
+        );
+        assert_eq!(
+            validate_apply_result(json!({"failed": 1}))
+                .unwrap_err()
+                .kind,
+            "partial_failure"
+        );
+        assert!(validate_apply_result(json!({"failed": 0})).is_ok());
+        let conflict = |applied: u64| {
+            validate_apply_result(json!({"failed": 0, "conflicts": 1, "applied": applied}))
+                .unwrap_err()
+                .kind
+        };
+        assert_eq!(conflict(0), "stale_plan");
+        assert_eq!(conflict(1), "partial_failure");
+    }
+
     #[test]
     fn read_only_mode_does_not_advertise_write_tools() {
         let mut server = McpServer::new(FakeBackend::new(), McpOptions::read_only());
@@ -1063,6 +1286,7 @@ mod tests {
         assert!(names.contains(&"dutis_handler_get"));
         assert!(names.contains(&"dutis_policy_check"));
         assert!(names.contains(&"dutis_audit"));
+        assert!(names.contains(&"dutis_audit_query"));
         assert!(!names.contains(&"dutis_apply"));
         assert!(!names.contains(&"dutis_rollback"));
     }
@@ -1126,6 +1350,87 @@ mod tests {
         assert_eq!(outcome.audit.unwrap().access, "read");
     }
 
+    #[test]
+    fn audit_query_tool_parses_filters_and_bounds_page_size() {
+        let mut server = McpServer::new(FakeBackend::new(), McpOptions::read_only());
+        let outcome = server.handle(request(
+            8,
+            "tools/call",
+            json!({
+                "name": "dutis_audit_query",
+                "arguments": {"target": "https", "channel": "mcp", "since": "7d", "limit": 10}
+            }),
+        ));
+        let response = outcome.response.unwrap();
+        let data = &response["result"]["structuredContent"]["data"];
+        assert_eq!(data["target"], "https");
+        assert_eq!(data["channel"], "mcp");
+        assert_eq!(data["filtered_by_time"], true);
+        assert_eq!(data["limit"], 10);
+        assert_eq!(outcome.audit.unwrap().access, "read");
+
+        for arguments in [
+            json!({"limit": 0}),
+            json!({"outcome": "exploded"}),
+            json!({"since": "last tuesday"}),
+        ] {
+            let response = server
+                .handle(request(
+                    9,
+                    "tools/call",
+                    json!({"name": "dutis_audit_query", "arguments": arguments}),
+                ))
+                .response
+                .unwrap();
+            assert_eq!(response["result"]["isError"], true);
+            assert_eq!(
+                response["result"]["structuredContent"]["error"]["kind"],
+                "invalid_arguments"
+            );
+        }
+    }
+
+    #[test]
+    fn rollback_plan_tool_forwards_selection_filters() {
+        let mut server = McpServer::new(FakeBackend::new(), McpOptions::read_only());
+        let response = server
+            .handle(request(
+                8,
+                "tools/call",
+                json!({
+                    "name": "dutis_rollback_plan",
+                    "arguments": {"snapshot_id": "1-abc", "only": [".md"], "kinds": ["url_scheme"]}
+                }),
+            ))
+            .response
+            .unwrap();
+        assert_eq!(response["result"]["isError"], false);
+        assert_eq!(
+            server.backend.rollback_selection,
+            Some(RollbackSelection {
+                only: vec![".md".to_owned()],
+                kinds: vec![AssociationKind::UrlScheme],
+                exclude: Vec::new(),
+            })
+        );
+
+        let response = server
+            .handle(request(
+                9,
+                "tools/call",
+                json!({
+                    "name": "dutis_rollback_plan",
+                    "arguments": {"snapshot_id": "1-abc", "exclude": ["not a selector"]}
+                }),
+            ))
+            .response
+            .unwrap();
+        assert_eq!(
+            response["result"]["structuredContent"]["error"]["kind"],
+            "invalid_arguments"
+        );
+    }
+
     #[test]
     fn typed_handler_get_validates_kind_identifier_and_role() {
         let mut server = McpServer::new(FakeBackend::new(), McpOptions::read_only());
@@ -0,0 +1,433 @@
+use crate::association::AssociationKind;
+use crate::drift::{DriftReport, DriftState};
+use crate::governance::serialized_name;
+use crate::planner::ApplyReport;
+use anyhow::{Context, Result};
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use time::OffsetDateTime;
+
+const KINDS: [AssociationKind; 4] = [
+    AssociationKind::Extension,
+    AssociationKind::Uti,
+    AssociationKind::Mime,
+    AssociationKind::UrlScheme,
+];
+const STATES: [DriftState; 4] = [
+    DriftState::InSync,
+    DriftState::DriftDetected,
+    DriftState::Flapping,
+    DriftState::Unresolved,
+];
+const REMEDIATION_OUTCOMES: [&str; 3] = ["succeeded", "partial_failure", "blocked"];
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+const MAX_REQUEST_HEADER_BYTES: u64 = 8 * 1024;
+
+/// Counters and gauges for one `dutis watch` process, rendered in the
+/// Prometheus text exposition format that both scrapers and the node exporter
+/// textfile collector read.
+#[derive(Debug, Default)]
+pub struct WatchMetrics {
+    checks: u64,
+    last_check: Option<OffsetDateTime>,
+    state: Option<DriftState>,
+    changes: [usize; KINDS.len()],
+    unresolved: [usize; KINDS.len()],
+    flapping: usize,
+    catalog_scan: Duration,
+    remediations: [u64; REMEDIATION_OUTCOMES.len()],
+    remediated_entries: u64,
+    failed_entries: u64,
+}
+
+impl WatchMetrics {
+    pub fn observe_check(&mut self, report: &DriftReport, catalog_scan: Duration) {
+        self.checks += 1;
+        self.last_check = OffsetDateTime::parse(
+            &report.checked_at,
+            &time::format_description::well_known::Rfc3339,
+        )
+        .ok()
+        .or_else(|| Some(OffsetDateTime::now_utc()));
+        self.state = Some(report.state);
+        self.changes = count_by_kind(report.changes.iter().map(|entry| entry.kind));
+        self.unresolved = count_by_kind(report.unresolved.iter().map(|entry| entry.kind));
+        self.flapping = report.flapping.len();
+        self.catalog_scan = catalog_scan;
+    }
+
+    /// Records one remediation attempt; `None` means policy or approval
+    /// blocked it before any entry was applied.
+    pub fn observe_remediation(&mut self, report: Option<&ApplyReport>) {
+        let outcome = match report {
+            Some(report) if report.succeeded() => 0,
+            Some(_) => 1,
+            None => 2,
+        };
+        self.remediations[outcome] += 1;
+        if let Some(report) = report {
+            self.remediated_entries += report.applied as u64;
+            self.failed_entries += (report.failed + report.conflicts) as u64;
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+        metric(
+            &mut output,
+            "dutis_watch_checks_total",
+            "counter",
+            "Drift checks completed by this watcher.",
+            &[(String::new(), self.checks.to_string())],
+        );
+        if let Some(last_check) = self.last_check {
+            metric(
+                &mut output,
+                "dutis_watch_last_check_timestamp_seconds",
+                "gauge",
+                "Unix time of the last completed drift check.",
+                &[(String::new(), last_check.unix_timestamp().to_string())],
+            );
+        }
+        if let Some(state) = self.state {
+            metric(
+                &mut output,
+                "dutis_drift_state",
+                "gauge",
+                "1 for the drift state of the last check, 0 for the others.",
+                &STATES
+                    .iter()
+                    .map(|candidate| {
+                        (
+                            format!("state=\"{}\"", serialized_name(candidate)),
+                            u8::from(*candidate == state).to_string(),
+                        )
+                    })
+                    .collect::<Vec<_>>(),
+            );
+            metric(
+                &mut output,
+                "dutis_drift_changes",
+                "gauge",
+                "Associations that differ from the configuration, by kind.",
+                &by_kind(&self.changes),
+            );
+            metric(
+                &mut output,
+                "dutis_drift_unresolved",
+                "gauge",
+                "Selectors that could not be resolved, by kind.",
+                &by_kind(&self.unresolved),
+            );
+            metric(
+                &mut output,
+                "dutis_drift_flapping",
+                "gauge",
+                "Associations currently considered flapping.",
+                &[(String::new(), self.flapping.to_string())],
+            );
+            metric(
+                &mut output,
+                "dutis_catalog_scan_duration_seconds",
+                "gauge",
+                "Time spent scanning installed applications in the last check.",
+                &[(
+                    String::new(),
+                    format!("{:.6}", self.catalog_scan.as_secs_f64()),
+                )],
+            );
+        }
+        metric(
+            &mut output,
+            "dutis_remediations_total",
+            "counter",
+            "Automatic remediation attempts, by outcome.",
+            &REMEDIATION_OUTCOMES
+                .iter()
+                .zip(self.remediations)
+                .map(|(outcome, count)| (format!("outcome=\"{outcome}\""), count.to_string()))
+                .collect::<Vec<_>>(),
+        );
+        metric(
+            &mut output,
+            "dutis_remediation_entries_total",
+            "counter",
+            "Associations handled by automatic remediation, by result.",
+            &[
+                (
+                    "result=\"applied\"".to_owned(),
+                    self.remediated_entries.to_string(),
+                ),
+                (
+                    "result=\"failed\"".to_owned(),
+                    self.failed_entries.to_string(),
+                ),
+            ],
+        );
+        output
+    }
+}
+
+fn count_by_kind(kinds: impl Iterator<Item = AssociationKind>) -> [usize; KINDS.len()] {
+    let mut counts = [0; KINDS.len()];
+    for kind in kinds {
+        if let Some(index) = KINDS.iter().position(|candidate| *candidate == kind) {
+            counts[index] += 1;
+        }
+    }
+    counts
+}
+
+fn by_kind(counts: &[usize; KINDS.len()]) -> Vec<(String, String)> {
+    KINDS
+        .iter()
+        .zip(counts)
+        .map(|(kind, count)| {
+            (
+                format!("kind=\"{}\"", serialized_name(kind)),
+                count.to_string(),
+            )
+        })
+        .collect()
+}
+
+fn metric(output: &mut String, name: &str, kind: &str, help: &str, samples: &[(String, String)]) {
+    let _ = writeln!(output, "# HELP {name} {help}");
+    let _ = writeln!(output, "# TYPE {name} {kind}");
+    for (labels, value) in samples {
+        if labels.is_empty() {
+            let _ = writeln!(output, "{name} {value}");
+        } else {
+            let _ = writeln!(output, "{name}{{{labels}}} {value}");
+        }
+    }
+}
+
+/// Replaces a textfile-collector file atomically so the collector never reads
+/// a partial exposition.
+pub fn write_textfile(path: &Path, contents: &str) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("metrics textfile {} has no file name", path.display()))?;
+    let temporary = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o644);
+    }
+    let mut file = options
+        .open(&temporary)
+        .with_context(|| format!("failed to create {}", temporary.display()))?;
+    file.write_all(contents.as_bytes())
+        .with_context(|| format!("failed to write {}", temporary.display()))?;
+    file.sync_all()?;
+    fs::rename(&temporary, path)
+        .with_context(|| format!("failed to replace metrics textfile {}", path.display()))
+}
+
+/// Serves the latest published exposition at `/metrics` from a background
+/// thread. Requests are answered one at a time; a scrape never blocks checks.
+pub struct MetricsServer {
+    address: SocketAddr,
+    exposition: Arc<Mutex<String>>,
+}
+
+impl MetricsServer {
+    pub fn bind(address: SocketAddr) -> Result<Self> {
+        let listener = TcpListener::bind(address)
+            .with_context(|| format!("failed to listen for metrics on {address}"))?;
+        let address = listener.local_addr()?;
+        let exposition = Arc::new(Mutex::new(String::new()));
+        let shared = Arc::clone(&exposition);
+        std::thread::Builder::new()
+            .name("dutis-metrics".to_owned())
+            .spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let body = shared
+                        .lock()
+                        .map(|exposition| exposition.clone())
+                        .unwrap_or_default();
+                    // A client that disconnects or sends garbage only affects itself.
+                    let _ = respond(stream, &body);
+                }
+            })
+            .context("failed to start the metrics server")?;
+        Ok(Self {
+            address,
+            exposition,
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+
+    pub fn publish(&self, exposition: String) {
+        if let Ok(mut current) = self.exposition.lock() {
+            *current = exposition;
+        }
+    }
+}
+
+fn respond(stream: TcpStream, body: &str) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    stream.set_write_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(stream.try_clone()?.take(MAX_REQUEST_HEADER_BYTES));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+    }
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (
+        parts.next().unwrap_or_default(),
+        parts.next().unwrap_or_default(),
+    );
+    let path = target.split('?').next().unwrap_or_default();
+    let (status, content_type, body) = match (method, path) {
+        ("GET" | "HEAD", "/metrics") => ("200 OK", CONTENT_TYPE, body),
+        ("GET" | "HEAD", _) => ("404 Not Found", "text/plain; charset=utf-8", "not found\n"),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain; charset=utf-8",
+            "method not allowed\n",
+        ),
+    };
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    )?;
+    if method != "HEAD" {
+        stream.write_all(body.as_bytes())?;
+    }
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::governance::{ApprovalMode, PolicyAssessment, PolicySummary};
+    use crate::planner::{assemble_plan, PlanAction, PlanEntry};
+    use std::path::PathBuf;
+
+    fn report(actions: &[(AssociationKind, PlanAction)]) -> DriftReport {
+        let entries = actions
+            .iter()
+            .enumerate()
+            .map(|(index, (kind, action))| PlanEntry {
+                kind: *kind,
+                role: crate::association::HandlerRole::All,
+                extension: format!("id{index}"),
+                selector: "com.example.Editor".to_owned(),
+                current: None,
+                target: None,
+                action: *action,
+                reason: None,
+            })
+            .collect();
+        DriftReport::at(
+            "2026-10-18T12:00:00Z".to_owned(),
+            assemble_plan(1, entries).unwrap(),
+            PolicySummary {
+                path: PathBuf::from("/tmp/policy.toml"),
+                exists: false,
+                digest: "digest".to_owned(),
+                version: 1,
+                approval_mode: ApprovalMode::Explicit,
+                approval_token_configured: false,
+                allowed_extensions: None,
+                allowed_kinds: None,
+                allowed_applications: None,
+                protected_associations: Default::default(),
+                protected_handlers: Vec::new(),
+                retention: Default::default(),
+                atomic_apply: false,
+                verification: Default::default(),
+                notification_sinks: Vec::new(),
+            },
+            PolicyAssessment {
+                allowed: true,
+                approval_mode: ApprovalMode::Explicit,
+                violations: Vec::new(),
+            },
+        )
+    }
+
+    #[test]
+    fn renders_drift_and_remediation_metrics() {
+        let mut metrics = WatchMetrics::default();
+        metrics.observe_check(
+            &report(&[
+                (AssociationKind::Extension, PlanAction::Change),
+                (AssociationKind::Extension, PlanAction::Change),
+                (AssociationKind::UrlScheme, PlanAction::Change),
+                (AssociationKind::Uti, PlanAction::Unchanged),
+            ]),
+            Duration::from_millis(1500),
+        );
+        metrics.observe_remediation(Some(&ApplyReport {
+            plan_digest: "digest".to_owned(),
+            applied: 2,
+            skipped: 0,
+            failed: 1,
+            conflicts: 0,
+            results: Vec::new(),
+        }));
+        metrics.observe_remediation(None);
+        let rendered = metrics.render();
+        for line in [
+            "dutis_watch_checks_total 1",
+            "dutis_watch_last_check_timestamp_seconds 1792324800",
+            "dutis_drift_state{state=\"drift_detected\"} 1",
+            "dutis_drift_state{state=\"in_sync\"} 0",
+            "dutis_drift_changes{kind=\"extension\"} 2",
+            "dutis_drift_changes{kind=\"url_scheme\"} 1",
+            "dutis_drift_unresolved{kind=\"uti\"} 0",
+            "dutis_catalog_scan_duration_seconds 1.500000",
+            "dutis_remediations_total{outcome=\"partial_failure\"} 1",
+            "dutis_remediations_total{outcome=\"blocked\"} 1",
+            "dutis_remediation_entries_total{result=\"applied\"} 2",
+            "dutis_remediation_entries_total{result=\"failed\"} 1",
+            "# TYPE dutis_remediations_total counter",
+        ] {
+            assert!(
+                rendered.lines().any(|rendered| rendered == line),
+                "{line}\n{rendered}"
+            );
+        }
+    }
+
+    #[test]
+    fn serves_the_published_exposition() {
+        let server = MetricsServer::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        server.publish("dutis_watch_checks_total 3\n".to_owned());
+        let get = |path: &str| {
+            let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+            write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+        let response = get("/metrics");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+        assert!(response.contains("version=0.0.4"));
+        assert!(response.ends_with("\r\n\r\ndutis_watch_checks_total 3\n"));
+        assert!(get("/").starts_with("HTTP/1.1 404 "));
+    }
+}
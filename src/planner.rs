@@ -89,6 +89,8 @@ pub enum ApplyStatus {
     Applied,
     Skipped,
     Failed,
+    /// The handler changed after the plan was built and was left untouched.
+    Conflict,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -110,9 +112,35 @@ pub struct ApplyReport {
     pub applied: usize,
     pub skipped: usize,
     pub failed: usize,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub conflicts: usize,
     pub results: Vec<ApplyEntryResult>,
 }
 
+impl ApplyReport {
+    /// True when every entry was applied or already converged.
+    pub fn succeeded(&self) -> bool {
+        self.failed == 0 && self.conflicts == 0
+    }
+
+    pub(crate) fn recount(&mut self) {
+        let count = |status| {
+            self.results
+                .iter()
+                .filter(|result| result.status == status)
+                .count()
+        };
+        self.applied = count(ApplyStatus::Applied);
+        self.skipped = count(ApplyStatus::Skipped);
+        self.failed = count(ApplyStatus::Failed);
+        self.conflicts = count(ApplyStatus::Conflict);
+    }
+}
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
+
 pub fn build_plan<F>(
     config: &DutisConfig,
     applications: &[Application],
@@ -190,9 +218,14 @@ pub fn assemble_plan(config_version: u32, entries: Vec<PlanEntry>) -> Result<Ass
     })
 }
 
-pub fn apply_plan<F>(plan: &AssociationPlan, mut apply: F) -> ApplyReport
+/// Applies every changed entry. Immediately before each change the current
+/// handler is read again; if it no longer matches the plan, the entry is a
+/// conflict and someone else's newer change is kept. An entry whose handler
+/// already is the target is skipped as converged.
+pub fn apply_plan<F, Q>(plan: &AssociationPlan, mut apply: F, mut query_default: Q) -> ApplyReport
 where
     F: FnMut(&AssociationTarget, &str) -> Result<()>,
+    Q: FnMut(&AssociationTarget) -> Result<Option<DefaultApplication>>,
 {
     let mut results = Vec::with_capacity(plan.entries.len());
     for entry in &plan.entries {
@@ -219,8 +252,40 @@ where
                     .as_ref()
                     .map(|target| target.bundle_id.as_str())
                     .expect("resolved plan entries have bundle IDs");
-                match apply(&entry.association(), bundle_id) {
-                    Ok(()) => ApplyEntryResult {
+                let association = entry.association();
+                let planned = entry.current.as_ref().map(|current| &current.bundle_id);
+                let outcome = query_default(&association).and_then(|current| {
+                    let current = current.map(|current| current.bundle_id);
+                    if current.as_deref() == Some(bundle_id) {
+                        Ok(SetOutcome::Converged)
+                    } else if current.as_ref() == planned {
+                        apply(&association, bundle_id).map(|()| SetOutcome::Applied)
+                    } else {
+                        Ok(SetOutcome::Conflict(format!(
+                            "handler changed from {} to {} after the plan was built; not overwritten",
+                            planned.map_or("<no default>", String::as_str),
+                            current.as_deref().unwrap_or("<no default>")
+                        )))
+                    }
+                });
+                match outcome {
+                    Ok(SetOutcome::Conflict(conflict)) => ApplyEntryResult {
+                        kind: entry.kind,
+                        role: entry.role,
+                        extension: entry.extension.clone(),
+                        bundle_id: Some(bundle_id.to_owned()),
+                        status: ApplyStatus::Conflict,
+                        error: Some(conflict),
+                    },
+                    Ok(SetOutcome::Converged) => ApplyEntryResult {
+                        kind: entry.kind,
+                        role: entry.role,
+                        extension: entry.extension.clone(),
+                        bundle_id: Some(bundle_id.to_owned()),
+                        status: ApplyStatus::Skipped,
+                        error: None,
+                    },
+                    Ok(SetOutcome::Applied) => ApplyEntryResult {
                         kind: entry.kind,
                         role: entry.role,
                         extension: entry.extension.clone(),
@@ -242,22 +307,23 @@ where
         results.push(result);
     }
 
-    ApplyReport {
+    let mut report = ApplyReport {
         plan_digest: plan.digest.clone(),
-        applied: results
-            .iter()
-            .filter(|result| result.status == ApplyStatus::Applied)
-            .count(),
-        skipped: results
-            .iter()
-            .filter(|result| result.status == ApplyStatus::Skipped)
-            .count(),
-        failed: results
-            .iter()
-            .filter(|result| result.status == ApplyStatus::Failed)
-            .count(),
+        applied: 0,
+        skipped: 0,
+        failed: 0,
+        conflicts: 0,
         results,
-    }
+    };
+    report.recount();
+    report
+}
+
+/// What the compare-and-set step of `apply_plan` did with one entry.
+enum SetOutcome {
+    Applied,
+    Converged,
+    Conflict(String),
 }
 
 fn unresolved_entry(association: &AssociationTarget, selector: &str, reason: String) -> PlanEntry {
@@ -350,7 +416,11 @@ mod tests {
             name: name.to_owned(),
             path: PathBuf::from(format!("/Applications/{name}.app")),
             bundle_id: Some(bundle_id.to_owned()),
+            version: None,
             extensions: vec!["md".to_owned(), "json".to_owned()],
+            utis: Vec::new(),
+            mime_types: Vec::new(),
+            url_schemes: Vec::new(),
         }
     }
 
@@ -433,17 +503,75 @@ mod tests {
         let applications = vec![app("Editor", "com.example.Editor")];
         let config = config(&[("json", "Editor"), ("md", "Editor")]);
         let plan = build_plan(&config, &applications, |_| Ok(None)).unwrap();
-        let report = apply_plan(&plan, |association, _| {
-            if association.identifier == "json" {
-                anyhow::bail!("simulated failure");
-            }
-            Ok(())
-        });
+        let report = apply_plan(
+            &plan,
+            |association, _| {
+                if association.identifier == "json" {
+                    anyhow::bail!("simulated failure");
+                }
+                Ok(())
+            },
+            |_| Ok(None),
+        );
         assert_eq!(report.applied, 1);
         assert_eq!(report.failed, 1);
         assert_eq!(report.results.len(), 2);
     }
 
+    #[test]
+    fn apply_keeps_handlers_that_changed_after_planning() {
+        let applications = vec![app("Editor", "com.example.Editor")];
+        let config = config(&[("json", "Editor"), ("md", "Editor")]);
+        let plan = build_plan(&config, &applications, |association| {
+            Ok(Some(current(&association.identifier, "com.example.Old")))
+        })
+        .unwrap();
+        let mut applied = Vec::new();
+        let report = apply_plan(
+            &plan,
+            |association, _| {
+                applied.push(association.identifier.clone());
+                Ok(())
+            },
+            |association| {
+                let bundle_id = if association.identifier == "md" {
+                    "com.example.Someone"
+                } else {
+                    "com.example.Old"
+                };
+                Ok(Some(current(&association.identifier, bundle_id)))
+            },
+        );
+        assert_eq!(applied, ["json"]);
+        assert_eq!((report.applied, report.conflicts), (1, 1));
+        assert!(!report.succeeded());
+        let conflict = &report.results[1];
+        assert_eq!(conflict.status, ApplyStatus::Conflict);
+        assert!(conflict
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("from com.example.Old to com.example.Someone"));
+    }
+
+    #[test]
+    fn apply_skips_handlers_that_reached_the_target_after_planning() {
+        let applications = vec![app("Editor", "com.example.Editor")];
+        let config = config(&[("md", "Editor")]);
+        let plan = build_plan(&config, &applications, |association| {
+            Ok(Some(current(&association.identifier, "com.example.Old")))
+        })
+        .unwrap();
+        let report = apply_plan(
+            &plan,
+            |_, _| panic!("converged entry was applied"),
+            |association| Ok(Some(current(&association.identifier, "com.example.Editor"))),
+        );
+        assert_eq!((report.applied, report.conflicts), (0, 0));
+        assert_eq!(report.results[0].status, ApplyStatus::Skipped);
+        assert!(report.succeeded());
+    }
+
     #[test]
     fn converged_apply_is_idempotent() {
         let applications = vec![app("Editor", "com.example.Editor")];
@@ -452,7 +580,11 @@ mod tests {
             Ok(Some(current(&association.identifier, "com.example.Editor")))
         })
         .unwrap();
-        let report = apply_plan(&plan, |_, _| panic!("unchanged entry was applied"));
+        let report = apply_plan(
+            &plan,
+            |_, _| panic!("unchanged entry was applied"),
+            |_| panic!("unchanged entry was queried"),
+        );
         assert_eq!(report.applied, 0);
         assert_eq!(report.skipped, 1);
         assert_eq!(report.failed, 0);
@@ -470,12 +602,14 @@ mod tests {
                     identifier: "https".to_owned(),
                     role: HandlerRole::All,
                     application: "com.example.Editor".to_owned(),
+                    on_drift: None,
                 },
                 AssociationRule {
                     kind: AssociationKind::Uti,
                     identifier: "public.html".to_owned(),
                     role: HandlerRole::Viewer,
                     application: "com.example.Editor".to_owned(),
+                    on_drift: None,
                 },
             ],
         };
@@ -6,10 +6,14 @@ use std::path::Path;
 #[derive(Default)]
 pub struct PlistParser;
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
 pub struct AppMetadata {
     pub bundle_id: Option<String>,
+    pub version: Option<String>,
     pub extensions: Vec<String>,
+    pub utis: Vec<String>,
+    pub mime_types: Vec<String>,
+    pub url_schemes: Vec<String>,
 }
 
 impl PlistParser {
@@ -26,7 +30,15 @@ impl PlistParser {
                 .and_then(|root| root.get("CFBundleIdentifier"))
                 .and_then(Value::as_string)
                 .map(str::to_owned),
+            version: plist
+                .as_dictionary()
+                .and_then(|root| root.get("CFBundleVersion"))
+                .and_then(Value::as_string)
+                .map(str::to_owned),
             extensions: extract_extensions(&plist),
+            utis: extract_utis(&plist),
+            mime_types: extract_mime_types(&plist),
+            url_schemes: extract_url_schemes(&plist),
         })
     }
 }
@@ -43,6 +55,92 @@ fn extract_extensions(plist: &Value) -> Vec<String> {
     extensions.into_iter().collect()
 }
 
+fn extract_utis(plist: &Value) -> Vec<String> {
+    let mut utis = BTreeSet::new();
+    let Some(root) = plist.as_dictionary() else {
+        return Vec::new();
+    };
+
+    for document_type in dictionaries(root, "CFBundleDocumentTypes") {
+        if let Some(values) = document_type.get("LSItemContentTypes") {
+            collect_lowercase_values(values, &mut utis);
+        }
+    }
+    for key in ["UTExportedTypeDeclarations", "UTImportedTypeDeclarations"] {
+        for declaration in dictionaries(root, key) {
+            if let Some(value) = declaration.get("UTTypeIdentifier") {
+                collect_lowercase_values(value, &mut utis);
+            }
+        }
+    }
+    utis.into_iter().collect()
+}
+
+fn extract_mime_types(plist: &Value) -> Vec<String> {
+    let mut mime_types = BTreeSet::new();
+    let Some(root) = plist.as_dictionary() else {
+        return Vec::new();
+    };
+
+    for document_type in dictionaries(root, "CFBundleDocumentTypes") {
+        if let Some(values) = document_type.get("CFBundleTypeMIMETypes") {
+            collect_lowercase_values(values, &mut mime_types);
+        }
+    }
+    for key in ["UTExportedTypeDeclarations", "UTImportedTypeDeclarations"] {
+        for declaration in dictionaries(root, key) {
+            if let Some(values) = declaration
+                .get("UTTypeTagSpecification")
+                .and_then(Value::as_dictionary)
+                .and_then(|tags| tags.get("public.mime-type"))
+            {
+                collect_lowercase_values(values, &mut mime_types);
+            }
+        }
+    }
+    mime_types.into_iter().collect()
+}
+
+fn extract_url_schemes(plist: &Value) -> Vec<String> {
+    let mut schemes = BTreeSet::new();
+    let Some(root) = plist.as_dictionary() else {
+        return Vec::new();
+    };
+
+    for url_type in dictionaries(root, "CFBundleURLTypes") {
+        if let Some(values) = url_type.get("CFBundleURLSchemes") {
+            collect_lowercase_values(values, &mut schemes);
+        }
+    }
+    schemes.into_iter().collect()
+}
+
+fn dictionaries<'a>(root: &'a Dictionary, key: &str) -> impl Iterator<Item = &'a Dictionary> {
+    root.get(key)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_dictionary)
+}
+
+fn collect_lowercase_values(value: &Value, values: &mut BTreeSet<String>) {
+    let mut insert = |value: &str| {
+        let value = value.trim().to_ascii_lowercase();
+        if !value.is_empty() && value != "*" {
+            values.insert(value);
+        }
+    };
+    match value {
+        Value::String(value) => insert(value),
+        Value::Array(items) => {
+            for item in items.iter().filter_map(Value::as_string) {
+                insert(item);
+            }
+        }
+        _ => {}
+    }
+}
+
 fn collect_document_type_extensions(root: &Dictionary, extensions: &mut BTreeSet<String>) {
     let Some(document_types) = root.get("CFBundleDocumentTypes").and_then(Value::as_array) else {
         return;
@@ -172,4 +270,59 @@ mod tests {
         );
         assert_eq!(extract_extensions(&plist), vec!["txt"]);
     }
+
+    #[test]
+    fn extracts_utis_mime_types_and_url_schemes() {
+        let plist = dictionary([
+            (
+                "CFBundleDocumentTypes",
+                Value::Array(vec![dictionary([
+                    (
+                        "LSItemContentTypes",
+                        Value::Array(vec![Value::String("public.plain-text".into())]),
+                    ),
+                    (
+                        "CFBundleTypeMIMETypes",
+                        Value::String("Text/Markdown".into()),
+                    ),
+                ])]),
+            ),
+            (
+                "UTExportedTypeDeclarations",
+                Value::Array(vec![dictionary([
+                    (
+                        "UTTypeIdentifier",
+                        Value::String("com.example.Notebook".into()),
+                    ),
+                    (
+                        "UTTypeTagSpecification",
+                        dictionary([(
+                            "public.mime-type",
+                            Value::String("application/x-notebook".into()),
+                        )]),
+                    ),
+                ])]),
+            ),
+            (
+                "CFBundleURLTypes",
+                Value::Array(vec![dictionary([(
+                    "CFBundleURLSchemes",
+                    Value::Array(vec![
+                        Value::String("HTTPS".into()),
+                        Value::String("http".into()),
+                    ]),
+                )])]),
+            ),
+        ]);
+
+        assert_eq!(
+            extract_utis(&plist),
+            vec!["com.example.notebook", "public.plain-text"]
+        );
+        assert_eq!(
+            extract_mime_types(&plist),
+            vec!["application/x-notebook", "text/markdown"]
+        );
+        assert_eq!(extract_url_schemes(&plist), vec!["http", "https"]);
+    }
 }
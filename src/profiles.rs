@@ -1,30 +1,266 @@
 use crate::application::Application;
-use crate::association::{AssociationKind, HandlerRole};
-use crate::config::{DutisConfig, CONFIG_VERSION};
+use crate::association::{AssociationKind, AssociationTarget, HandlerRole};
+use crate::config::{AssociationRule, DutisConfig, CONFIG_VERSION};
 use crate::planner::{assemble_plan, AssociationPlan, PlanAction, PlanEntry, PlannedApplication};
+use crate::snapshot::SnapshotStore;
 use crate::system::DefaultApplication;
-use anyhow::Result;
-use serde::Serialize;
-use std::collections::BTreeMap;
-use std::path::PathBuf;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const PROFILE_SCHEMA_VERSION: u32 = 1;
+const MAX_PROFILE_NAME_LENGTH: usize = 64;
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize)]
 pub struct ProfileDefinition {
-    pub name: &'static str,
-    pub description: &'static str,
+    pub name: String,
+    pub description: String,
+    /// The file a user-defined profile was loaded from; built-ins have none.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<PathBuf>,
     pub associations: Vec<ProfileAssociation>,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize)]
 pub struct ProfileAssociation {
-    pub extension: &'static str,
+    pub kind: AssociationKind,
+    pub role: HandlerRole,
+    /// The extension, UTI, MIME type, or URL scheme, as in plan entries.
+    pub extension: String,
     pub candidates: Vec<ProfileCandidate>,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+impl ProfileAssociation {
+    pub fn target(&self) -> AssociationTarget {
+        AssociationTarget {
+            kind: self.kind,
+            identifier: self.extension.clone(),
+            role: self.role,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct ProfileCandidate {
-    pub bundle_id: &'static str,
-    pub rationale: &'static str,
+    pub bundle_id: String,
+    pub rationale: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawProfile {
+    version: u32,
+    name: String,
+    description: String,
+    #[serde(default)]
+    associations: Vec<RawProfileAssociation>,
+    #[serde(default)]
+    handlers: Vec<RawProfileHandler>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawProfileAssociation {
+    extension: String,
+    candidates: Vec<ProfileCandidate>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawProfileHandler {
+    kind: AssociationKind,
+    identifier: String,
+    #[serde(default)]
+    role: HandlerRole,
+    candidates: Vec<ProfileCandidate>,
+}
+
+impl ProfileDefinition {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("failed to read profile {}", path.display()))?;
+        let mut profile = Self::parse(&contents)
+            .with_context(|| format!("invalid profile {}", path.display()))?;
+        profile.source = Some(path.to_path_buf());
+        Ok(profile)
+    }
+
+    /// Parses a user-defined profile. Extension preferences use
+    /// `[[associations]]`; UTI, MIME, and URL scheme preferences use
+    /// `[[handlers]]`, mirroring the configuration format.
+    pub fn parse(contents: &str) -> Result<Self> {
+        let parsed: RawProfile = toml::from_str(contents).context("failed to parse TOML")?;
+        if parsed.version != PROFILE_SCHEMA_VERSION {
+            bail!(
+                "unsupported profile version {}; expected {PROFILE_SCHEMA_VERSION}",
+                parsed.version
+            );
+        }
+        let name = parsed.name.trim();
+        validate_profile_name(name)?;
+        let description = parsed.description.trim();
+        if description.is_empty() {
+            bail!("profile description cannot be empty");
+        }
+
+        let targets = parsed
+            .associations
+            .into_iter()
+            .map(|association| {
+                Ok((
+                    AssociationTarget::extension(&association.extension)?,
+                    association.candidates,
+                ))
+            })
+            .chain(parsed.handlers.into_iter().map(|handler| {
+                Ok((
+                    AssociationTarget::new(handler.kind, &handler.identifier, handler.role)?,
+                    handler.candidates,
+                ))
+            }))
+            .collect::<Result<Vec<_>>>()?;
+        if targets.is_empty() {
+            bail!("profile {name} defines no associations or handlers");
+        }
+
+        let mut seen = BTreeSet::new();
+        let mut associations = Vec::with_capacity(targets.len());
+        for (target, candidates) in targets {
+            if !seen.insert(target.clone()) {
+                bail!("duplicate association target {target}");
+            }
+            if candidates.is_empty() {
+                bail!("{target} needs at least one candidate");
+            }
+            let mut bundle_ids = BTreeSet::new();
+            let candidates = candidates
+                .into_iter()
+                .map(|candidate| {
+                    let bundle_id = candidate.bundle_id.trim();
+                    let rationale = candidate.rationale.trim();
+                    if bundle_id.is_empty() {
+                        bail!("candidate bundle identifier for {target} cannot be empty");
+                    }
+                    if rationale.is_empty() {
+                        bail!("candidate {bundle_id} for {target} needs a rationale");
+                    }
+                    if !bundle_ids.insert(bundle_id.to_owned()) {
+                        bail!("duplicate candidate {bundle_id} for {target}");
+                    }
+                    Ok(ProfileCandidate {
+                        bundle_id: bundle_id.to_owned(),
+                        rationale: rationale.to_owned(),
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            associations.push(ProfileAssociation {
+                kind: target.kind,
+                role: target.role,
+                extension: target.identifier,
+                candidates,
+            });
+        }
+
+        Ok(Self {
+            name: name.to_owned(),
+            description: description.to_owned(),
+            source: None,
+            associations,
+        })
+    }
+}
+
+/// Profile names are 1 to 64 ASCII letters, digits, `-`, or `_`.
+pub fn validate_profile_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || name.len() > MAX_PROFILE_NAME_LENGTH
+        || !name
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || matches!(character, '-' | '_'))
+    {
+        bail!("invalid profile name '{name}'; use 1 to {MAX_PROFILE_NAME_LENGTH} letters, digits, '-', or '_'");
+    }
+    Ok(())
+}
+
+/// The built-in profiles together with user-defined ones. A user-defined
+/// profile replaces the built-in profile of the same name.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ProfileCatalog {
+    profiles: Vec<ProfileDefinition>,
+}
+
+impl ProfileCatalog {
+    pub fn builtin() -> Self {
+        Self {
+            profiles: profiles(),
+        }
+    }
+
+    /// Loads `<state>/profiles/*.toml` and then each of `files`.
+    pub fn from_environment(files: &[PathBuf]) -> Result<Self> {
+        let directory = SnapshotStore::from_environment()?.root().join("profiles");
+        Self::load(&directory, files)
+    }
+
+    pub fn load(directory: &Path, files: &[PathBuf]) -> Result<Self> {
+        let mut paths = match fs::read_dir(directory) {
+            Ok(entries) => entries
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<std::io::Result<Vec<_>>>()
+                .with_context(|| format!("failed to read {}", directory.display()))?
+                .into_iter()
+                .filter(|path| {
+                    path.extension()
+                        .is_some_and(|extension| extension == "toml")
+                })
+                .collect::<Vec<_>>(),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(error) => {
+                return Err(error)
+                    .with_context(|| format!("failed to read {}", directory.display()))
+            }
+        };
+        paths.sort();
+        paths.extend(files.iter().cloned());
+
+        let mut profiles = Self::builtin().profiles;
+        let mut user_defined = BTreeMap::<String, PathBuf>::new();
+        for path in paths {
+            let profile = ProfileDefinition::load(&path)?;
+            if let Some(previous) =
+                user_defined.insert(profile.name.to_ascii_lowercase(), path.clone())
+            {
+                bail!(
+                    "profile '{}' is defined in both {} and {}",
+                    profile.name,
+                    previous.display(),
+                    path.display()
+                );
+            }
+            match profiles
+                .iter_mut()
+                .find(|existing| existing.name.eq_ignore_ascii_case(&profile.name))
+            {
+                Some(existing) => *existing = profile,
+                None => profiles.push(profile),
+            }
+        }
+        Ok(Self { profiles })
+    }
+
+    pub fn profiles(&self) -> &[ProfileDefinition] {
+        &self.profiles
+    }
+
+    pub fn find(&self, name: &str) -> Option<&ProfileDefinition> {
+        self.profiles
+            .iter()
+            .find(|profile| profile.name.eq_ignore_ascii_case(name.trim()))
+    }
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
@@ -40,6 +276,8 @@ pub struct CandidateEvidence {
     pub bundle_id: String,
     pub priority: usize,
     pub installed_paths: Vec<PathBuf>,
+    /// Whether the bundle declares the extension, UTI, MIME type, or URL
+    /// scheme being recommended.
     pub declares_extension: bool,
     pub selected: bool,
     pub rationale: String,
@@ -47,6 +285,8 @@ pub struct CandidateEvidence {
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize)]
 pub struct AssociationRecommendation {
+    pub kind: AssociationKind,
+    pub role: HandlerRole,
     pub extension: String,
     pub action: RecommendationAction,
     pub current: Option<DefaultApplication>,
@@ -55,6 +295,17 @@ pub struct AssociationRecommendation {
     pub evidence: Vec<CandidateEvidence>,
 }
 
+impl AssociationRecommendation {
+    /// The association being recommended; `target` is the proposed handler.
+    pub fn target_association(&self) -> AssociationTarget {
+        AssociationTarget {
+            kind: self.kind,
+            identifier: self.extension.clone(),
+            role: self.role,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Serialize)]
 pub struct RecommendationSummary {
     pub total: usize,
@@ -75,6 +326,7 @@ pub struct ProfileRecommendation {
     pub plan: AssociationPlan,
 }
 
+/// The built-in profiles, without user-defined ones; see [`ProfileCatalog`].
 pub fn profiles() -> Vec<ProfileDefinition> {
     vec![
         developer_profile(),
@@ -96,14 +348,16 @@ pub fn recommend_profile<F>(
     mut query_default: F,
 ) -> Result<ProfileRecommendation>
 where
-    F: FnMut(&str) -> Result<Option<DefaultApplication>>,
+    F: FnMut(&AssociationTarget) -> Result<Option<DefaultApplication>>,
 {
     let mut proposed_associations = BTreeMap::new();
+    let mut proposed_handlers = Vec::new();
     let mut plan_entries = Vec::new();
     let mut recommendations = Vec::with_capacity(profile.associations.len());
 
     for association in &profile.associations {
-        let current = query_default(association.extension)?;
+        let association_target = association.target();
+        let current = query_default(&association_target)?;
         let candidates = association
             .candidates
             .iter()
@@ -112,24 +366,21 @@ where
                 let matches = applications
                     .iter()
                     .filter(|application| {
-                        application.bundle_id.as_deref() == Some(candidate.bundle_id)
+                        application.bundle_id.as_deref() == Some(candidate.bundle_id.as_str())
                     })
                     .collect::<Vec<_>>();
                 CandidateEvidence {
-                    bundle_id: candidate.bundle_id.to_owned(),
+                    bundle_id: candidate.bundle_id.clone(),
                     priority: index + 1,
                     installed_paths: matches
                         .iter()
                         .map(|application| application.path.clone())
                         .collect(),
-                    declares_extension: matches.iter().any(|application| {
-                        application
-                            .extensions
-                            .iter()
-                            .any(|extension| extension.eq_ignore_ascii_case(association.extension))
-                    }),
+                    declares_extension: matches
+                        .iter()
+                        .any(|application| declares(application, &association_target)),
                     selected: false,
-                    rationale: candidate.rationale.to_owned(),
+                    rationale: candidate.rationale.clone(),
                 }
             })
             .collect::<Vec<_>>();
@@ -155,14 +406,14 @@ where
             let application = applications
                 .iter()
                 .find(|application| {
-                    application.bundle_id.as_deref() == Some(selected.bundle_id)
+                    application.bundle_id.as_deref() == Some(selected.bundle_id.as_str())
                         && application.path == evidence[index].installed_paths[0]
                 })
                 .expect("selected profile candidate has one installed application");
             let target = PlannedApplication::from_application(application)
                 .expect("profile candidates have bundle identifiers");
-            let keep_current =
-                current.as_ref().map(|value| value.bundle_id.as_str()) == Some(selected.bundle_id);
+            let keep_current = current.as_ref().map(|value| value.bundle_id.as_str())
+                == Some(selected.bundle_id.as_str());
             let action = if keep_current {
                 RecommendationAction::KeepCurrent
             } else {
@@ -175,19 +426,30 @@ where
                 )
             } else {
                 format!(
-                    "Recommend {} because it is the highest-priority uniquely installed candidate for .{}: {}",
-                    application.name, association.extension, selected.rationale
+                    "Recommend {} because it is the highest-priority uniquely installed candidate for {}: {}",
+                    application.name, association_target, selected.rationale
                 )
             };
-            proposed_associations.insert(
-                association.extension.to_owned(),
-                selected.bundle_id.to_owned(),
-            );
+            // The compact `[associations]` table always means role `all`.
+            if association.kind == AssociationKind::Extension
+                && association.role == HandlerRole::All
+            {
+                proposed_associations
+                    .insert(association.extension.clone(), selected.bundle_id.clone());
+            } else {
+                proposed_handlers.push(AssociationRule {
+                    kind: association.kind,
+                    identifier: association.extension.clone(),
+                    role: association.role,
+                    application: selected.bundle_id.clone(),
+                    on_drift: None,
+                });
+            }
             plan_entries.push(PlanEntry {
-                kind: AssociationKind::Extension,
-                role: HandlerRole::All,
-                extension: association.extension.to_owned(),
-                selector: selected.bundle_id.to_owned(),
+                kind: association.kind,
+                role: association.role,
+                extension: association.extension.clone(),
+                selector: selected.bundle_id.clone(),
                 current: current.clone(),
                 target: Some(target.clone()),
                 action: if keep_current {
@@ -203,14 +465,15 @@ where
                 RecommendationAction::Unavailable,
                 None,
                 format!(
-                    "No uniquely installed candidate is available for .{}; no change is proposed.",
-                    association.extension
+                    "No uniquely installed candidate is available for {association_target}; no change is proposed."
                 ),
             )
         };
 
         recommendations.push(AssociationRecommendation {
-            extension: association.extension.to_owned(),
+            kind: association.kind,
+            role: association.role,
+            extension: association.extension.clone(),
             action,
             current,
             target,
@@ -219,12 +482,22 @@ where
         });
     }
 
+    proposed_handlers.sort_by(|left, right| {
+        (&left.kind, &left.identifier, &left.role).cmp(&(
+            &right.kind,
+            &right.identifier,
+            &right.role,
+        ))
+    });
     let proposed_config = DutisConfig {
         version: CONFIG_VERSION,
         associations: proposed_associations,
-        handlers: Vec::new(),
+        handlers: proposed_handlers,
     };
     let proposed_toml = toml::to_string_pretty(&proposed_config)?;
+    // Order entries as `build_plan` does, so applying the proposed
+    // configuration reproduces this digest.
+    plan_entries.sort_by_key(PlanEntry::association);
     let plan = assemble_plan(CONFIG_VERSION, plan_entries)?;
     let summary = RecommendationSummary {
         total: recommendations.len(),
@@ -247,8 +520,8 @@ where
     };
 
     Ok(ProfileRecommendation {
-        profile: profile.name.to_owned(),
-        description: profile.description.to_owned(),
+        profile: profile.name.clone(),
+        description: profile.description.clone(),
         summary,
         recommendations,
         proposed_config,
@@ -257,10 +530,24 @@ where
     })
 }
 
+fn declares(application: &Application, target: &AssociationTarget) -> bool {
+    let declared = match target.kind {
+        AssociationKind::Extension => &application.extensions,
+        AssociationKind::Uti => &application.utis,
+        AssociationKind::Mime => &application.mime_types,
+        AssociationKind::UrlScheme => &application.url_schemes,
+    };
+    declared
+        .iter()
+        .any(|value| value.eq_ignore_ascii_case(&target.identifier))
+}
+
 fn developer_profile() -> ProfileDefinition {
     ProfileDefinition {
-        name: "developer",
-        description: "Source code, structured data, scripts, and technical documentation.",
+        name: "developer".to_owned(),
+        description: "Source code, structured data, scripts, and technical documentation."
+            .to_owned(),
+        source: None,
         associations: vec![
             developer_association("md"),
             developer_association("json"),
@@ -276,10 +563,10 @@ fn developer_profile() -> ProfileDefinition {
     }
 }
 
-fn developer_association(extension: &'static str) -> ProfileAssociation {
-    ProfileAssociation {
+fn developer_association(extension: &str) -> ProfileAssociation {
+    extension_association(
         extension,
-        candidates: vec![
+        vec![
             candidate(
                 "dev.zed.Zed",
                 "Fast native editor with strong project and language tooling.",
@@ -301,21 +588,22 @@ fn developer_association(extension: &'static str) -> ProfileAssociation {
                 "Built-in fallback for plain-text-compatible files.",
             ),
         ],
-    }
+    )
 }
 
 fn designer_profile() -> ProfileDefinition {
     ProfileDefinition {
-        name: "designer",
-        description: "Images, vector assets, design documents, and PDFs.",
+        name: "designer".to_owned(),
+        description: "Images, vector assets, design documents, and PDFs.".to_owned(),
+        source: None,
         associations: vec![
             image_association("png"),
             image_association("jpg"),
             image_association("jpeg"),
             image_association("svg"),
-            ProfileAssociation {
-                extension: "pdf",
-                candidates: vec![
+            extension_association(
+                "pdf",
+                vec![
                     candidate(
                         "com.apple.Preview",
                         "Built-in fast PDF viewing and annotation.",
@@ -325,15 +613,15 @@ fn designer_profile() -> ProfileDefinition {
                         "Advanced PDF editing and production workflows.",
                     ),
                 ],
-            },
+            ),
         ],
     }
 }
 
-fn image_association(extension: &'static str) -> ProfileAssociation {
-    ProfileAssociation {
+fn image_association(extension: &str) -> ProfileAssociation {
+    extension_association(
         extension,
-        candidates: vec![
+        vec![
             candidate(
                 "com.pixelmatorteam.pixelmator.x",
                 "Native image editing workflow for design assets.",
@@ -343,13 +631,14 @@ fn image_association(extension: &'static str) -> ProfileAssociation {
                 "Built-in lightweight image inspection.",
             ),
         ],
-    }
+    )
 }
 
 fn media_profile() -> ProfileDefinition {
     ProfileDefinition {
-        name: "media",
-        description: "Audio and video playback with native and broad-codec options.",
+        name: "media".to_owned(),
+        description: "Audio and video playback with native and broad-codec options.".to_owned(),
+        source: None,
         associations: vec![
             media_association("mp3"),
             media_association("m4a"),
@@ -362,10 +651,10 @@ fn media_profile() -> ProfileDefinition {
     }
 }
 
-fn media_association(extension: &'static str) -> ProfileAssociation {
-    ProfileAssociation {
+fn media_association(extension: &str) -> ProfileAssociation {
+    extension_association(
         extension,
-        candidates: vec![
+        vec![
             candidate(
                 "com.apple.Music",
                 "Built-in music library and audio playback.",
@@ -376,13 +665,13 @@ fn media_association(extension: &'static str) -> ProfileAssociation {
                 "Native macOS player with broad format support.",
             ),
         ],
-    }
+    )
 }
 
-fn video_association(extension: &'static str) -> ProfileAssociation {
-    ProfileAssociation {
+fn video_association(extension: &str) -> ProfileAssociation {
+    extension_association(
         extension,
-        candidates: vec![
+        vec![
             candidate(
                 "com.colliderli.iina",
                 "Native macOS player with broad codec support.",
@@ -393,64 +682,74 @@ fn video_association(extension: &'static str) -> ProfileAssociation {
                 "Built-in playback for common Apple-supported formats.",
             ),
         ],
-    }
+    )
 }
 
 fn minimal_profile() -> ProfileDefinition {
     ProfileDefinition {
-        name: "minimal",
-        description: "Built-in macOS applications with no third-party dependency.",
+        name: "minimal".to_owned(),
+        description: "Built-in macOS applications with no third-party dependency.".to_owned(),
+        source: None,
         associations: vec![
-            ProfileAssociation {
-                extension: "txt",
-                candidates: vec![candidate(
+            extension_association(
+                "txt",
+                vec![candidate(
                     "com.apple.TextEdit",
                     "Built-in macOS plain-text editor.",
                 )],
-            },
-            ProfileAssociation {
-                extension: "rtf",
-                candidates: vec![candidate(
+            ),
+            extension_association(
+                "rtf",
+                vec![candidate(
                     "com.apple.TextEdit",
                     "Built-in macOS rich-text editor.",
                 )],
-            },
-            ProfileAssociation {
-                extension: "pdf",
-                candidates: vec![candidate(
+            ),
+            extension_association(
+                "pdf",
+                vec![candidate(
                     "com.apple.Preview",
                     "Built-in macOS document viewer.",
                 )],
-            },
-            ProfileAssociation {
-                extension: "png",
-                candidates: vec![candidate(
+            ),
+            extension_association(
+                "png",
+                vec![candidate(
                     "com.apple.Preview",
                     "Built-in macOS image viewer.",
                 )],
-            },
-            ProfileAssociation {
-                extension: "jpg",
-                candidates: vec![candidate(
+            ),
+            extension_association(
+                "jpg",
+                vec![candidate(
                     "com.apple.Preview",
                     "Built-in macOS image viewer.",
                 )],
-            },
-            ProfileAssociation {
-                extension: "mov",
-                candidates: vec![candidate(
+            ),
+            extension_association(
+                "mov",
+                vec![candidate(
                     "com.apple.QuickTimePlayerX",
                     "Built-in macOS media player.",
                 )],
-            },
+            ),
         ],
     }
 }
 
-fn candidate(bundle_id: &'static str, rationale: &'static str) -> ProfileCandidate {
+fn extension_association(extension: &str, candidates: Vec<ProfileCandidate>) -> ProfileAssociation {
+    ProfileAssociation {
+        kind: AssociationKind::Extension,
+        role: HandlerRole::All,
+        extension: extension.to_owned(),
+        candidates,
+    }
+}
+
+fn candidate(bundle_id: &str, rationale: &str) -> ProfileCandidate {
     ProfileCandidate {
-        bundle_id,
-        rationale,
+        bundle_id: bundle_id.to_owned(),
+        rationale: rationale.to_owned(),
     }
 }
 
@@ -463,7 +762,11 @@ mod tests {
             name: name.to_owned(),
             path: PathBuf::from(format!("/Applications/{name}.app")),
             bundle_id: Some(bundle_id.to_owned()),
+            version: None,
             extensions: extensions.iter().map(|value| (*value).to_owned()).collect(),
+            utis: Vec::new(),
+            mime_types: Vec::new(),
+            url_schemes: Vec::new(),
         }
     }
 
@@ -472,7 +775,7 @@ mod tests {
         assert_eq!(
             profiles()
                 .iter()
-                .map(|profile| profile.name)
+                .map(|profile| profile.name.as_str())
                 .collect::<Vec<_>>(),
             vec!["developer", "designer", "media", "minimal"]
         );
@@ -483,25 +786,26 @@ mod tests {
     #[test]
     fn keeps_a_compatible_current_handler_to_minimize_churn() {
         let profile = ProfileDefinition {
-            name: "test",
-            description: "test profile",
-            associations: vec![ProfileAssociation {
-                extension: "md",
-                candidates: vec![
+            name: "test".to_owned(),
+            description: "test profile".to_owned(),
+            source: None,
+            associations: vec![extension_association(
+                "md",
+                vec![
                     candidate("com.example.First", "first"),
                     candidate("com.example.Current", "current"),
                 ],
-            }],
+            )],
         };
         let applications = vec![
             app("First", "com.example.First", &["md"]),
             app("Current", "com.example.Current", &["md"]),
         ];
-        let recommendation = recommend_profile(&profile, &applications, |extension| {
+        let recommendation = recommend_profile(&profile, &applications, |association| {
             Ok(Some(DefaultApplication {
-                kind: AssociationKind::Extension,
-                role: HandlerRole::All,
-                extension: extension.to_owned(),
+                kind: association.kind,
+                role: association.role,
+                extension: association.identifier.clone(),
                 name: Some("Current".to_owned()),
                 path: Some("/Applications/Current.app".to_owned()),
                 bundle_id: "com.example.Current".to_owned(),
@@ -523,15 +827,16 @@ mod tests {
     #[test]
     fn selects_first_uniquely_installed_candidate_with_evidence() {
         let profile = ProfileDefinition {
-            name: "test",
-            description: "test profile",
-            associations: vec![ProfileAssociation {
-                extension: "md",
-                candidates: vec![
+            name: "test".to_owned(),
+            description: "test profile".to_owned(),
+            source: None,
+            associations: vec![extension_association(
+                "md",
+                vec![
                     candidate("com.example.Missing", "preferred"),
                     candidate("com.example.Editor", "available"),
                 ],
-            }],
+            )],
         };
         let recommendation = recommend_profile(
             &profile,
@@ -550,12 +855,13 @@ mod tests {
     #[test]
     fn leaves_ambiguous_or_missing_candidates_out_of_the_plan() {
         let profile = ProfileDefinition {
-            name: "test",
-            description: "test profile",
-            associations: vec![ProfileAssociation {
-                extension: "md",
-                candidates: vec![candidate("com.example.Editor", "candidate")],
-            }],
+            name: "test".to_owned(),
+            description: "test profile".to_owned(),
+            source: None,
+            associations: vec![extension_association(
+                "md",
+                vec![candidate("com.example.Editor", "candidate")],
+            )],
         };
         let applications = vec![
             app("Editor", "com.example.Editor", &["md"]),
@@ -569,4 +875,158 @@ mod tests {
         assert_eq!(recommendation.plan.summary.total, 0);
         assert!(recommendation.proposed_config.associations.is_empty());
     }
+
+    const DATA_SCIENCE: &str = r#"
+        version = 1
+        name = "data-science"
+        description = "Notebooks, tabular data, and analysis scripts."
+
+        [[associations]]
+        extension = ".IPYNB"
+        candidates = [
+            { bundle_id = "com.example.Notebook", rationale = "Runs notebooks with kernels." },
+            { bundle_id = "com.microsoft.VSCode", rationale = "Edits notebooks as JSON." },
+        ]
+
+        [[handlers]]
+        kind = "uti"
+        identifier = "public.comma-separated-values-text"
+        role = "editor"
+        candidates = [{ bundle_id = "com.example.Sheets", rationale = "Tabular editing." }]
+    "#;
+
+    #[test]
+    fn parses_user_defined_profiles_with_typed_handlers() {
+        let profile = ProfileDefinition::parse(DATA_SCIENCE).unwrap();
+        assert_eq!(profile.name, "data-science");
+        assert_eq!(profile.associations[0].extension, "ipynb");
+        assert_eq!(profile.associations[1].kind, AssociationKind::Uti);
+        assert_eq!(profile.associations[1].role, HandlerRole::Editor);
+
+        let recommendation = recommend_profile(
+            &profile,
+            &[Application {
+                utis: vec!["public.comma-separated-values-text".to_owned()],
+                ..app("Sheets", "com.example.Sheets", &[])
+            }],
+            |_| Ok(None),
+        )
+        .unwrap();
+        assert_eq!(recommendation.summary.unavailable, 1);
+        assert!(recommendation.recommendations[1].evidence[0].declares_extension);
+        assert_eq!(
+            recommendation.proposed_config.handlers[0].identifier,
+            "public.comma-separated-values-text"
+        );
+        assert_eq!(
+            DutisConfig::parse(&recommendation.proposed_toml).unwrap(),
+            recommendation.proposed_config
+        );
+
+        for invalid in [
+            DATA_SCIENCE.replace("version = 1", "version = 2"),
+            DATA_SCIENCE.replace("\"data-science\"", "\"data science\""),
+            DATA_SCIENCE.replace("Tabular editing.", " "),
+            DATA_SCIENCE.replace("com.microsoft.VSCode", "com.example.Notebook"),
+            DATA_SCIENCE.replace(
+                "role = \"editor\"",
+                "role = \"editor\"\napplication = \"x\"",
+            ),
+            DATA_SCIENCE
+                .replace("kind = \"uti\"", "kind = \"extension\"")
+                .replace("public.comma-separated-values-text", "ipynb")
+                .replace("role = \"editor\"", ""),
+        ] {
+            assert!(ProfileDefinition::parse(&invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn role_specific_extension_handlers_round_trip_through_the_proposal() {
+        let profile = ProfileDefinition::parse(
+            r#"
+                version = 1
+                name = "tables"
+                description = "Tabular data."
+
+                [[associations]]
+                extension = "tsv"
+                candidates = [{ bundle_id = "com.example.Viewer", rationale = "Opens tables." }]
+
+                [[associations]]
+                extension = "csv"
+                candidates = [{ bundle_id = "com.example.Viewer", rationale = "Opens tables." }]
+
+                [[handlers]]
+                kind = "extension"
+                identifier = "csv"
+                role = "editor"
+                candidates = [{ bundle_id = "com.example.Sheets", rationale = "Edits tables." }]
+            "#,
+        )
+        .unwrap();
+        let applications = [
+            app("Viewer", "com.example.Viewer", &["csv", "tsv"]),
+            app("Sheets", "com.example.Sheets", &["csv"]),
+        ];
+        let recommendation = recommend_profile(&profile, &applications, |_| Ok(None)).unwrap();
+        assert_eq!(
+            recommendation.proposed_config.associations["csv"],
+            "com.example.Viewer"
+        );
+        assert_eq!(
+            recommendation.proposed_config.handlers[0].role,
+            HandlerRole::Editor
+        );
+
+        let config = DutisConfig::parse(&recommendation.proposed_toml).unwrap();
+        assert_eq!(config, recommendation.proposed_config);
+        let rebuilt = crate::planner::build_plan(&config, &applications, |_| Ok(None)).unwrap();
+        assert_eq!(rebuilt.digest, recommendation.plan.digest);
+    }
+
+    #[test]
+    fn user_profiles_replace_builtins_and_reject_duplicate_names() {
+        let unique = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let root =
+            std::env::temp_dir().join(format!("dutis-profiles-{}-{unique}", std::process::id()));
+        let directory = root.join("profiles");
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("data-science.toml"), DATA_SCIENCE).unwrap();
+        fs::write(directory.join("notes.txt"), "ignored").unwrap();
+        let minimal = root.join("minimal.toml");
+        fs::write(
+            &minimal,
+            DATA_SCIENCE.replace("\"data-science\"", "\"Minimal\""),
+        )
+        .unwrap();
+
+        let catalog = ProfileCatalog::load(&directory, std::slice::from_ref(&minimal)).unwrap();
+        assert_eq!(
+            catalog
+                .profiles()
+                .iter()
+                .map(|profile| profile.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["developer", "designer", "media", "Minimal", "data-science"]
+        );
+        assert_eq!(
+            catalog.find("minimal").unwrap().source.as_deref(),
+            Some(minimal.as_path())
+        );
+        assert!(catalog.find("DATA-SCIENCE").is_some());
+        assert_eq!(
+            ProfileCatalog::load(&root.join("missing"), &[]).unwrap(),
+            ProfileCatalog::builtin()
+        );
+
+        let error = ProfileCatalog::load(&directory, &[directory.join("data-science.toml")])
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("is defined in both"), "{error}");
+        fs::remove_dir_all(root).unwrap();
+    }
 }
@@ -0,0 +1,451 @@
+use crate::governance::{record_nanos, AuditOutcome, AuditStore};
+use crate::snapshot::SnapshotStore;
+use crate::state_lock::{lock_timeout_from_environment, LockHolder, StateLock};
+use anyhow::{bail, Context, Result};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use time::{Duration, OffsetDateTime};
+
+const ARCHIVE_SUFFIX: &str = ".jsonl.gz";
+
+/// Local retention limits read from the `[retention]` policy section.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RetentionPolicy {
+    /// Move pruned records into compressed bundles instead of deleting them.
+    #[serde(default)]
+    pub archive: bool,
+    #[serde(default)]
+    pub snapshots: RetentionRule,
+    #[serde(default)]
+    pub audit: RetentionRule,
+}
+
+/// Keeps an item when it is among the newest `keep_last` items or younger than
+/// `keep_days`. A rule without either limit keeps everything.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RetentionRule {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep_last: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep_days: Option<u32>,
+}
+
+impl RetentionPolicy {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    pub fn is_unbounded(&self) -> bool {
+        self.snapshots.is_unbounded() && self.audit.is_unbounded()
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for (name, rule) in [("snapshots", self.snapshots), ("audit", self.audit)] {
+            if rule.keep_last == Some(0) {
+                bail!("retention.{name}.keep_last must be at least 1");
+            }
+            if rule.keep_days == Some(0) {
+                bail!("retention.{name}.keep_days must be at least 1");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RetentionRule {
+    pub fn is_unbounded(&self) -> bool {
+        self.keep_last.is_none() && self.keep_days.is_none()
+    }
+
+    /// `index` counts from the newest item; `nanos` is the item's creation time.
+    /// A `keep_days` cutoff before the earliest representable time keeps everything.
+    pub fn keeps(&self, index: usize, nanos: i128, now: OffsetDateTime) -> bool {
+        self.is_unbounded()
+            || self.keep_last.is_some_and(|keep_last| index < keep_last)
+            || self.keep_days.is_some_and(|keep_days| {
+                now.checked_sub(Duration::days(i64::from(keep_days)))
+                    .is_none_or(|cutoff| nanos >= cutoff.unix_timestamp_nanos())
+            })
+    }
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize)]
+pub struct GarbageCollectionSection {
+    pub examined: usize,
+    pub retained: usize,
+    /// Past the retention limits but kept because something still depends on them.
+    pub protected: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct GarbageCollectionReport {
+    pub dry_run: bool,
+    pub archive: bool,
+    pub retention: RetentionPolicy,
+    pub snapshots: GarbageCollectionSection,
+    pub audit: GarbageCollectionSection,
+    pub archives: Vec<PathBuf>,
+}
+
+/// Applies retention to both stores, holding the state directory lock unless
+/// `dry_run` is set.
+/// Audit records are pruned only from the oldest end of the hash chain and
+/// never while pending. Snapshots referenced by a retained or archived audit
+/// record, and snapshots with a name or tags, are always kept.
+pub fn collect_garbage(
+    snapshot_store: &SnapshotStore,
+    audit_store: &AuditStore,
+    retention: &RetentionPolicy,
+    holder: &LockHolder,
+    now: OffsetDateTime,
+    dry_run: bool,
+) -> Result<GarbageCollectionReport> {
+    // A preview changes nothing, so it neither waits for nor creates the lock.
+    let _lock = if dry_run {
+        None
+    } else {
+        Some(StateLock::acquire(
+            snapshot_store.root(),
+            holder,
+            lock_timeout_from_environment()?,
+        )?)
+    };
+    let records = audit_store.history()?;
+    let expired = records
+        .iter()
+        .enumerate()
+        .filter(|(index, record)| !retention.audit.keeps(*index, record_nanos(&record.id), now))
+        .map(|(_, record)| record.id.as_str())
+        .collect::<BTreeSet<_>>();
+    let mut chained = records
+        .iter()
+        .filter_map(|record| record.chain.as_ref().map(|link| (link.sequence, record)))
+        .collect::<Vec<_>>();
+    chained.sort_by_key(|(sequence, _)| *sequence);
+    let mut audit = GarbageCollectionSection {
+        examined: records.len(),
+        ..GarbageCollectionSection::default()
+    };
+    let mut prunable = chained
+        .iter()
+        .map(|(_, record)| *record)
+        .take_while(|record| {
+            expired.contains(record.id.as_str()) && record.outcome != AuditOutcome::Pending
+        })
+        .map(|record| record.id.clone())
+        .collect::<Vec<_>>();
+    prunable.extend(
+        records
+            .iter()
+            .filter(|record| {
+                record.chain.is_none()
+                    && expired.contains(record.id.as_str())
+                    && record.outcome != AuditOutcome::Pending
+            })
+            .map(|record| record.id.clone()),
+    );
+    audit.protected = expired
+        .iter()
+        .filter(|id| !prunable.iter().any(|pruned| pruned == *id))
+        .map(|id| (*id).to_owned())
+        .collect();
+    audit.retained = records.len() - prunable.len();
+    audit.removed = prunable;
+
+    let archived = audit_store.archived_history()?;
+    let referenced = records
+        .iter()
+        .filter(|record| retention.archive || !audit.removed.contains(&record.id))
+        .chain(&archived)
+        .filter_map(|record| record.safety_snapshot_id.as_deref())
+        .collect::<BTreeSet<_>>();
+    let mut summaries = snapshot_store.history()?;
+    summaries.sort_by(|left, right| right.id.cmp(&left.id));
+    let mut snapshots = GarbageCollectionSection {
+        examined: summaries.len(),
+        ..GarbageCollectionSection::default()
+    };
+    for (index, summary) in summaries.iter().enumerate() {
+        if retention
+            .snapshots
+            .keeps(index, record_nanos(&summary.id), now)
+        {
+            snapshots.retained += 1;
+        } else if referenced.contains(summary.id.as_str())
+            || summary.name.is_some()
+            || !summary.tags.is_empty()
+        {
+            snapshots.retained += 1;
+            snapshots.protected.push(summary.id.clone());
+        } else {
+            snapshots.removed.push(summary.id.clone());
+        }
+    }
+
+    let mut archives = Vec::new();
+    if !dry_run {
+        archives.extend(audit_store.prune(&audit.removed, retention.archive)?);
+        archives.extend(snapshot_store.remove(&snapshots.removed, retention.archive)?);
+    }
+    Ok(GarbageCollectionReport {
+        dry_run,
+        archive: retention.archive,
+        retention: retention.clone(),
+        snapshots,
+        audit,
+        archives,
+    })
+}
+
+/// Writes items as gzip-compressed JSON Lines into a new bundle named
+/// `<kind>-<nanos>-<pid>.jsonl.gz`.
+pub(crate) fn write_archive<T: Serialize>(
+    directory: &Path,
+    kind: &str,
+    items: &[T],
+) -> Result<PathBuf> {
+    fs::create_dir_all(directory)
+        .with_context(|| format!("failed to create {}", directory.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(directory, fs::Permissions::from_mode(0o700))?;
+    }
+    let name = format!(
+        "{kind}-{}-{}{ARCHIVE_SUFFIX}",
+        OffsetDateTime::now_utc().unix_timestamp_nanos(),
+        std::process::id()
+    );
+    let destination = directory.join(&name);
+    let temporary = directory.join(format!(".{name}.tmp"));
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let file = options
+        .open(&temporary)
+        .with_context(|| format!("failed to create {}", temporary.display()))?;
+    let mut encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+    for item in items {
+        serde_json::to_writer(&mut encoder, item)?;
+        encoder.write_all(b"\n")?;
+    }
+    let mut writer = encoder.finish()?;
+    writer.flush()?;
+    writer.get_ref().sync_all()?;
+    fs::rename(&temporary, &destination)
+        .with_context(|| format!("failed to store archive {}", destination.display()))?;
+    Ok(destination)
+}
+
+/// Lists bundles of one kind in creation order.
+pub(crate) fn archive_bundles(directory: &Path, kind: &str) -> Result<Vec<PathBuf>> {
+    if !directory.exists() {
+        return Ok(Vec::new());
+    }
+    let prefix = format!("{kind}-");
+    let mut bundles = Vec::new();
+    for entry in fs::read_dir(directory)
+        .with_context(|| format!("failed to read {}", directory.display()))?
+    {
+        let path = entry?.path();
+        if path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(&prefix) && name.ends_with(ARCHIVE_SUFFIX))
+        {
+            bundles.push(path);
+        }
+    }
+    bundles.sort();
+    Ok(bundles)
+}
+
+/// Reads every line of a bundle. A corrupt line is returned as an error so
+/// callers can report it; a corrupt stream ends the bundle with one error.
+pub(crate) fn read_archive<T: DeserializeOwned>(path: &Path) -> Result<Vec<Result<T>>> {
+    let file =
+        fs::File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let mut items = Vec::new();
+    for line in BufReader::new(GzDecoder::new(file)).lines() {
+        match line {
+            Ok(line) if line.trim().is_empty() => {}
+            Ok(line) => items.push(serde_json::from_str(&line).map_err(Into::into)),
+            Err(error) => {
+                items.push(Err(anyhow::Error::from(error)
+                    .context(format!("failed to decompress {}", path.display()))));
+                break;
+            }
+        }
+    }
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot::{SnapshotMetadata, SnapshotReason};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_root(label: &str) -> PathBuf {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        std::env::temp_dir().join(format!("dutis-{label}-{}-{unique}", std::process::id()))
+    }
+
+    #[test]
+    fn rule_keeps_newest_items_or_recent_items() {
+        let now = OffsetDateTime::now_utc();
+        let old = (now - Duration::days(10)).unix_timestamp_nanos();
+        let recent = (now - Duration::days(1)).unix_timestamp_nanos();
+        let rule = RetentionRule {
+            keep_last: Some(2),
+            keep_days: Some(7),
+        };
+        assert!(rule.keeps(1, old, now));
+        assert!(rule.keeps(5, recent, now));
+        assert!(!rule.keeps(2, old, now));
+        assert!(RetentionRule::default().keeps(100, 0, now));
+        let forever = RetentionRule {
+            keep_last: None,
+            keep_days: Some(u32::MAX),
+        };
+        assert!(forever.keeps(100, i128::MIN, now));
+    }
+
+    #[test]
+    fn archives_round_trip_through_gzip_json_lines() {
+        let root = temp_root("archive-round-trip");
+        let path = write_archive(&root, "audit", &["first", "second"]).unwrap();
+        assert!(path
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .ends_with(".jsonl.gz"));
+        assert_eq!(archive_bundles(&root, "audit").unwrap(), vec![path.clone()]);
+        assert!(archive_bundles(&root, "snapshots").unwrap().is_empty());
+        let items = read_archive::<String>(&path)
+            .unwrap()
+            .into_iter()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(items, ["first", "second"]);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn garbage_collection_removes_unreferenced_snapshots_past_retention() {
+        let root = temp_root("gc-snapshots");
+        let snapshot_store = SnapshotStore::new(&root);
+        let audit_store = AuditStore::new(root.join("audit"));
+        let ids = (0..3)
+            .map(|_| {
+                snapshot_store
+                    .create(SnapshotReason::Manual, None, Vec::new())
+                    .unwrap()
+                    .id
+            })
+            .collect::<Vec<_>>();
+        let retention = RetentionPolicy {
+            archive: true,
+            snapshots: RetentionRule {
+                keep_last: Some(1),
+                keep_days: None,
+            },
+            audit: RetentionRule::default(),
+        };
+        let holder = LockHolder::current(crate::governance::MutationChannel::Cli, "gc", "test");
+        let now = OffsetDateTime::now_utc();
+        let preview = collect_garbage(
+            &snapshot_store,
+            &audit_store,
+            &retention,
+            &holder,
+            now,
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            preview.snapshots.removed,
+            vec![ids[1].clone(), ids[0].clone()]
+        );
+        assert_eq!(snapshot_store.history().unwrap().len(), 3);
+
+        let report = collect_garbage(
+            &snapshot_store,
+            &audit_store,
+            &retention,
+            &holder,
+            now,
+            false,
+        )
+        .unwrap();
+        assert_eq!(report.snapshots.removed.len(), 2);
+        assert_eq!(report.archives.len(), 1);
+        assert_eq!(snapshot_store.history().unwrap()[0].id, ids[2]);
+        let archived = read_archive::<crate::snapshot::Snapshot>(&report.archives[0]).unwrap();
+        assert_eq!(archived.len(), 2);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn garbage_collection_keeps_named_and_tagged_snapshots() {
+        let root = temp_root("gc-named");
+        let snapshot_store = SnapshotStore::new(&root);
+        let audit_store = AuditStore::new(root.join("audit"));
+        let holder = LockHolder::current(crate::governance::MutationChannel::Cli, "gc", "test");
+        let create = |metadata: SnapshotMetadata| {
+            snapshot_store
+                .create_with_metadata(SnapshotReason::Manual, None, Vec::new(), metadata, &holder)
+                .unwrap()
+                .id
+        };
+        let named = create(SnapshotMetadata {
+            name: Some("before-xcode-16".to_owned()),
+            ..SnapshotMetadata::default()
+        });
+        let tagged = create(SnapshotMetadata {
+            tags: vec!["baseline".to_owned()],
+            ..SnapshotMetadata::default()
+        });
+        let plain = create(SnapshotMetadata::default());
+        create(SnapshotMetadata::default());
+        let retention = RetentionPolicy {
+            archive: false,
+            snapshots: RetentionRule {
+                keep_last: Some(1),
+                keep_days: None,
+            },
+            audit: RetentionRule::default(),
+        };
+        let report = collect_garbage(
+            &snapshot_store,
+            &audit_store,
+            &retention,
+            &holder,
+            OffsetDateTime::now_utc(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(report.snapshots.removed, vec![plain]);
+        assert_eq!(report.snapshots.protected, vec![tagged, named]);
+        assert_eq!(snapshot_store.history().unwrap().len(), 3);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -1,21 +1,31 @@
 use crate::application::{normalize_extension, resolve_app, Application};
-use crate::association::{AssociationKind, AssociationTarget, HandlerRole};
+use crate::association::{normalize_identifier, AssociationKind, AssociationTarget, HandlerRole};
 use crate::planner::{
-    apply_plan, assemble_plan, ApplyReport, AssociationPlan, PlanAction, PlanEntry,
-    PlannedApplication,
+    apply_plan, assemble_plan, ApplyEntryResult, ApplyReport, ApplyStatus, AssociationPlan,
+    PlanAction, PlanEntry, PlannedApplication,
 };
+use crate::retention::write_archive;
+use crate::state_lock::{lock_timeout_from_environment, LockHolder, StateLock};
 use crate::system::DefaultApplication;
 use anyhow::{anyhow, bail, Context, Result};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
 use std::fs::{self, OpenOptions};
 use std::io::{BufReader, BufWriter, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
 use time::format_description::well_known::Rfc3339;
 use time::OffsetDateTime;
 
 pub const SNAPSHOT_SCHEMA_VERSION: u32 = 1;
+pub const SNAPSHOT_BUNDLE_FORMAT: &str = "dutis-snapshot";
+pub const SNAPSHOT_BUNDLE_VERSION: u32 = 1;
+const MAX_SNAPSHOT_LABEL_BYTES: usize = 64;
+const MAX_SNAPSHOT_NOTE_CHARS: usize = 1024;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -45,18 +55,233 @@ pub struct Snapshot {
     pub reason: SnapshotReason,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub source_plan_digest: Option<String>,
+    /// Optional human metadata. Absent fields keep older schema 1 files valid.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
     pub associations: Vec<SnapshotAssociation>,
 }
 
+/// Human-friendly labels attached to a snapshot when it is created.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct SnapshotMetadata {
+    pub name: Option<String>,
+    pub note: Option<String>,
+    pub tags: Vec<String>,
+}
+
+impl SnapshotMetadata {
+    /// Trims the note, sorts and deduplicates tags, and validates every label.
+    pub fn normalized(self) -> Result<Self> {
+        if let Some(name) = &self.name {
+            validate_label("snapshot name", name)?;
+        }
+        let note = self
+            .note
+            .map(|note| note.trim().to_owned())
+            .filter(|note| !note.is_empty());
+        if let Some(note) = &note {
+            if note.chars().count() > MAX_SNAPSHOT_NOTE_CHARS {
+                bail!("snapshot notes may contain at most {MAX_SNAPSHOT_NOTE_CHARS} characters");
+            }
+            if note
+                .chars()
+                .any(|character| character.is_control() && character != '\n')
+            {
+                bail!("snapshot notes may not contain control characters");
+            }
+        }
+        let mut tags = BTreeSet::new();
+        for tag in self.tags {
+            validate_label("snapshot tag", &tag)?;
+            tags.insert(tag);
+        }
+        Ok(Self {
+            name: self.name,
+            note,
+            tags: tags.into_iter().collect(),
+        })
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Serialize)]
 pub struct SnapshotSummary {
     pub id: String,
     pub created_at: String,
     pub reason: SnapshotReason,
     pub source_plan_digest: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
     pub associations: usize,
 }
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotChange {
+    Added,
+    Removed,
+    Changed,
+    /// Only the later side captured the association, so its earlier handler
+    /// is unknown rather than absent.
+    NotCapturedBefore,
+    /// Only the earlier side captured the association.
+    NotCapturedAfter,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct SnapshotDiffEntry {
+    pub kind: AssociationKind,
+    pub role: HandlerRole,
+    /// Normalized identifier, named like [`SnapshotAssociation::extension`].
+    pub extension: String,
+    pub change: SnapshotChange,
+    pub before: Option<DefaultApplication>,
+    pub after: Option<DefaultApplication>,
+}
+
+impl SnapshotDiffEntry {
+    pub fn association(&self) -> AssociationTarget {
+        AssociationTarget {
+            kind: self.kind,
+            identifier: self.extension.clone(),
+            role: self.role,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct SnapshotDiff {
+    pub from: String,
+    pub to: String,
+    pub added: usize,
+    pub removed: usize,
+    pub changed: usize,
+    pub unchanged: usize,
+    pub not_captured: usize,
+    pub entries: Vec<SnapshotDiffEntry>,
+}
+
+/// Restricts a rollback to part of a snapshot. Selectors are extensions, UTIs,
+/// MIME types, or URL schemes, compared after normalizing for each entry's kind.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize)]
+pub struct RollbackSelection {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub only: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub kinds: Vec<AssociationKind>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+}
+
+impl RollbackSelection {
+    pub fn new(
+        only: Vec<String>,
+        kinds: Vec<AssociationKind>,
+        exclude: Vec<String>,
+    ) -> Result<Self> {
+        for selector in only.iter().chain(&exclude) {
+            let valid = [
+                AssociationKind::Extension,
+                AssociationKind::Uti,
+                AssociationKind::Mime,
+                AssociationKind::UrlScheme,
+            ]
+            .into_iter()
+            .any(|kind| normalize_identifier(kind, selector).is_ok());
+            if !valid {
+                bail!("invalid association selector '{selector}'");
+            }
+        }
+        Ok(Self {
+            only,
+            kinds,
+            exclude,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.only.is_empty() && self.kinds.is_empty() && self.exclude.is_empty()
+    }
+
+    pub fn includes(&self, association: &SnapshotAssociation) -> bool {
+        let selects = |selector: &String| {
+            normalize_identifier(association.kind, selector)
+                .is_ok_and(|identifier| identifier == association.extension)
+        };
+        (self.kinds.is_empty() || self.kinds.contains(&association.kind))
+            && (self.only.is_empty() || self.only.iter().any(selects))
+            && !self.exclude.iter().any(selects)
+    }
+}
+
+/// A machine-independent copy of a snapshot for moving associations between
+/// Macs. Application paths are dropped; handlers are restored by bundle ID.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotBundle {
+    pub format: String,
+    pub bundle_version: u32,
+    pub exported_at: String,
+    /// SHA-256 of the compact JSON encoding of `snapshot`. It is unkeyed, so it
+    /// detects corruption in transit but not a deliberate edit.
+    pub sha256: String,
+    pub snapshot: Snapshot,
+}
+
+impl SnapshotBundle {
+    pub fn new(snapshot: &Snapshot) -> Result<Self> {
+        validate_snapshot(snapshot, None)?;
+        let mut snapshot = snapshot.clone();
+        for default in snapshot
+            .associations
+            .iter_mut()
+            .filter_map(|association| association.default.as_mut())
+        {
+            default.path = None;
+        }
+        Ok(Self {
+            format: SNAPSHOT_BUNDLE_FORMAT.to_owned(),
+            bundle_version: SNAPSHOT_BUNDLE_VERSION,
+            exported_at: OffsetDateTime::now_utc().format(&Rfc3339)?,
+            sha256: bundle_digest(&snapshot)?,
+            snapshot,
+        })
+    }
+
+    /// Checks the bundle format, its checksum, and the snapshot inside.
+    pub fn verify(&self) -> Result<()> {
+        if self.format != SNAPSHOT_BUNDLE_FORMAT {
+            bail!("not a dutis snapshot bundle");
+        }
+        if self.bundle_version != SNAPSHOT_BUNDLE_VERSION {
+            bail!(
+                "unsupported snapshot bundle version {}; expected {}",
+                self.bundle_version,
+                SNAPSHOT_BUNDLE_VERSION
+            );
+        }
+        if bundle_digest(&self.snapshot)? != self.sha256 {
+            bail!("snapshot bundle checksum does not match its contents; the file is corrupt");
+        }
+        validate_snapshot(&self.snapshot, None)
+    }
+}
+
+/// An application a snapshot expects that is not installed on this Mac.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct MissingApplication {
+    pub bundle_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub associations: Vec<AssociationTarget>,
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct ProtectedApply {
     pub safety_snapshot: Option<Snapshot>,
@@ -70,11 +295,27 @@ impl From<&Snapshot> for SnapshotSummary {
             created_at: snapshot.created_at.clone(),
             reason: snapshot.reason,
             source_plan_digest: snapshot.source_plan_digest.clone(),
+            name: snapshot.name.clone(),
+            note: snapshot.note.clone(),
+            tags: snapshot.tags.clone(),
             associations: snapshot.associations.len(),
         }
     }
 }
 
+/// A snapshot name that cannot be used: another snapshot has it, or the
+/// snapshot being imported is already stored under a different name.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SnapshotNameConflict(String);
+
+impl fmt::Display for SnapshotNameConflict {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for SnapshotNameConflict {}
+
 #[derive(Debug, Clone)]
 pub struct SnapshotStore {
     root: PathBuf,
@@ -105,6 +346,45 @@ impl SnapshotStore {
         reason: SnapshotReason,
         source_plan_digest: Option<String>,
         associations: Vec<SnapshotAssociation>,
+    ) -> Result<Snapshot> {
+        self.write_new(
+            reason,
+            source_plan_digest,
+            associations,
+            SnapshotMetadata::default(),
+        )
+    }
+
+    /// Creates a snapshot carrying a name, note, and tags. Names must be
+    /// unique within the store so they can stand in for snapshot IDs; the
+    /// check and the write happen under the state directory lock.
+    pub fn create_with_metadata(
+        &self,
+        reason: SnapshotReason,
+        source_plan_digest: Option<String>,
+        associations: Vec<SnapshotAssociation>,
+        metadata: SnapshotMetadata,
+        holder: &LockHolder,
+    ) -> Result<Snapshot> {
+        let metadata = metadata.normalized()?;
+        let _lock = StateLock::acquire(&self.root, holder, lock_timeout_from_environment()?)?;
+        if let Some(name) = &metadata.name {
+            if self.find_by_name(name)?.is_some() {
+                return Err(SnapshotNameConflict(format!(
+                    "a snapshot named '{name}' already exists"
+                ))
+                .into());
+            }
+        }
+        self.write_new(reason, source_plan_digest, associations, metadata)
+    }
+
+    fn write_new(
+        &self,
+        reason: SnapshotReason,
+        source_plan_digest: Option<String>,
+        associations: Vec<SnapshotAssociation>,
+        metadata: SnapshotMetadata,
     ) -> Result<Snapshot> {
         let associations = normalize_associations(associations)?;
         let now = OffsetDateTime::now_utc();
@@ -129,9 +409,66 @@ impl SnapshotStore {
             created_at,
             reason,
             source_plan_digest,
+            name: metadata.name,
+            note: metadata.note,
+            tags: metadata.tags,
             associations,
         };
+        self.write(&snapshot)?;
+        Ok(snapshot)
+    }
 
+    /// Stores a verified bundle's snapshot under its original ID, optionally
+    /// renamed, holding the state directory lock. Importing the same snapshot
+    /// twice is a no-op, but not under a name other than the one it is stored
+    /// with.
+    pub fn import(
+        &self,
+        bundle: &SnapshotBundle,
+        name: Option<String>,
+        holder: &LockHolder,
+    ) -> Result<Snapshot> {
+        bundle.verify()?;
+        let mut snapshot = bundle.snapshot.clone();
+        if name.is_some() {
+            snapshot.name = name;
+        }
+        validate_snapshot(&snapshot, None)?;
+        let _lock = StateLock::acquire(&self.root, holder, lock_timeout_from_environment()?)?;
+        if self.snapshot_path(&snapshot.id)?.is_file() {
+            let existing = self.load(&snapshot.id)?;
+            if existing.associations != snapshot.associations {
+                bail!(
+                    "a different snapshot with ID {} already exists",
+                    snapshot.id
+                );
+            }
+            if existing.name != snapshot.name {
+                return Err(SnapshotNameConflict(format!(
+                    "snapshot {} is already imported {}",
+                    snapshot.id,
+                    existing
+                        .name
+                        .as_deref()
+                        .map_or("without a name".to_owned(), |name| format!("as '{name}'"))
+                ))
+                .into());
+            }
+            return Ok(existing);
+        }
+        if let Some(name) = &snapshot.name {
+            if self.find_by_name(name)?.is_some() {
+                return Err(SnapshotNameConflict(format!(
+                    "a snapshot named '{name}' already exists; import it under another name"
+                ))
+                .into());
+            }
+        }
+        self.write(&snapshot)?;
+        Ok(snapshot)
+    }
+
+    fn write(&self, snapshot: &Snapshot) -> Result<()> {
         let directory = self.snapshots_directory();
         fs::create_dir_all(&directory)
             .with_context(|| format!("failed to create {}", directory.display()))?;
@@ -149,7 +486,7 @@ impl SnapshotStore {
             .open(&temporary)
             .with_context(|| format!("failed to create {}", temporary.display()))?;
         let mut writer = BufWriter::new(file);
-        serde_json::to_writer_pretty(&mut writer, &snapshot)?;
+        serde_json::to_writer_pretty(&mut writer, snapshot)?;
         writer.write_all(b"\n")?;
         writer.flush()?;
         writer.get_ref().sync_all()?;
@@ -158,8 +495,7 @@ impl SnapshotStore {
                 "failed to atomically store snapshot {}",
                 destination.display()
             )
-        })?;
-        Ok(snapshot)
+        })
     }
 
     pub fn load(&self, id: &str) -> Result<Snapshot> {
@@ -173,6 +509,32 @@ impl SnapshotStore {
     }
 
     pub fn history(&self) -> Result<Vec<SnapshotSummary>> {
+        let mut snapshots = self
+            .read_snapshots()?
+            .iter()
+            .map(SnapshotSummary::from)
+            .collect::<Vec<_>>();
+        snapshots.sort_by(|left, right| right.created_at.cmp(&left.created_at));
+        Ok(snapshots)
+    }
+
+    /// Resolves a snapshot ID or name to the ID of a stored snapshot.
+    pub fn resolve(&self, reference: &str) -> Result<Option<String>> {
+        validate_snapshot_reference(reference)?;
+        if validate_snapshot_id(reference).is_ok() && self.snapshot_path(reference)?.is_file() {
+            return Ok(Some(reference.to_owned()));
+        }
+        Ok(self.find_by_name(reference)?.map(|snapshot| snapshot.id))
+    }
+
+    fn find_by_name(&self, name: &str) -> Result<Option<Snapshot>> {
+        Ok(self
+            .read_snapshots()?
+            .into_iter()
+            .find(|snapshot| snapshot.name.as_deref() == Some(name)))
+    }
+
+    fn read_snapshots(&self) -> Result<Vec<Snapshot>> {
         let directory = self.snapshots_directory();
         if !directory.exists() {
             return Ok(Vec::new());
@@ -189,12 +551,42 @@ impl SnapshotStore {
             let snapshot: Snapshot = serde_json::from_reader(BufReader::new(file))
                 .with_context(|| format!("failed to parse snapshot {}", path.display()))?;
             validate_snapshot(&snapshot, None)?;
-            snapshots.push(SnapshotSummary::from(&snapshot));
+            snapshots.push(snapshot);
         }
-        snapshots.sort_by(|left, right| right.created_at.cmp(&left.created_at));
         Ok(snapshots)
     }
 
+    /// Deletes snapshots, first copying them into a compressed bundle under
+    /// `archive/` when `archive` is set.
+    pub fn remove(&self, ids: &[String], archive: bool) -> Result<Option<PathBuf>> {
+        if ids.is_empty() {
+            return Ok(None);
+        }
+        let bundle = if archive {
+            let snapshots = ids
+                .iter()
+                .map(|id| self.load(id))
+                .collect::<Result<Vec<_>>>()?;
+            Some(write_archive(
+                &self.archive_directory(),
+                "snapshots",
+                &snapshots,
+            )?)
+        } else {
+            None
+        };
+        for id in ids {
+            let path = self.snapshot_path(id)?;
+            fs::remove_file(&path)
+                .with_context(|| format!("failed to remove snapshot {}", path.display()))?;
+        }
+        Ok(bundle)
+    }
+
+    pub fn archive_directory(&self) -> PathBuf {
+        self.root.join("archive")
+    }
+
     pub fn snapshot_path(&self, id: &str) -> Result<PathBuf> {
         validate_snapshot_id(id)?;
         Ok(self.snapshots_directory().join(format!("{id}.json")))
@@ -249,6 +641,234 @@ where
         .collect()
 }
 
+/// Every target declared by installed applications, with the count of
+/// declarations that are not valid Launch Services identifiers.
+pub fn declared_targets(applications: &[Application]) -> (Vec<AssociationTarget>, usize) {
+    let mut targets = BTreeSet::new();
+    let mut invalid = 0;
+    for application in applications {
+        let declarations = [
+            (AssociationKind::Extension, &application.extensions),
+            (AssociationKind::Uti, &application.utis),
+            (AssociationKind::Mime, &application.mime_types),
+            (AssociationKind::UrlScheme, &application.url_schemes),
+        ];
+        for (kind, values) in declarations {
+            for value in values {
+                match AssociationTarget::new(kind, value, HandlerRole::All) {
+                    Ok(target) => {
+                        targets.insert(target);
+                    }
+                    Err(_) => invalid += 1,
+                }
+            }
+        }
+    }
+    (targets.into_iter().collect(), invalid)
+}
+
+/// A target whose handler could not be read during a parallel capture.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct CaptureFailure {
+    pub target: AssociationTarget,
+    pub error: String,
+}
+
+/// The associations a parallel capture read, and the targets it could not.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ParallelCapture {
+    pub associations: Vec<SnapshotAssociation>,
+    pub failures: Vec<CaptureFailure>,
+}
+
+/// Captures targets like [`capture_targets`], spreading queries over up to
+/// `workers` threads. `progress` receives `(completed, total)` after each query.
+/// A failed query is recorded and leaves its target out of the capture; the
+/// capture fails only when every query does.
+pub fn capture_targets_parallel<F, P, I>(
+    targets: I,
+    workers: usize,
+    query: F,
+    mut progress: P,
+) -> Result<ParallelCapture>
+where
+    I: IntoIterator<Item = AssociationTarget>,
+    F: Fn(&AssociationTarget) -> Result<Option<DefaultApplication>> + Sync,
+    P: FnMut(usize, usize),
+{
+    let targets = targets
+        .into_iter()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    let total = targets.len();
+    let next = AtomicUsize::new(0);
+    let mut results = (0..total).map(|_| None).collect::<Vec<_>>();
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        for _ in 0..workers.clamp(1, total.max(1)) {
+            let sender = sender.clone();
+            let (targets, next, query) = (&targets, &next, &query);
+            scope.spawn(move || loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(target) = targets.get(index) else {
+                    break;
+                };
+                if sender.send((index, query(target))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+        for (completed, (index, result)) in receiver.into_iter().enumerate() {
+            results[index] = Some(result);
+            progress(completed + 1, total);
+        }
+    });
+    let mut capture = ParallelCapture::default();
+    for (target, result) in targets.into_iter().zip(results) {
+        match result.context("association capture ended early")? {
+            Ok(default) => capture.associations.push(SnapshotAssociation {
+                kind: target.kind,
+                role: target.role,
+                extension: target.identifier,
+                default,
+            }),
+            Err(error) => capture.failures.push(CaptureFailure {
+                target,
+                error: format!("{error:#}"),
+            }),
+        }
+    }
+    if total > 0 && capture.associations.is_empty() {
+        bail!(
+            "every association query failed; for example {}: {}",
+            capture.failures[0].target,
+            capture.failures[0].error
+        );
+    }
+    Ok(capture)
+}
+
+/// Handlers in a snapshot whose bundle ID matches no installed application,
+/// grouped by bundle ID.
+pub fn missing_applications(
+    snapshot: &Snapshot,
+    applications: &[Application],
+) -> Result<Vec<MissingApplication>> {
+    let mut missing = BTreeMap::<String, MissingApplication>::new();
+    for association in &snapshot.associations {
+        let Some(default) = &association.default else {
+            continue;
+        };
+        if applications
+            .iter()
+            .any(|application| application.bundle_id.as_deref() == Some(default.bundle_id.as_str()))
+        {
+            continue;
+        }
+        let target =
+            AssociationTarget::new(association.kind, &association.extension, association.role)?;
+        missing
+            .entry(default.bundle_id.clone())
+            .or_insert_with(|| MissingApplication {
+                bundle_id: default.bundle_id.clone(),
+                name: default.name.clone(),
+                associations: Vec::new(),
+            })
+            .associations
+            .push(target);
+    }
+    Ok(missing.into_values().collect())
+}
+
+/// The live targets needed to compare a snapshot with the current system.
+pub fn snapshot_targets(snapshot: &Snapshot) -> Result<Vec<AssociationTarget>> {
+    snapshot
+        .associations
+        .iter()
+        .map(|association| {
+            AssociationTarget::new(association.kind, &association.extension, association.role)
+        })
+        .collect()
+}
+
+/// Compares two association sets by kind, identifier, and role. Handlers are
+/// added or removed only when both sides captured the association; one
+/// captured on a single side is reported as not captured.
+pub fn diff_associations(
+    from: impl Into<String>,
+    before: &[SnapshotAssociation],
+    to: impl Into<String>,
+    after: &[SnapshotAssociation],
+) -> SnapshotDiff {
+    let mut pairs = BTreeMap::new();
+    for association in before {
+        pairs
+            .entry((
+                association.kind,
+                association.extension.clone(),
+                association.role,
+            ))
+            .or_insert((None, None))
+            .0 = Some(association.default.clone());
+    }
+    for association in after {
+        pairs
+            .entry((
+                association.kind,
+                association.extension.clone(),
+                association.role,
+            ))
+            .or_insert((None, None))
+            .1 = Some(association.default.clone());
+    }
+    let mut diff = SnapshotDiff {
+        from: from.into(),
+        to: to.into(),
+        added: 0,
+        removed: 0,
+        changed: 0,
+        unchanged: 0,
+        not_captured: 0,
+        entries: Vec::new(),
+    };
+    for ((kind, extension, role), (before, after)) in pairs {
+        let change = match (&before, &after) {
+            (None, Some(_)) => Some(SnapshotChange::NotCapturedBefore),
+            (Some(_), None) => Some(SnapshotChange::NotCapturedAfter),
+            (Some(None), Some(None)) | (None, None) => None,
+            (Some(None), Some(Some(_))) => Some(SnapshotChange::Added),
+            (Some(Some(_)), Some(None)) => Some(SnapshotChange::Removed),
+            (Some(Some(before)), Some(Some(after))) if before.bundle_id != after.bundle_id => {
+                Some(SnapshotChange::Changed)
+            }
+            (Some(Some(_)), Some(Some(_))) => None,
+        };
+        let Some(change) = change else {
+            diff.unchanged += 1;
+            continue;
+        };
+        match change {
+            SnapshotChange::Added => diff.added += 1,
+            SnapshotChange::Removed => diff.removed += 1,
+            SnapshotChange::Changed => diff.changed += 1,
+            SnapshotChange::NotCapturedBefore | SnapshotChange::NotCapturedAfter => {
+                diff.not_captured += 1
+            }
+        }
+        diff.entries.push(SnapshotDiffEntry {
+            kind,
+            role,
+            extension,
+            change,
+            before: before.flatten(),
+            after: after.flatten(),
+        });
+    }
+    diff
+}
+
 pub fn associations_from_plan(plan: &AssociationPlan) -> Vec<SnapshotAssociation> {
     plan.entries
         .iter()
@@ -261,14 +881,16 @@ pub fn associations_from_plan(plan: &AssociationPlan) -> Vec<SnapshotAssociation
         .collect()
 }
 
-pub fn apply_plan_with_snapshot<F>(
+pub fn apply_plan_with_snapshot<F, Q>(
     store: &SnapshotStore,
     plan: &AssociationPlan,
     reason: SnapshotReason,
     apply: F,
+    query_default: Q,
 ) -> Result<ProtectedApply>
 where
     F: FnMut(&AssociationTarget, &str) -> Result<()>,
+    Q: FnMut(&AssociationTarget) -> Result<Option<DefaultApplication>>,
 {
     let safety_snapshot = if plan.summary.changes > 0 {
         Some(store.create(
@@ -279,15 +901,78 @@ where
     } else {
         None
     };
-    let report = apply_plan(plan, apply);
+    let report = apply_plan(plan, apply, query_default);
     Ok(ProtectedApply {
         safety_snapshot,
         report,
     })
 }
 
+/// Restores the safety snapshot's handler for every entry `report` applied.
+///
+/// Used by atomic mutations after a failure. Entries that had no default
+/// before the mutation cannot be reverted because `duti` cannot remove an
+/// association, so they are reported as failed compensations.
+pub fn revert_applied_entries<F>(
+    snapshot: &Snapshot,
+    report: &ApplyReport,
+    mut apply: F,
+) -> ApplyReport
+where
+    F: FnMut(&AssociationTarget, &str) -> Result<()>,
+{
+    let mut results = Vec::new();
+    for applied in report
+        .results
+        .iter()
+        .filter(|result| result.status == ApplyStatus::Applied)
+    {
+        let previous = snapshot.associations.iter().find(|association| {
+            association.kind == applied.kind
+                && association.role == applied.role
+                && association.extension == applied.extension
+        });
+        let bundle_id = previous
+            .and_then(|association| association.default.as_ref())
+            .map(|default| default.bundle_id.clone());
+        let outcome = match (&bundle_id, previous) {
+            (None, None) => Err(anyhow!("the safety snapshot does not record this entry")),
+            (None, Some(_)) => Err(anyhow!(
+                "no previous default to restore; duti cannot remove an association"
+            )),
+            (Some(bundle_id), _) => {
+                AssociationTarget::new(applied.kind, &applied.extension, applied.role)
+                    .and_then(|target| apply(&target, bundle_id))
+            }
+        };
+        results.push(ApplyEntryResult {
+            kind: applied.kind,
+            role: applied.role,
+            extension: applied.extension.clone(),
+            bundle_id,
+            status: if outcome.is_ok() {
+                ApplyStatus::Applied
+            } else {
+                ApplyStatus::Failed
+            },
+            error: outcome.err().map(|error| format!("{error:#}")),
+        });
+    }
+    let mut compensation = ApplyReport {
+        plan_digest: report.plan_digest.clone(),
+        applied: 0,
+        skipped: 0,
+        failed: 0,
+        conflicts: 0,
+        results,
+    };
+    compensation.recount();
+    compensation
+}
+
 pub fn build_rollback_plan<F>(
     snapshot: &Snapshot,
+    selection: &RollbackSelection,
     applications: &[Application],
     mut query_default: F,
 ) -> Result<AssociationPlan>
@@ -296,7 +981,11 @@ where
 {
     validate_snapshot(snapshot, None)?;
     let mut entries = Vec::with_capacity(snapshot.associations.len());
-    for association in &snapshot.associations {
+    for association in snapshot
+        .associations
+        .iter()
+        .filter(|association| selection.includes(association))
+    {
         let target =
             AssociationTarget::new(association.kind, &association.extension, association.role)?;
         let current = query_default(&target)?;
@@ -423,6 +1112,47 @@ fn validate_snapshot(snapshot: &Snapshot, expected_id: Option<&str>) -> Result<(
         bail!("snapshot ID does not match its filename");
     }
     normalize_associations(snapshot.associations.clone())?;
+    if let Some(name) = &snapshot.name {
+        validate_label("snapshot name", name)?;
+    }
+    for tag in &snapshot.tags {
+        validate_label("snapshot tag", tag)?;
+    }
+    Ok(())
+}
+
+fn bundle_digest(snapshot: &Snapshot) -> Result<String> {
+    Ok(Sha256::digest(serde_json::to_vec(snapshot)?)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+/// Accepts anything that could be a snapshot ID or a snapshot name.
+pub fn validate_snapshot_reference(reference: &str) -> Result<()> {
+    if validate_snapshot_id(reference).is_err()
+        && validate_label("snapshot name", reference).is_err()
+    {
+        bail!("invalid snapshot ID or name '{reference}'");
+    }
+    Ok(())
+}
+
+/// Names and tags start with a letter so they never collide with generated IDs.
+fn validate_label(label: &str, value: &str) -> Result<()> {
+    if value.len() > MAX_SNAPSHOT_LABEL_BYTES
+        || !value
+            .chars()
+            .next()
+            .is_some_and(|character| character.is_ascii_alphabetic())
+        || !value.chars().all(|character| {
+            character.is_ascii_alphanumeric() || matches!(character, '-' | '_' | '.')
+        })
+    {
+        bail!(
+            "invalid {label} '{value}'; use up to {MAX_SNAPSHOT_LABEL_BYTES} letters, digits, '-', '_' or '.', starting with a letter"
+        );
+    }
     Ok(())
 }
 
@@ -452,6 +1182,10 @@ mod tests {
         )
     }
 
+    fn holder() -> LockHolder {
+        LockHolder::current(crate::governance::MutationChannel::Cli, "snapshot", "test")
+    }
+
     fn default(extension: &str, bundle_id: &str) -> DefaultApplication {
         DefaultApplication {
             kind: AssociationKind::Extension,
@@ -468,10 +1202,125 @@ mod tests {
             name: "Editor".to_owned(),
             path: PathBuf::from("/Applications/Editor.app"),
             bundle_id: Some(bundle_id.to_owned()),
+            version: None,
             extensions: vec!["md".to_owned()],
+            utis: Vec::new(),
+            mime_types: Vec::new(),
+            url_schemes: Vec::new(),
         }
     }
 
+    #[test]
+    fn parallel_capture_matches_sequential_capture_and_reports_progress() {
+        let mut application = application("com.example.Editor");
+        application.extensions = vec!["md".to_owned(), "bad ext".to_owned()];
+        application.utis = vec!["public.plain-text".to_owned()];
+        application.mime_types = vec!["text/markdown".to_owned()];
+        application.url_schemes = vec!["https".to_owned()];
+        let (targets, invalid) = declared_targets(&[application.clone(), application]);
+        assert_eq!(targets.len(), 4);
+        assert_eq!(invalid, 2);
+
+        let query = |target: &AssociationTarget| {
+            Ok(
+                (target.kind != AssociationKind::Mime).then(|| DefaultApplication {
+                    kind: target.kind,
+                    role: target.role,
+                    extension: target.identifier.clone(),
+                    name: None,
+                    path: None,
+                    bundle_id: format!("com.example.{}", target.identifier),
+                }),
+            )
+        };
+        let mut reported = Vec::new();
+        let parallel = capture_targets_parallel(targets.clone(), 3, query, |done, total| {
+            reported.push((done, total))
+        })
+        .unwrap();
+        assert_eq!(
+            parallel.associations,
+            capture_targets(targets.clone(), query).unwrap()
+        );
+        assert!(parallel.failures.is_empty());
+        assert_eq!(reported.last(), Some(&(4, 4)));
+
+        let partial = capture_targets_parallel(
+            targets.clone(),
+            2,
+            |target: &AssociationTarget| {
+                if target.kind == AssociationKind::UrlScheme {
+                    bail!("duti could not query {target}");
+                }
+                Ok(None)
+            },
+            |_, _| {},
+        )
+        .unwrap();
+        assert_eq!(partial.associations.len(), 3);
+        assert_eq!(partial.failures.len(), 1);
+        assert_eq!(partial.failures[0].target.kind, AssociationKind::UrlScheme);
+        assert!(partial.failures[0].error.contains("https://"));
+
+        let error = capture_targets_parallel(
+            targets,
+            2,
+            |target: &AssociationTarget| bail!("duti could not query {target}"),
+            |_, _| {},
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("every association query failed"));
+    }
+
+    #[test]
+    fn diff_reports_added_removed_changed_and_uncaptured_handlers() {
+        let association = |extension: &str, bundle_id: Option<&str>| SnapshotAssociation {
+            kind: AssociationKind::Extension,
+            role: HandlerRole::All,
+            extension: extension.to_owned(),
+            default: bundle_id.map(|bundle_id| default(extension, bundle_id)),
+        };
+        let before = vec![
+            association("md", Some("com.example.Old")),
+            association("pdf", Some("com.apple.Preview")),
+            association("txt", Some("com.apple.TextEdit")),
+            association("csv", None),
+            association("html", Some("com.apple.Safari")),
+        ];
+        let after = vec![
+            association("md", Some("com.example.New")),
+            association("txt", Some("com.apple.TextEdit")),
+            association("pdf", None),
+            association("csv", Some("com.example.Sheets")),
+            association("rtf", Some("com.apple.TextEdit")),
+        ];
+        let diff = diff_associations("a", &before, "live", &after);
+        assert_eq!(
+            (diff.added, diff.removed, diff.changed, diff.unchanged),
+            (1, 1, 1, 1)
+        );
+        assert_eq!(diff.not_captured, 2);
+        let changes = diff
+            .entries
+            .iter()
+            .map(|entry| (entry.extension.as_str(), entry.change))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            [
+                ("csv", SnapshotChange::Added),
+                ("html", SnapshotChange::NotCapturedAfter),
+                ("md", SnapshotChange::Changed),
+                ("pdf", SnapshotChange::Removed),
+                ("rtf", SnapshotChange::NotCapturedBefore),
+            ]
+        );
+        assert_eq!(
+            diff.entries[2].after.as_ref().unwrap().bundle_id,
+            "com.example.New"
+        );
+    }
+
     #[test]
     fn atomically_stores_loads_and_lists_snapshots() {
         let store = temporary_store();
@@ -550,16 +1399,202 @@ mod tests {
             created_at: "2026-08-22T00:00:00Z".to_owned(),
             reason: SnapshotReason::Manual,
             source_plan_digest: None,
+            name: None,
+            note: None,
+            tags: Vec::new(),
             associations,
         };
-        let plan = build_rollback_plan(&snapshot, &[application("com.example.Editor")], |_| {
-            Ok(None)
-        })
+        let plan = build_rollback_plan(
+            &snapshot,
+            &RollbackSelection::default(),
+            &[application("com.example.Editor")],
+            |_| Ok(None),
+        )
         .unwrap();
         assert_eq!(plan.entries[0].kind, AssociationKind::Uti);
         assert_eq!(plan.entries[0].role, HandlerRole::Viewer);
     }
 
+    #[test]
+    fn named_snapshots_resolve_by_name_and_keep_schema_one() {
+        let store = temporary_store();
+        let snapshot = store
+            .create_with_metadata(
+                SnapshotReason::Manual,
+                None,
+                Vec::new(),
+                SnapshotMetadata {
+                    name: Some("before-xcode-16".to_owned()),
+                    note: Some("  pre-upgrade baseline \n".to_owned()),
+                    tags: vec![
+                        "release".to_owned(),
+                        "audit".to_owned(),
+                        "release".to_owned(),
+                    ],
+                },
+                &holder(),
+            )
+            .unwrap();
+        assert_eq!(snapshot.schema_version, SNAPSHOT_SCHEMA_VERSION);
+        assert_eq!(snapshot.note.as_deref(), Some("pre-upgrade baseline"));
+        assert_eq!(snapshot.tags, vec!["audit", "release"]);
+        assert_eq!(
+            store.resolve("before-xcode-16").unwrap(),
+            Some(snapshot.id.clone())
+        );
+        assert_eq!(
+            store.resolve(&snapshot.id).unwrap(),
+            Some(snapshot.id.clone())
+        );
+        assert_eq!(store.resolve("after-xcode-16").unwrap(), None);
+        assert_eq!(store.history().unwrap()[0].tags, vec!["audit", "release"]);
+
+        let duplicate = store.create_with_metadata(
+            SnapshotReason::Manual,
+            None,
+            Vec::new(),
+            SnapshotMetadata {
+                name: Some("before-xcode-16".to_owned()),
+                ..SnapshotMetadata::default()
+            },
+            &holder(),
+        );
+        assert!(duplicate
+            .unwrap_err()
+            .downcast_ref::<SnapshotNameConflict>()
+            .is_some_and(|conflict| conflict.to_string().contains("already exists")));
+        for name in ["16-before", "has space", ""] {
+            let metadata = SnapshotMetadata {
+                name: Some(name.to_owned()),
+                ..SnapshotMetadata::default()
+            };
+            assert!(metadata.normalized().is_err(), "{name}");
+        }
+
+        let unnamed = store
+            .create(SnapshotReason::Manual, None, Vec::new())
+            .unwrap();
+        let json = fs::read_to_string(store.snapshot_path(&unnamed.id).unwrap()).unwrap();
+        assert!(!json.contains("\"name\"") && !json.contains("\"tags\""));
+        fs::remove_dir_all(store.root()).unwrap();
+    }
+
+    #[test]
+    fn concurrent_creates_cannot_take_the_same_name() {
+        let store = temporary_store();
+        let start = std::sync::Barrier::new(4);
+        let created = thread::scope(|scope| {
+            let workers = (0..4)
+                .map(|_| {
+                    scope.spawn(|| {
+                        start.wait();
+                        store.create_with_metadata(
+                            SnapshotReason::Manual,
+                            None,
+                            Vec::new(),
+                            SnapshotMetadata {
+                                name: Some("baseline".to_owned()),
+                                ..SnapshotMetadata::default()
+                            },
+                            &holder(),
+                        )
+                    })
+                })
+                .collect::<Vec<_>>();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(created.iter().filter(|result| result.is_ok()).count(), 1);
+        assert_eq!(store.history().unwrap().len(), 1);
+        fs::remove_dir_all(store.root()).unwrap();
+    }
+
+    #[test]
+    fn bundles_round_trip_without_paths_and_detect_corruption() {
+        let source = temporary_store();
+        let mut editor = default("md", "com.example.Editor");
+        editor.path = Some("/Users/senior/Applications/Editor.app".to_owned());
+        let snapshot = source
+            .create_with_metadata(
+                SnapshotReason::Manual,
+                None,
+                vec![
+                    SnapshotAssociation {
+                        kind: AssociationKind::Extension,
+                        role: HandlerRole::All,
+                        extension: "md".to_owned(),
+                        default: Some(editor),
+                    },
+                    SnapshotAssociation {
+                        kind: AssociationKind::Extension,
+                        role: HandlerRole::All,
+                        extension: "psd".to_owned(),
+                        default: Some(default("psd", "com.example.Painter")),
+                    },
+                ],
+                SnapshotMetadata {
+                    name: Some("senior-setup".to_owned()),
+                    ..SnapshotMetadata::default()
+                },
+                &holder(),
+            )
+            .unwrap();
+        let bundle = SnapshotBundle::new(&snapshot).unwrap();
+        assert!(bundle.snapshot.associations[0]
+            .default
+            .as_ref()
+            .unwrap()
+            .path
+            .is_none());
+        let encoded = serde_json::to_string(&bundle).unwrap();
+
+        let target = temporary_store();
+        let decoded: SnapshotBundle = serde_json::from_str(&encoded).unwrap();
+        let imported = target.import(&decoded, None, &holder()).unwrap();
+        assert_eq!(imported.id, snapshot.id);
+        assert_eq!(
+            target.resolve("senior-setup").unwrap(),
+            Some(snapshot.id.clone())
+        );
+        assert_eq!(
+            target.import(&decoded, None, &holder()).unwrap().id,
+            snapshot.id
+        );
+        let renamed = target
+            .import(&decoded, Some("new-hire".to_owned()), &holder())
+            .unwrap_err();
+        assert_eq!(
+            renamed.downcast_ref::<SnapshotNameConflict>().unwrap().0,
+            format!(
+                "snapshot {} is already imported as 'senior-setup'",
+                snapshot.id
+            )
+        );
+        assert_eq!(
+            target.load(&snapshot.id).unwrap().name.as_deref(),
+            Some("senior-setup")
+        );
+
+        let missing =
+            missing_applications(&imported, &[application("com.example.Editor")]).unwrap();
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].bundle_id, "com.example.Painter");
+        assert_eq!(missing[0].associations[0].identifier, "psd");
+
+        let corrupt: SnapshotBundle =
+            serde_json::from_str(&encoded.replace("com.example.Painter", "com.example.Paintex"))
+                .unwrap();
+        assert!(corrupt
+            .verify()
+            .unwrap_err()
+            .to_string()
+            .contains("corrupt"));
+        fs::remove_dir_all(source.root()).unwrap();
+        fs::remove_dir_all(target.root()).unwrap();
+    }
+
     #[test]
     fn builds_verified_rollback_plan() {
         let store = temporary_store();
@@ -575,15 +1610,77 @@ mod tests {
                 }],
             )
             .unwrap();
-        let plan = build_rollback_plan(&snapshot, &[application("com.example.Editor")], |target| {
-            Ok(Some(default(&target.identifier, "com.example.Other")))
-        })
+        let plan = build_rollback_plan(
+            &snapshot,
+            &RollbackSelection::default(),
+            &[application("com.example.Editor")],
+            |target| Ok(Some(default(&target.identifier, "com.example.Other"))),
+        )
         .unwrap();
         assert_eq!(plan.summary.changes, 1);
         assert_eq!(plan.entries[0].action, PlanAction::Change);
         fs::remove_dir_all(store.root()).unwrap();
     }
 
+    #[test]
+    fn selective_rollback_plans_only_selected_entries_with_their_own_digest() {
+        let association = |kind, extension: &str| SnapshotAssociation {
+            kind,
+            role: HandlerRole::All,
+            extension: extension.to_owned(),
+            default: Some(default(extension, "com.example.Editor")),
+        };
+        let snapshot = Snapshot {
+            schema_version: SNAPSHOT_SCHEMA_VERSION,
+            id: "1-abc".to_owned(),
+            created_at: "2026-01-05T09:00:00Z".to_owned(),
+            reason: SnapshotReason::Manual,
+            source_plan_digest: None,
+            name: None,
+            note: None,
+            tags: Vec::new(),
+            associations: vec![
+                association(AssociationKind::Extension, "json"),
+                association(AssociationKind::Extension, "md"),
+                association(AssociationKind::Extension, "txt"),
+                association(AssociationKind::UrlScheme, "https"),
+            ],
+        };
+        let plan = |selection: &RollbackSelection| {
+            build_rollback_plan(
+                &snapshot,
+                selection,
+                &[application("com.example.Editor")],
+                |target| Ok(Some(default(&target.identifier, "com.example.Other"))),
+            )
+            .unwrap()
+        };
+        let identifiers = |plan: &AssociationPlan| {
+            plan.entries
+                .iter()
+                .map(|entry| entry.extension.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let full = plan(&RollbackSelection::default());
+        let only = RollbackSelection::new(
+            vec![".MD".to_owned(), "json".to_owned()],
+            Vec::new(),
+            vec!["json".to_owned()],
+        )
+        .unwrap();
+        let selected = plan(&only);
+        assert_eq!(identifiers(&selected), vec!["md"]);
+        assert_ne!(selected.digest, full.digest);
+        let schemes =
+            RollbackSelection::new(Vec::new(), vec![AssociationKind::UrlScheme], Vec::new())
+                .unwrap();
+        assert_eq!(identifiers(&plan(&schemes)), vec!["https"]);
+        assert!(
+            RollbackSelection::new(vec!["not valid!".to_owned()], Vec::new(), Vec::new()).is_err()
+        );
+    }
+
     #[test]
     fn refuses_to_fake_restoring_an_absent_default() {
         let store = temporary_store();
@@ -599,7 +1696,7 @@ mod tests {
                 }],
             )
             .unwrap();
-        let plan = build_rollback_plan(&snapshot, &[], |target| {
+        let plan = build_rollback_plan(&snapshot, &RollbackSelection::default(), &[], |target| {
             Ok(Some(default(&target.identifier, "com.example.Current")))
         })
         .unwrap();
@@ -623,15 +1720,21 @@ mod tests {
                 }],
             )
             .unwrap();
-        let plan = build_rollback_plan(&snapshot, &[application("com.example.Editor")], |target| {
-            Ok(Some(default(&target.identifier, "com.example.Other")))
-        })
+        let plan = build_rollback_plan(
+            &snapshot,
+            &RollbackSelection::default(),
+            &[application("com.example.Editor")],
+            |target| Ok(Some(default(&target.identifier, "com.example.Other"))),
+        )
+        .unwrap();
+        let protected = apply_plan_with_snapshot(
+            &store,
+            &plan,
+            SnapshotReason::BeforeRollback,
+            |_, _| anyhow::bail!("simulated failure"),
+            |target| Ok(Some(default(&target.identifier, "com.example.Other"))),
+        )
         .unwrap();
-        let protected =
-            apply_plan_with_snapshot(&store, &plan, SnapshotReason::BeforeRollback, |_, _| {
-                anyhow::bail!("simulated failure")
-            })
-            .unwrap();
         assert_eq!(protected.report.failed, 1);
         let safety = protected.safety_snapshot.unwrap();
         assert_eq!(
@@ -655,6 +1758,9 @@ mod tests {
             created_at: "2026-08-22T00:00:00Z".to_owned(),
             reason: SnapshotReason::Manual,
             source_plan_digest: None,
+            name: None,
+            note: None,
+            tags: Vec::new(),
             associations: vec![SnapshotAssociation {
                 kind: AssociationKind::Extension,
                 role: HandlerRole::All,
@@ -662,14 +1768,20 @@ mod tests {
                 default: Some(default("md", "com.example.Editor")),
             }],
         };
-        let plan = build_rollback_plan(&snapshot, &[application("com.example.Editor")], |target| {
-            Ok(Some(default(&target.identifier, "com.example.Other")))
-        })
+        let plan = build_rollback_plan(
+            &snapshot,
+            &RollbackSelection::default(),
+            &[application("com.example.Editor")],
+            |target| Ok(Some(default(&target.identifier, "com.example.Other"))),
+        )
         .unwrap();
-        let result =
-            apply_plan_with_snapshot(&store, &plan, SnapshotReason::BeforeRollback, |_, _| {
-                panic!("mutation ran without a safety snapshot")
-            });
+        let result = apply_plan_with_snapshot(
+            &store,
+            &plan,
+            SnapshotReason::BeforeRollback,
+            |_, _| panic!("mutation ran without a safety snapshot"),
+            |_| panic!("state was read without a safety snapshot"),
+        );
         assert!(result.is_err());
         fs::remove_file(store.root()).unwrap();
     }
@@ -0,0 +1,373 @@
+use crate::governance::{serialized_name, MutationChannel};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+pub const LOCK_FILE_NAME: &str = "dutis.lock";
+const LOCK_TIMEOUT_ENV: &str = "DUTIS_LOCK_TIMEOUT_SECONDS";
+const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(30);
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The process that holds the state directory lock, as stored in the lock file.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LockHolder {
+    pub pid: u32,
+    pub channel: MutationChannel,
+    /// The mutation operation, `gc` while local history is pruned, or
+    /// `snapshot` while a manual or imported snapshot is stored.
+    pub operation: String,
+    pub requester: String,
+    pub acquired_at: String,
+}
+
+impl LockHolder {
+    pub fn current(channel: MutationChannel, operation: &str, requester: &str) -> Self {
+        Self {
+            pid: std::process::id(),
+            channel,
+            operation: operation.to_owned(),
+            requester: requester.trim().to_owned(),
+            acquired_at: OffsetDateTime::now_utc()
+                .format(&Rfc3339)
+                .unwrap_or_else(|_| "1970-01-01T00:00:00Z".to_owned()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LockError {
+    /// Another live process kept the lock for the whole timeout.
+    Busy {
+        holder: Option<LockHolder>,
+        waited: Duration,
+    },
+    Failed(anyhow::Error),
+}
+
+impl std::fmt::Display for LockError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Busy {
+                holder: Some(holder),
+                waited,
+            } => write!(
+                formatter,
+                "state directory is locked by pid {} (channel {}, {} by {}) since {}; gave up after {}s",
+                holder.pid,
+                serialized_name(&holder.channel),
+                holder.operation,
+                holder.requester,
+                holder.acquired_at,
+                waited.as_secs()
+            ),
+            Self::Busy {
+                holder: None,
+                waited,
+            } => write!(
+                formatter,
+                "state directory is locked by another process; gave up after {}s",
+                waited.as_secs()
+            ),
+            Self::Failed(error) => write!(formatter, "failed to lock state directory: {error:#}"),
+        }
+    }
+}
+
+impl std::error::Error for LockError {}
+
+/// An advisory lock on the state directory, released when dropped.
+///
+/// The lock file is created atomically with its holder already written, so a
+/// reader never sees a half-written holder. A lock whose process no longer
+/// runs, or whose contents cannot be read, is stale and is replaced.
+#[derive(Debug)]
+pub struct StateLock {
+    path: PathBuf,
+    contents: String,
+}
+
+impl StateLock {
+    pub fn acquire(
+        state_root: &Path,
+        holder: &LockHolder,
+        timeout: Duration,
+    ) -> std::result::Result<Self, LockError> {
+        fs::create_dir_all(state_root)
+            .with_context(|| format!("failed to create {}", state_root.display()))
+            .map_err(LockError::Failed)?;
+        let path = state_root.join(LOCK_FILE_NAME);
+        let contents = serde_json::to_string(holder)
+            .context("failed to serialize lock holder")
+            .map_err(LockError::Failed)?;
+        let started = Instant::now();
+        loop {
+            if try_create(&path, &contents).map_err(LockError::Failed)? {
+                return Ok(Self { path, contents });
+            }
+            let current = match fs::read_to_string(&path) {
+                Ok(current) => current,
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(error) => {
+                    return Err(LockError::Failed(anyhow!(
+                        "failed to read {}: {error}",
+                        path.display()
+                    )))
+                }
+            };
+            let current_holder = serde_json::from_str::<LockHolder>(&current).ok();
+            let stale = current_holder
+                .as_ref()
+                .is_none_or(|holder| !process_is_running(holder.pid));
+            if stale {
+                remove_if_unchanged(&path, &current).map_err(LockError::Failed)?;
+                continue;
+            }
+            let waited = started.elapsed();
+            if waited >= timeout {
+                return Err(LockError::Busy {
+                    holder: current_holder,
+                    waited,
+                });
+            }
+            std::thread::sleep(LOCK_POLL_INTERVAL.min(timeout - waited));
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for StateLock {
+    fn drop(&mut self) {
+        let _ = remove_if_unchanged(&self.path, &self.contents);
+    }
+}
+
+/// Reads the lock timeout from `DUTIS_LOCK_TIMEOUT_SECONDS`, defaulting to 30s.
+pub fn lock_timeout_from_environment() -> Result<Duration> {
+    match std::env::var(LOCK_TIMEOUT_ENV) {
+        Ok(value) if !value.trim().is_empty() => value
+            .trim()
+            .parse::<u64>()
+            .map(Duration::from_secs)
+            .map_err(|_| anyhow!("{LOCK_TIMEOUT_ENV} must be a whole number of seconds")),
+        _ => Ok(DEFAULT_LOCK_TIMEOUT),
+    }
+}
+
+/// Writes the holder to a private temporary file and links it into place,
+/// which fails without side effects when the lock already exists.
+fn try_create(path: &Path, contents: &str) -> Result<bool> {
+    let unique = OffsetDateTime::now_utc().unix_timestamp_nanos();
+    let temporary = path.with_extension(format!("lock.{}-{unique}.tmp", std::process::id()));
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(&temporary)
+        .with_context(|| format!("failed to create {}", temporary.display()))?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    drop(file);
+    let linked = fs::hard_link(&temporary, path);
+    let _ = fs::remove_file(&temporary);
+    match linked {
+        Ok(()) => Ok(true),
+        Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+        Err(error) => bail!("failed to create {}: {error}", path.display()),
+    }
+}
+
+/// Removes the lock only if it still holds `expected`, so a lock that another
+/// process re-acquired in the meantime is left alone.
+///
+/// The lock is first renamed to a unique path, which no other process can
+/// replace, and checked there. A lock that turns out to have changed is
+/// linked back into place. If a third process created a new lock in the
+/// meantime, the claimed copy is left behind as a `.stale` file instead.
+fn remove_if_unchanged(path: &Path, expected: &str) -> Result<()> {
+    remove_if_unchanged_after_claim(path, expected, || {})
+}
+
+/// [`remove_if_unchanged`], running `after_claim` once the lock is renamed so
+/// tests can interleave another process's steps.
+fn remove_if_unchanged_after_claim(
+    path: &Path,
+    expected: &str,
+    after_claim: impl FnOnce(),
+) -> Result<()> {
+    let unique = OffsetDateTime::now_utc().unix_timestamp_nanos();
+    let claimed = path.with_extension(format!("lock.{}-{unique}.stale", std::process::id()));
+    match fs::rename(path, &claimed) {
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        result => result.with_context(|| format!("failed to move {}", path.display()))?,
+    }
+    after_claim();
+    let current = fs::read_to_string(&claimed);
+    if !current.as_ref().is_ok_and(|current| current == expected) {
+        match fs::hard_link(&claimed, path) {
+            // Removing the claimed copy now would leave its holder without a
+            // lock file while the new one also believes it holds the lock.
+            Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => {
+                return current
+                    .map(drop)
+                    .with_context(|| format!("failed to read {}", claimed.display()))
+            }
+            Err(error) => {
+                return Err(error).with_context(|| format!("failed to restore {}", path.display()))
+            }
+            Ok(()) => {}
+        }
+    }
+    fs::remove_file(&claimed).with_context(|| format!("failed to remove {}", claimed.display()))?;
+    current
+        .map(drop)
+        .with_context(|| format!("failed to read {}", path.display()))
+}
+
+#[cfg(unix)]
+fn process_is_running(pid: u32) -> bool {
+    if pid == std::process::id() {
+        return true;
+    }
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    if pid == 0 {
+        return false;
+    }
+    // Signal 0 only checks that the process exists. EPERM means it does but
+    // belongs to another user, so only ESRCH makes the lock stale.
+    // SAFETY: signal 0 is never delivered, so the call has no side effects.
+    if unsafe { libc::kill(pid, 0) } == 0 {
+        return true;
+    }
+    std::io::Error::last_os_error().raw_os_error() != Some(libc::ESRCH)
+}
+
+/// Without a way to check the holder, assume it is alive rather than steal
+/// its lock.
+#[cfg(not(unix))]
+fn process_is_running(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_root(label: &str) -> PathBuf {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        std::env::temp_dir().join(format!("dutis-{label}-{}-{unique}", std::process::id()))
+    }
+
+    fn holder(pid: u32) -> LockHolder {
+        LockHolder {
+            pid,
+            ..LockHolder::current(MutationChannel::Mcp, "apply", "agent")
+        }
+    }
+
+    #[test]
+    fn a_held_lock_times_out_with_its_holder_and_is_released_on_drop() {
+        let root = temp_root("lock-busy");
+        let lock = StateLock::acquire(&root, &holder(std::process::id()), Duration::ZERO).unwrap();
+        assert!(lock.path().exists());
+
+        let error =
+            StateLock::acquire(&root, &holder(std::process::id()), Duration::ZERO).unwrap_err();
+        let message = error.to_string();
+        assert!(message.contains(&format!(
+            "locked by pid {} (channel mcp, apply by agent)",
+            std::process::id()
+        )));
+
+        drop(lock);
+        assert!(!root.join(LOCK_FILE_NAME).exists());
+        drop(StateLock::acquire(&root, &holder(std::process::id()), Duration::ZERO).unwrap());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stale_and_corrupt_locks_are_recovered() {
+        let root = temp_root("lock-stale");
+        fs::create_dir_all(&root).unwrap();
+        let path = root.join(LOCK_FILE_NAME);
+        // PIDs never reach i32::MAX, so no process can hold this lock.
+        fs::write(
+            &path,
+            serde_json::to_string(&holder(i32::MAX as u32)).unwrap(),
+        )
+        .unwrap();
+        let lock = StateLock::acquire(&root, &holder(std::process::id()), Duration::ZERO).unwrap();
+        let stored: LockHolder = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(stored.pid, std::process::id());
+        drop(lock);
+
+        fs::write(&path, "{").unwrap();
+        drop(StateLock::acquire(&root, &holder(std::process::id()), Duration::ZERO).unwrap());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn locks_of_other_users_processes_are_not_stale() {
+        // PID 1 always runs and, for an unprivileged user, answers EPERM.
+        assert!(process_is_running(1));
+        assert!(!process_is_running(i32::MAX as u32));
+        assert!(!process_is_running(u32::MAX));
+    }
+
+    #[test]
+    fn a_changed_lock_is_not_removed() {
+        let root = temp_root("lock-changed");
+        fs::create_dir_all(&root).unwrap();
+        let path = root.join(LOCK_FILE_NAME);
+        fs::write(&path, "current").unwrap();
+        remove_if_unchanged(&path, "previous").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "current");
+        remove_if_unchanged(&path, "current").unwrap();
+        assert!(!path.exists());
+        assert_eq!(fs::read_dir(&root).unwrap().count(), 0);
+        remove_if_unchanged(&path, "current").unwrap();
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn a_lock_created_during_a_claim_is_kept_along_with_the_claimed_one() {
+        let root = temp_root("lock-interleaved");
+        fs::create_dir_all(&root).unwrap();
+        let path = root.join(LOCK_FILE_NAME);
+        fs::write(&path, "reacquired").unwrap();
+        // A third process finds the path free while the lock is renamed away.
+        remove_if_unchanged_after_claim(&path, "stale", || {
+            assert!(try_create(&path, "third").unwrap());
+        })
+        .unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "third");
+        let claimed = fs::read_dir(&root)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|entry| entry != &path)
+            .collect::<Vec<_>>();
+        assert_eq!(claimed.len(), 1);
+        assert!(claimed[0].to_str().unwrap().ends_with(".stale"));
+        assert_eq!(fs::read_to_string(&claimed[0]).unwrap(), "reacquired");
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -111,6 +111,22 @@ pub fn set_default_app(extension: &str, bundle_id: &str) -> Result<()> {
 }
 
 pub fn set_default_handler(association: &AssociationTarget, bundle_id: &str) -> Result<()> {
+    write_default_handler(association, bundle_id)?;
+    let actual = query_default_handler(association)?
+        .ok_or_else(|| anyhow!("verification found no default application for {association}"))?;
+    if actual.bundle_id != bundle_id {
+        bail!(
+            "verification returned bundle ID '{}' instead of '{}'",
+            actual.bundle_id,
+            bundle_id
+        );
+    }
+    Ok(())
+}
+
+/// Runs `duti -s` without reading the result back. Governed mutations verify
+/// the whole plan in a separate pass once Launch Services has settled.
+pub fn write_default_handler(association: &AssociationTarget, bundle_id: &str) -> Result<()> {
     duti_version()?;
     let arguments = duti_set_arguments(association, bundle_id);
     let mut command = Command::new("duti");
@@ -122,16 +138,6 @@ pub fn set_default_handler(association: &AssociationTarget, bundle_id: &str) ->
             String::from_utf8_lossy(&output.stderr).trim()
         );
     }
-
-    let actual = query_default_handler(association)?
-        .ok_or_else(|| anyhow!("verification found no default application for {association}"))?;
-    if actual.bundle_id != bundle_id {
-        bail!(
-            "verification returned bundle ID '{}' instead of '{}'",
-            actual.bundle_id,
-            bundle_id
-        );
-    }
     Ok(())
 }
 
@@ -0,0 +1,324 @@
+use crate::association::{AssociationKind, AssociationTarget, HandlerRole};
+use crate::planner::{ApplyReport, ApplyStatus};
+use crate::system::DefaultApplication;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const MAX_VERIFICATION_RETRIES: u32 = 10;
+const MAX_VERIFICATION_DELAY_MS: u64 = 60_000;
+/// Bounds the settle delay plus every backoff delay, so a mutation never
+/// holds the state lock for long while Launch Services settles.
+const MAX_VERIFICATION_WAIT_MS: u64 = 60_000;
+
+/// Post-apply verification timing read from the `[verification]` policy
+/// section. Launch Services can report the previous handler for a short time
+/// after `duti` returns, so the whole plan is read back only after a settle
+/// delay and mismatches are retried with exponential backoff.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VerificationPolicy {
+    #[serde(default = "default_settle_delay_ms")]
+    pub settle_delay_ms: u64,
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+    /// Delay before the first retry; each further retry doubles it.
+    #[serde(default = "default_retry_delay_ms")]
+    pub retry_delay_ms: u64,
+}
+
+fn default_settle_delay_ms() -> u64 {
+    250
+}
+
+fn default_retries() -> u32 {
+    3
+}
+
+fn default_retry_delay_ms() -> u64 {
+    500
+}
+
+impl Default for VerificationPolicy {
+    fn default() -> Self {
+        Self {
+            settle_delay_ms: default_settle_delay_ms(),
+            retries: default_retries(),
+            retry_delay_ms: default_retry_delay_ms(),
+        }
+    }
+}
+
+impl VerificationPolicy {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.retries > MAX_VERIFICATION_RETRIES {
+            bail!("verification.retries must be at most {MAX_VERIFICATION_RETRIES}");
+        }
+        for (name, value) in [
+            ("settle_delay_ms", self.settle_delay_ms),
+            ("retry_delay_ms", self.retry_delay_ms),
+        ] {
+            if value > MAX_VERIFICATION_DELAY_MS {
+                bail!("verification.{name} must be at most {MAX_VERIFICATION_DELAY_MS}");
+            }
+        }
+        let total = self.total_wait().as_millis();
+        if total > u128::from(MAX_VERIFICATION_WAIT_MS) {
+            bail!(
+                "verification would wait up to {total} ms; settle_delay_ms plus every doubled retry_delay_ms must be at most {MAX_VERIFICATION_WAIT_MS}"
+            );
+        }
+        Ok(())
+    }
+
+    /// The longest verification can sleep when every retry is needed.
+    fn total_wait(&self) -> Duration {
+        (0..self.retries).fold(
+            Duration::from_millis(self.settle_delay_ms),
+            |total, retry| total.saturating_add(self.retry_delay(retry)),
+        )
+    }
+
+    fn retry_delay(&self, retry: u32) -> Duration {
+        Duration::from_millis(self.retry_delay_ms.saturating_mul(1 << retry.min(16)))
+    }
+}
+
+/// What the verification pass observed for one applied entry.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct EntryVerification {
+    pub kind: AssociationKind,
+    pub role: HandlerRole,
+    pub extension: String,
+    pub expected_bundle_id: String,
+    pub observed_bundle_id: Option<String>,
+    pub attempts: u32,
+    pub verified: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Reads back every entry `report` applied and marks entries that never reach
+/// the expected handler as failed, updating the report counts.
+pub fn verify_applied<Q, S>(
+    report: &mut ApplyReport,
+    policy: &VerificationPolicy,
+    mut query_default: Q,
+    mut sleep: S,
+) -> Vec<EntryVerification>
+where
+    Q: FnMut(&AssociationTarget) -> Result<Option<DefaultApplication>>,
+    S: FnMut(Duration),
+{
+    let mut verifications = report
+        .results
+        .iter()
+        .enumerate()
+        .filter(|(_, result)| result.status == ApplyStatus::Applied)
+        .filter_map(|(index, result)| {
+            let expected_bundle_id = result.bundle_id.clone()?;
+            Some((
+                index,
+                EntryVerification {
+                    kind: result.kind,
+                    role: result.role,
+                    extension: result.extension.clone(),
+                    expected_bundle_id,
+                    observed_bundle_id: None,
+                    attempts: 0,
+                    verified: false,
+                    error: None,
+                },
+            ))
+        })
+        .collect::<Vec<_>>();
+    if verifications.is_empty() {
+        return Vec::new();
+    }
+
+    sleep(Duration::from_millis(policy.settle_delay_ms));
+    for attempt in 0..=policy.retries {
+        if attempt > 0 {
+            sleep(policy.retry_delay(attempt - 1));
+        }
+        for (_, verification) in verifications
+            .iter_mut()
+            .filter(|(_, verification)| !verification.verified)
+        {
+            verification.attempts += 1;
+            let observed = AssociationTarget::new(
+                verification.kind,
+                &verification.extension,
+                verification.role,
+            )
+            .and_then(|target| query_default(&target));
+            match observed {
+                Ok(observed) => {
+                    verification.observed_bundle_id = observed.map(|default| default.bundle_id);
+                    verification.verified = verification.observed_bundle_id.as_deref()
+                        == Some(verification.expected_bundle_id.as_str());
+                    verification.error = None;
+                }
+                Err(error) => verification.error = Some(format!("{error:#}")),
+            }
+        }
+        if verifications
+            .iter()
+            .all(|(_, verification)| verification.verified)
+        {
+            break;
+        }
+    }
+
+    for (index, verification) in &verifications {
+        if verification.verified {
+            continue;
+        }
+        let result = &mut report.results[*index];
+        result.status = ApplyStatus::Failed;
+        result.error = Some(
+            match (&verification.error, &verification.observed_bundle_id) {
+                (Some(error), _) => format!(
+                    "verification failed after {} attempt(s): {error}",
+                    verification.attempts
+                ),
+                (None, Some(observed)) => format!(
+                    "verification returned bundle ID '{observed}' instead of '{expected}' after {attempts} attempt(s)",
+                    expected = verification.expected_bundle_id,
+                    attempts = verification.attempts
+                ),
+                (None, None) => format!(
+                    "verification found no default application after {} attempt(s)",
+                    verification.attempts
+                ),
+            },
+        );
+    }
+    report.recount();
+    verifications
+        .into_iter()
+        .map(|(_, verification)| verification)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::planner::ApplyEntryResult;
+
+    fn applied(extension: &str) -> ApplyEntryResult {
+        ApplyEntryResult {
+            kind: AssociationKind::Extension,
+            role: HandlerRole::All,
+            extension: extension.to_owned(),
+            bundle_id: Some("com.example.Editor".to_owned()),
+            status: ApplyStatus::Applied,
+            error: None,
+        }
+    }
+
+    fn default(target: &AssociationTarget, bundle_id: &str) -> Option<DefaultApplication> {
+        Some(DefaultApplication {
+            kind: target.kind,
+            role: target.role,
+            extension: target.identifier.clone(),
+            name: None,
+            path: None,
+            bundle_id: bundle_id.to_owned(),
+        })
+    }
+
+    #[test]
+    fn retries_late_settling_entries_with_backoff_and_fails_the_rest() {
+        let mut report = ApplyReport {
+            plan_digest: "digest".to_owned(),
+            applied: 2,
+            skipped: 0,
+            failed: 0,
+            conflicts: 0,
+            results: vec![applied("md"), applied("json")],
+        };
+        let mut md_queries = 0;
+        let mut sleeps = Vec::new();
+        let verifications = verify_applied(
+            &mut report,
+            &VerificationPolicy {
+                settle_delay_ms: 100,
+                retries: 2,
+                retry_delay_ms: 10,
+            },
+            |target| {
+                if target.identifier == "md" {
+                    md_queries += 1;
+                    let bundle_id = if md_queries < 2 {
+                        "com.example.Old"
+                    } else {
+                        "com.example.Editor"
+                    };
+                    Ok(default(target, bundle_id))
+                } else {
+                    Ok(default(target, "com.example.Other"))
+                }
+            },
+            |duration| sleeps.push(duration.as_millis()),
+        );
+
+        assert_eq!(sleeps, [100, 10, 20]);
+        assert!(verifications[0].verified);
+        assert_eq!(verifications[0].attempts, 2);
+        assert!(!verifications[1].verified);
+        assert_eq!(verifications[1].attempts, 3);
+        assert_eq!(
+            verifications[1].observed_bundle_id.as_deref(),
+            Some("com.example.Other")
+        );
+        assert_eq!((report.applied, report.failed), (1, 1));
+        assert_eq!(report.results[1].status, ApplyStatus::Failed);
+        assert!(report.results[1]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("'com.example.Other' instead of 'com.example.Editor' after 3"));
+    }
+
+    #[test]
+    fn rejects_excessive_retries() {
+        let policy = VerificationPolicy {
+            retries: 11,
+            ..VerificationPolicy::default()
+        };
+        assert!(policy.validate().is_err());
+    }
+
+    #[test]
+    fn bounds_the_total_backoff_wait() {
+        let default = VerificationPolicy::default();
+        assert_eq!(
+            default.total_wait(),
+            Duration::from_millis(250 + 500 + 1000 + 2000)
+        );
+        assert!(default.validate().is_ok());
+
+        let exponential = VerificationPolicy {
+            settle_delay_ms: 0,
+            retries: 10,
+            retry_delay_ms: 60_000,
+        };
+        assert!(exponential
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("must be at most 60000"));
+        let longest = VerificationPolicy {
+            settle_delay_ms: 0,
+            retries: 10,
+            retry_delay_ms: 58,
+        };
+        assert_eq!(longest.total_wait(), Duration::from_millis(58 * 1023));
+        assert!(longest.validate().is_ok());
+    }
+}
@@ -0,0 +1,326 @@
+use crate::drift::DriftState;
+use crate::snapshot::SnapshotStore;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+pub const WATCH_STATUS_SCHEMA_VERSION: u32 = 1;
+/// Checks may run late by this much before a monitor counts as stale.
+const STALE_GRACE_SECONDS: i64 = 60;
+
+/// The heartbeat a running `dutis watch` rewrites after every check, so
+/// `launch-agent status` and `doctor` can tell a working monitor from one
+/// that is loaded but failing or stuck.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct WatchStatus {
+    pub schema_version: u32,
+    pub config: String,
+    pub pid: u32,
+    pub started_at: String,
+    pub interval_seconds: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_check_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_success_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_state: Option<DriftState>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan_digest: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// Failed checks since the last successful one, including failures of
+    /// earlier watcher processes for the same configuration.
+    pub consecutive_failures: u64,
+}
+
+impl WatchStatus {
+    /// Starts a status for this process, carrying over a failure streak that
+    /// launchd restarts would otherwise hide.
+    pub fn start(
+        config: &Path,
+        interval_seconds: u64,
+        previous: Option<&WatchStatus>,
+        now: OffsetDateTime,
+    ) -> Result<Self> {
+        Ok(Self {
+            schema_version: WATCH_STATUS_SCHEMA_VERSION,
+            config: config.display().to_string(),
+            pid: std::process::id(),
+            started_at: format_time(now)?,
+            interval_seconds,
+            last_check_at: None,
+            last_success_at: previous.and_then(|previous| previous.last_success_at.clone()),
+            last_state: None,
+            plan_digest: None,
+            last_error: previous.and_then(|previous| previous.last_error.clone()),
+            consecutive_failures: previous.map_or(0, |previous| previous.consecutive_failures),
+        })
+    }
+
+    pub fn succeeded(
+        &mut self,
+        state: DriftState,
+        plan_digest: &str,
+        now: OffsetDateTime,
+    ) -> Result<()> {
+        let now = format_time(now)?;
+        self.last_check_at = Some(now.clone());
+        self.last_success_at = Some(now);
+        self.last_state = Some(state);
+        self.plan_digest = Some(plan_digest.to_owned());
+        self.last_error = None;
+        self.consecutive_failures = 0;
+        Ok(())
+    }
+
+    pub fn failed(&mut self, error: &str, now: OffsetDateTime) -> Result<()> {
+        self.last_check_at = Some(format_time(now)?);
+        self.last_state = None;
+        self.plan_digest = None;
+        self.last_error = Some(error.to_owned());
+        self.consecutive_failures += 1;
+        Ok(())
+    }
+
+    pub fn health(&self, now: OffsetDateTime) -> WatchHealth {
+        let reference = self.last_check_at.as_deref().unwrap_or(&self.started_at);
+        let overdue = OffsetDateTime::parse(reference, &Rfc3339).is_ok_and(|checked| {
+            let allowed = i64::try_from(self.interval_seconds)
+                .unwrap_or(i64::MAX / 4)
+                .saturating_mul(2)
+                .saturating_add(STALE_GRACE_SECONDS);
+            (now - checked).whole_seconds() > allowed
+        });
+        if overdue {
+            WatchHealth::Stale
+        } else if self.consecutive_failures > 0 {
+            WatchHealth::Failing
+        } else if self.last_check_at.is_none() {
+            WatchHealth::Starting
+        } else {
+            WatchHealth::Healthy
+        }
+    }
+
+    pub fn report(self, now: OffsetDateTime) -> WatchStatusReport {
+        let health = self.health(now);
+        let summary = match health {
+            WatchHealth::Healthy => format!(
+                "last check at {} found {}",
+                self.last_check_at.as_deref().unwrap_or_default(),
+                self.last_state
+                    .map(|state| crate::governance::serialized_name(&state))
+                    .unwrap_or_default()
+            ),
+            WatchHealth::Starting => format!("started at {}; no check yet", self.started_at),
+            WatchHealth::Failing => format!(
+                "running but last check failed {} time(s): {}",
+                self.consecutive_failures,
+                self.last_error.as_deref().unwrap_or("unknown error")
+            ),
+            WatchHealth::Stale => format!(
+                "no check since {} although checks run every {}s; pid {} may have stopped",
+                self.last_check_at.as_deref().unwrap_or(&self.started_at),
+                self.interval_seconds,
+                self.pid
+            ),
+        };
+        WatchStatusReport {
+            health,
+            summary,
+            status: Some(self),
+        }
+    }
+}
+
+impl WatchStatusReport {
+    /// Reports a monitor whose status file exists but cannot be read as
+    /// failing, since nothing is known about its checks.
+    pub fn unreadable(error: &anyhow::Error) -> Self {
+        Self {
+            health: WatchHealth::Failing,
+            summary: format!("status is unknown: {error:#}"),
+            status: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchHealth {
+    Healthy,
+    Starting,
+    Failing,
+    Stale,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct WatchStatusReport {
+    pub health: WatchHealth,
+    pub summary: String,
+    /// Absent when the status file is unreadable.
+    #[serde(flatten)]
+    pub status: Option<WatchStatus>,
+}
+
+/// One status file per watched configuration under `<state>/watch`.
+#[derive(Debug, Clone)]
+pub struct WatchStatusStore {
+    root: PathBuf,
+}
+
+impl WatchStatusStore {
+    pub fn from_environment() -> Result<Self> {
+        Ok(Self::new(
+            SnapshotStore::from_environment()?.root().join("watch"),
+        ))
+    }
+
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn path(&self, config: &Path) -> PathBuf {
+        let digest = Sha256::digest(config.display().to_string().as_bytes());
+        let name = digest[..8]
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+        self.root.join(format!("{name}.json"))
+    }
+
+    pub fn load(&self, config: &Path) -> Result<Option<WatchStatus>> {
+        read_status(&self.path(config))
+    }
+
+    pub fn save(&self, status: &WatchStatus) -> Result<()> {
+        fs::create_dir_all(&self.root)
+            .with_context(|| format!("failed to create {}", self.root.display()))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&self.root, fs::Permissions::from_mode(0o700))?;
+        }
+        let path = self.path(Path::new(&status.config));
+        let temporary = path.with_extension(format!("{}.tmp", std::process::id()));
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options
+            .open(&temporary)
+            .with_context(|| format!("failed to create {}", temporary.display()))?;
+        serde_json::to_writer_pretty(&mut file, status)?;
+        file.write_all(b"\n")?;
+        file.sync_all()?;
+        fs::rename(&temporary, &path)
+            .with_context(|| format!("failed to store watch status {}", path.display()))
+    }
+
+    /// Every recorded monitor, ordered by configuration path.
+    pub fn all(&self) -> Result<Vec<WatchStatus>> {
+        let entries = match fs::read_dir(&self.root) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => {
+                return Err(error)
+                    .with_context(|| format!("failed to read {}", self.root.display()))
+            }
+        };
+        let mut statuses = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "json")
+            {
+                statuses.extend(read_status(&path)?);
+            }
+        }
+        statuses.sort_by(|left, right| left.config.cmp(&right.config));
+        Ok(statuses)
+    }
+}
+
+fn read_status(path: &Path) -> Result<Option<WatchStatus>> {
+    let contents = match fs::read(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => {
+            return Err(error).with_context(|| format!("failed to read {}", path.display()))
+        }
+    };
+    let status: WatchStatus = serde_json::from_slice(&contents)
+        .with_context(|| format!("invalid watch status {}", path.display()))?;
+    if status.schema_version != WATCH_STATUS_SCHEMA_VERSION {
+        bail!(
+            "unsupported watch status schema version {} in {}",
+            status.schema_version,
+            path.display()
+        );
+    }
+    Ok(Some(status))
+}
+
+fn format_time(time: OffsetDateTime) -> Result<String> {
+    time.format(&Rfc3339)
+        .context("failed to format watch status time")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn failure_streaks_survive_restarts_and_staleness_is_reported() {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let root = std::env::temp_dir().join(format!(
+            "dutis-watch-status-{}-{unique}",
+            std::process::id()
+        ));
+        let store = WatchStatusStore::new(&root);
+        let config = Path::new("/Users/test/dutis.toml");
+        let start = OffsetDateTime::parse("2026-10-18T12:00:00Z", &Rfc3339).unwrap();
+
+        let mut status = WatchStatus::start(config, 60, None, start).unwrap();
+        assert_eq!(status.health(start), WatchHealth::Starting);
+        status.failed("duti not found", start).unwrap();
+        status.failed("duti not found", start).unwrap();
+        store.save(&status).unwrap();
+
+        let previous = store.load(config).unwrap().unwrap();
+        let mut restarted = WatchStatus::start(config, 60, Some(&previous), start).unwrap();
+        restarted.failed("duti not found", start).unwrap();
+        let report = restarted.clone().report(start);
+        assert_eq!(report.health, WatchHealth::Failing);
+        assert_eq!(
+            report.summary,
+            "running but last check failed 3 time(s): duti not found"
+        );
+
+        restarted
+            .succeeded(DriftState::InSync, "digest", start)
+            .unwrap();
+        assert_eq!(restarted.consecutive_failures, 0);
+        assert_eq!(restarted.health(start), WatchHealth::Healthy);
+        assert_eq!(
+            restarted.health(start + time::Duration::seconds(181)),
+            WatchHealth::Stale
+        );
+        store.save(&restarted).unwrap();
+        assert_eq!(store.all().unwrap(), vec![restarted]);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
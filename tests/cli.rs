@@ -1,8 +1,10 @@
+use dutis::watch_status::{WatchStatus, WatchStatusStore};
 use serde_json::Value;
 use std::fs;
 use std::io::Write;
 use std::process::{Command, Stdio};
 use std::time::{SystemTime, UNIX_EPOCH};
+use time::OffsetDateTime;
 
 fn dutis() -> Command {
     Command::new(env!("CARGO_BIN_EXE_dutis"))
@@ -98,6 +100,255 @@ fn history_is_empty_for_an_isolated_state_directory() {
     assert_eq!(response["data"].as_array().unwrap().len(), 0);
 }
 
+#[test]
+fn snapshot_diff_compares_two_stored_snapshots() {
+    let unique = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let state = std::env::temp_dir().join(format!(
+        "dutis-cli-snapshot-diff-{}-{unique}",
+        std::process::id()
+    ));
+    let snapshots = state.join("snapshots");
+    fs::create_dir_all(&snapshots).unwrap();
+    let write_snapshot = |id: &str, md: &str, extra: &str| {
+        fs::write(
+            snapshots.join(format!("{id}.json")),
+            format!(
+                r#"{{"schema_version":1,"id":"{id}","created_at":"2026-01-05T09:00:00Z","reason":"manual","associations":[
+                    {{"extension":"md","default":{{"extension":"md","name":null,"path":null,"bundle_id":"{md}"}}}},
+                    {{"kind":"url_scheme","extension":"{extra}","default":{{"kind":"url_scheme","extension":"{extra}","name":"Browser","path":null,"bundle_id":"com.example.Browser"}}}}
+                ]}}"#
+            ),
+        )
+        .unwrap();
+    };
+    write_snapshot("100-aaaaaaaaaaaa", "com.example.Old", "http");
+    write_snapshot("200-bbbbbbbbbbbb", "com.example.New", "https");
+    fs::write(
+        snapshots.join("300-cccccccccccc.json"),
+        r#"{"schema_version":1,"id":"300-cccccccccccc","created_at":"2026-01-05T10:00:00Z","reason":"manual","name":"monday","note":"weekly baseline","tags":["weekly"],"associations":[]}"#,
+    )
+    .unwrap();
+
+    let output = dutis()
+        .env("DUTIS_STATE_DIR", &state)
+        .args([
+            "snapshot",
+            "diff",
+            "100-aaaaaaaaaaaa",
+            "200-bbbbbbbbbbbb",
+            "--json",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let response: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(response["data"]["changed"], 1);
+    assert_eq!(response["data"]["added"], 0);
+    assert_eq!(response["data"]["removed"], 0);
+    assert_eq!(response["data"]["not_captured"], 2);
+
+    let output = dutis()
+        .env("DUTIS_STATE_DIR", &state)
+        .args(["snapshot", "diff", "100-aaaaaaaaaaaa", "200-bbbbbbbbbbbb"])
+        .output()
+        .unwrap();
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert!(text.contains("-.md\tcom.example.Old\n+.md\tcom.example.New\n"));
+    assert!(text.contains("?http://\tnot captured in 200-bbbbbbbbbbbb"));
+    assert!(text.contains("?https://\tnot captured in 100-aaaaaaaaaaaa"));
+
+    let output = dutis()
+        .env("DUTIS_STATE_DIR", &state)
+        .args(["snapshot", "diff", "100-aaaaaaaaaaaa", "400-dddddddddddd"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3));
+
+    let output = dutis()
+        .env("DUTIS_STATE_DIR", &state)
+        .args(["snapshot", "diff", "monday", "200-bbbbbbbbbbbb", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let response: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(response["data"]["from"], "300-cccccccccccc");
+    assert_eq!(response["data"]["added"], 0);
+    assert_eq!(response["data"]["not_captured"], 2);
+
+    let output = dutis()
+        .env("DUTIS_STATE_DIR", &state)
+        .args(["history", "--tag", "weekly", "--json"])
+        .output()
+        .unwrap();
+    let response: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let history = response["data"].as_array().unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0]["name"], "monday");
+    assert_eq!(history[0]["note"], "weekly baseline");
+
+    let output = dutis()
+        .env("DUTIS_STATE_DIR", &state)
+        .args([
+            "rollback",
+            "100-aaaaaaaaaaaa",
+            "--only",
+            ".pdf",
+            "--kind",
+            "url-scheme",
+            "--dry-run",
+        ])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("match no associations"));
+    fs::remove_dir_all(state).unwrap();
+}
+
+#[test]
+fn snapshot_bundles_move_between_state_directories_and_reject_corruption() {
+    let unique = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let root = std::env::temp_dir().join(format!(
+        "dutis-cli-snapshot-bundle-{}-{unique}",
+        std::process::id()
+    ));
+    let (source, target) = (root.join("source"), root.join("target"));
+    fs::create_dir_all(source.join("snapshots")).unwrap();
+    fs::write(
+        source.join("snapshots/100-aaaaaaaaaaaa.json"),
+        r#"{"schema_version":1,"id":"100-aaaaaaaaaaaa","created_at":"2026-01-05T09:00:00Z","reason":"manual","name":"senior","associations":[
+            {"extension":"md","default":{"extension":"md","name":"Editor","path":"/Users/senior/Applications/Editor.app","bundle_id":"com.example.Editor"}}
+        ]}"#,
+    )
+    .unwrap();
+    let bundle = root.join("setup.dutis-snapshot");
+    let export = |state: &std::path::Path| {
+        dutis()
+            .env("DUTIS_STATE_DIR", state)
+            .args(["snapshot", "export", "senior", "--out"])
+            .arg(&bundle)
+            .arg("--json")
+            .output()
+            .unwrap()
+    };
+    let output = export(&source);
+    assert!(output.status.success());
+    let contents = fs::read_to_string(&bundle).unwrap();
+    assert!(!contents.contains("/Users/senior"));
+    assert_eq!(export(&source).status.code(), Some(2));
+
+    let output = dutis()
+        .env("DUTIS_STATE_DIR", &target)
+        .args(["snapshot", "import"])
+        .arg(&bundle)
+        .args(["--name", "new-hire", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let response: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(response["data"]["snapshot"]["name"], "new-hire");
+    assert!(target.join("snapshots/100-aaaaaaaaaaaa.json").is_file());
+
+    let output = dutis()
+        .env("DUTIS_STATE_DIR", &target)
+        .args(["snapshot", "import"])
+        .arg(&bundle)
+        .args(["--name", "other-name"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("already imported as 'new-hire'"));
+
+    fs::write(
+        &bundle,
+        contents.replace("com.example.Editor", "com.example.Other"),
+    )
+    .unwrap();
+    let output = dutis()
+        .env("DUTIS_STATE_DIR", root.join("third"))
+        .args(["snapshot", "import"])
+        .arg(&bundle)
+        .arg("--json")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(10));
+    assert!(!root.join("third").exists());
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn gc_requires_confirmation_and_previews_an_isolated_state_directory() {
+    let unique = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let state = std::env::temp_dir().join(format!("dutis-cli-gc-{}-{unique}", std::process::id()));
+    let output = dutis()
+        .env("DUTIS_STATE_DIR", &state)
+        .args(["gc", "--json"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = dutis()
+        .env("DUTIS_STATE_DIR", &state)
+        .args(["gc", "--dry-run", "--keep-last", "5", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let response: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(response["command"], "gc");
+    assert_eq!(response["data"]["dry_run"], true);
+    assert_eq!(response["data"]["retention"]["audit"]["keep_last"], 5);
+    assert_eq!(
+        response["data"]["snapshots"]["removed"]
+            .as_array()
+            .unwrap()
+            .len(),
+        0
+    );
+    assert!(!state.exists());
+}
+
+#[test]
+fn undo_requires_confirmation_and_an_undoable_audit_record() {
+    let unique = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let state =
+        std::env::temp_dir().join(format!("dutis-cli-undo-{}-{unique}", std::process::id()));
+    let output = dutis()
+        .env("DUTIS_STATE_DIR", &state)
+        .args(["undo", "--json"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = dutis()
+        .env("DUTIS_STATE_DIR", &state)
+        .args(["undo", "--dry-run", "--json"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    let response: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(response["command"], "undo");
+    assert_eq!(response["error"]["kind"], "not_found");
+
+    let output = dutis()
+        .env("DUTIS_STATE_DIR", &state)
+        .args(["undo", "100-aaaaaaaaaaaa", "--dry-run"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    assert!(!state.exists());
+}
+
 #[test]
 fn profile_list_and_show_are_available_without_duti() {
     let list = dutis()
@@ -126,6 +377,22 @@ fn profile_list_and_show_are_available_without_duti() {
     assert!(response["data"]["associations"].as_array().unwrap().len() >= 5);
 }
 
+#[test]
+fn oversized_since_is_a_usage_error() {
+    for args in [
+        &["audit", "--since", "9999999d", "--json"][..],
+        &["drift", "history", "--since", "9999999d", "--json"],
+    ] {
+        let output = dutis()
+            .env("DUTIS_STATE_DIR", "/nonexistent/dutis-state")
+            .args(args)
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(2), "{output:?}");
+        assert!(!String::from_utf8_lossy(&output.stderr).contains("panicked"));
+    }
+}
+
 #[test]
 fn unknown_profile_has_stable_json_error() {
     let output = dutis()
@@ -138,6 +405,95 @@ fn unknown_profile_has_stable_json_error() {
     assert_eq!(response["error"]["kind"], "not_found");
 }
 
+#[test]
+fn user_defined_profiles_load_from_state_and_profile_files() {
+    let unique = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let state = std::env::temp_dir().join(format!(
+        "dutis-user-profiles-{}-{unique}",
+        std::process::id()
+    ));
+    fs::create_dir_all(state.join("profiles")).unwrap();
+    fs::write(
+        state.join("profiles/data-science.toml"),
+        concat!(
+            "version = 1\n",
+            "name = 'data-science'\n",
+            "description = 'Notebooks and tabular data.'\n",
+            "[[handlers]]\n",
+            "kind = 'mime'\n",
+            "identifier = 'text/csv'\n",
+            "candidates = [{ bundle_id = 'com.example.Sheets', rationale = 'Tabular editing.' }]\n",
+        ),
+    )
+    .unwrap();
+    let extra = state.join("writing.toml");
+    fs::write(
+        &extra,
+        concat!(
+            "version = 1\n",
+            "name = 'writing'\n",
+            "description = 'Prose.'\n",
+            "[[associations]]\n",
+            "extension = 'md'\n",
+            "candidates = [{ bundle_id = 'com.example.Writer', rationale = 'Focused prose editor.' }]\n",
+        ),
+    )
+    .unwrap();
+
+    let list = dutis()
+        .env("PATH", "")
+        .env("DUTIS_STATE_DIR", &state)
+        .args(["profile", "list", "--json", "--profile-file"])
+        .arg(&extra)
+        .output()
+        .unwrap();
+    assert!(list.status.success());
+    let response: Value = serde_json::from_slice(&list.stdout).unwrap();
+    let names = response["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|profile| profile["name"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "developer",
+            "designer",
+            "media",
+            "minimal",
+            "data-science",
+            "writing"
+        ]
+    );
+
+    let show = dutis()
+        .env("PATH", "")
+        .env("DUTIS_STATE_DIR", &state)
+        .args(["profile", "show", "data-science"])
+        .output()
+        .unwrap();
+    assert!(show.status.success());
+    let stdout = String::from_utf8(show.stdout).unwrap();
+    assert!(stdout.contains("MIME text/csv"), "{stdout}");
+    assert!(stdout.contains("1. com.example.Sheets — Tabular editing."));
+
+    fs::write(&extra, "version = 1\nname = 'writing'\n").unwrap();
+    let invalid = dutis()
+        .env("DUTIS_STATE_DIR", &state)
+        .args(["profile", "show", "writing", "--json", "--profile-file"])
+        .arg(&extra)
+        .output()
+        .unwrap();
+    assert_eq!(invalid.status.code(), Some(2));
+    let response: Value = serde_json::from_slice(&invalid.stdout).unwrap();
+    assert_eq!(response["error"]["kind"], "usage");
+    fs::remove_dir_all(state).unwrap();
+}
+
 #[cfg(unix)]
 #[test]
 fn typed_handler_get_uses_duti_default_handler_query() {
@@ -424,19 +780,17 @@ fn watch_once_reports_drift_without_invoking_duti_set() {
     fs::remove_dir_all(root).unwrap();
 }
 
+/// Runs one `watch --once --remediate` against a fake `duti` whose handler
+/// for `.md` has drifted, returning the temporary root and the output.
 #[cfg(target_os = "macos")]
-#[test]
-fn opted_in_watch_remediation_uses_snapshot_audit_and_verification() {
+fn run_watch_remediation(label: &str) -> (std::path::PathBuf, std::process::Output) {
     use std::os::unix::fs::PermissionsExt;
 
     let unique = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_nanos();
-    let root = std::env::temp_dir().join(format!(
-        "dutis-watch-remediation-{}-{unique}",
-        std::process::id()
-    ));
+    let root = std::env::temp_dir().join(format!("dutis-{label}-{}-{unique}", std::process::id()));
     let bin = root.join("bin");
     fs::create_dir_all(&bin).unwrap();
     let duti = bin.join("duti");
@@ -481,6 +835,15 @@ fn opted_in_watch_remediation_uses_snapshot_audit_and_verification() {
         ])
         .output()
         .unwrap();
+    (root, output)
+}
+
+#[cfg(target_os = "macos")]
+#[test]
+fn opted_in_watch_remediation_uses_snapshot_audit_and_verification() {
+    let (root, output) = run_watch_remediation("watch-remediation");
+    let calls = root.join("duti-calls.log");
+    let state = root.join("state");
     assert!(
         output.status.success(),
         "{}",
@@ -500,6 +863,235 @@ fn opted_in_watch_remediation_uses_snapshot_audit_and_verification() {
     fs::remove_dir_all(root).unwrap();
 }
 
+#[cfg(target_os = "macos")]
+#[test]
+fn watch_remediates_only_rules_that_allow_it() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let unique = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let root = std::env::temp_dir().join(format!(
+        "dutis-watch-on-drift-{}-{unique}",
+        std::process::id()
+    ));
+    let bin = root.join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    let duti = bin.join("duti");
+    fs::write(
+        &duti,
+        concat!(
+            "#!/bin/sh\n",
+            "printf '%s\\n' \"$*\" >> \"$DUTI_CALL_LOG\"\n",
+            "if [ \"$1\" = \"-V\" ]; then printf 'test-duti\\n'; exit 0; fi\n",
+            "if [ \"$1\" = \"-s\" ]; then : > \"$DUTI_FAKE_STATE\"; exit 0; fi\n",
+            "if [ \"$1\" = \"-x\" ] && [ \"$2\" = \"md\" ] && [ -f \"$DUTI_FAKE_STATE\" ]; then printf 'TextEdit\\n/System/Applications/TextEdit.app\\ncom.apple.TextEdit\\n'; exit 0; fi\n",
+            "if [ \"$1\" = \"-x\" ]; then printf 'Other\\n/Applications/Other.app\\ncom.example.Other\\n'; exit 0; fi\n",
+            "exit 99\n",
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&duti, fs::Permissions::from_mode(0o700)).unwrap();
+    let config = root.join("dutis.toml");
+    fs::write(
+        &config,
+        concat!(
+            "version = 2\n",
+            "[associations]\n",
+            "md = 'com.apple.TextEdit'\n",
+            "[[handlers]]\n",
+            "kind = 'extension'\n",
+            "identifier = 'txt'\n",
+            "application = 'com.apple.TextEdit'\n",
+            "on_drift = 'notify'\n",
+            "[[handlers]]\n",
+            "kind = 'extension'\n",
+            "identifier = 'rtf'\n",
+            "application = 'com.apple.TextEdit'\n",
+            "on_drift = 'ignore'\n",
+        ),
+    )
+    .unwrap();
+    let calls = root.join("duti-calls.log");
+
+    let output = dutis()
+        .env("PATH", &bin)
+        .env("DUTI_CALL_LOG", &calls)
+        .env("DUTI_FAKE_STATE", root.join("applied"))
+        .env("DUTIS_STATE_DIR", root.join("state"))
+        .args([
+            "watch",
+            config.to_str().unwrap(),
+            "--once",
+            "--remediate",
+            "--yes",
+            "--requester",
+            "integration-test",
+            "--json",
+        ])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let response: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(response["data"]["remediation"]["status"], "succeeded");
+    assert_eq!(response["data"]["remediation"]["mutation"]["applied"], 1);
+    let actions = response["data"]["drift_actions"].as_array().unwrap();
+    assert_eq!(actions.len(), 2);
+    assert!(actions.iter().any(|action| {
+        action["association"]["identifier"] == "txt" && action["on_drift"] == "notify"
+    }));
+    let calls = fs::read_to_string(&calls).unwrap();
+    assert!(calls
+        .lines()
+        .any(|line| line == "-s com.apple.TextEdit .md all"));
+    assert!(!calls.lines().any(|line| line.contains(".txt")));
+    assert!(!calls.lines().any(|line| line.contains("rtf")));
+
+    // Without --remediate, a remediate rule reports the action it really gets.
+    let remediate_rule = root.join("remediate.toml");
+    fs::write(
+        &remediate_rule,
+        "version = 2\n[[handlers]]\nkind = 'extension'\nidentifier = 'md'\napplication = 'com.apple.TextEdit'\non_drift = 'remediate'\n",
+    )
+    .unwrap();
+    let output = dutis()
+        .env("PATH", &bin)
+        .env("DUTI_CALL_LOG", root.join("notify-calls.log"))
+        .env("DUTI_FAKE_STATE", root.join("not-applied"))
+        .env("DUTIS_STATE_DIR", root.join("state"))
+        .args([
+            "watch",
+            remediate_rule.to_str().unwrap(),
+            "--once",
+            "--json",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let response: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(response["data"]["drift_actions"][0]["on_drift"], "notify");
+    assert!(response["data"]["remediation"].is_null());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("without --remediate"));
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[cfg(target_os = "macos")]
+#[test]
+fn watch_remediation_is_recorded_in_drift_history() {
+    let (root, output) = run_watch_remediation("watch-remediation-history");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let output = dutis()
+        .env("DUTIS_STATE_DIR", root.join("state"))
+        .args(["drift", "history", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let response: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let events = response["data"]["events"].as_array().unwrap();
+    assert_eq!(events[0]["event"], "remediated");
+    assert_eq!(events[1]["event"], "detected");
+    assert_eq!(
+        events[1]["entries"][0]["current_bundle_id"],
+        "com.example.Other"
+    );
+    assert_eq!(response["data"]["statistics"][0]["remediations"], 1);
+
+    let output = dutis()
+        .env("DUTIS_STATE_DIR", root.join("state"))
+        .args(["drift", "history"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("\tremediated\t"), "{stdout}");
+    assert!(stdout.contains("\tdetected\t"), "{stdout}");
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn drift_history_filters_recorded_events_and_summarizes_associations() {
+    let unique = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let state = std::env::temp_dir().join(format!(
+        "dutis-drift-history-{}-{unique}",
+        std::process::id()
+    ));
+    fs::create_dir_all(state.join("drift")).unwrap();
+    let event = |timestamp: &str, config: &str| {
+        serde_json::json!({
+            "schema_version": 1,
+            "timestamp": timestamp,
+            "event": "detected",
+            "config": config,
+            "plan_digest": timestamp,
+            "entries": [{
+                "kind": "url_scheme",
+                "role": "all",
+                "identifier": "https",
+                "previous_bundle_id": "com.apple.Safari",
+                "current_bundle_id": "com.google.Chrome"
+            }]
+        })
+        .to_string()
+    };
+    fs::write(
+        state.join("drift/history.jsonl"),
+        [
+            event("2026-07-01T00:00:00Z", "/nonexistent/dutis.toml"),
+            event("2026-08-01T00:00:00Z", "/nonexistent/other.toml"),
+            event("2026-08-02T00:00:00Z", "/nonexistent/dutis.toml"),
+        ]
+        .join("\n"),
+    )
+    .unwrap();
+
+    let output = dutis()
+        .env("DUTIS_STATE_DIR", &state)
+        .args([
+            "drift",
+            "history",
+            "--since",
+            "2026-07-15",
+            "--config",
+            "/nonexistent/dutis.toml",
+            "--json",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let response: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(response["command"], "drift");
+    assert_eq!(response["data"]["events"].as_array().unwrap().len(), 1);
+    assert_eq!(response["data"]["statistics"][0]["identifier"], "https");
+    assert_eq!(
+        response["data"]["statistics"][0]["drifted_to"]["com.google.Chrome"],
+        1
+    );
+
+    let output = dutis()
+        .env("DUTIS_STATE_DIR", &state)
+        .args(["drift", "history"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout).unwrap().contains(
+        "https:// drifted 2 time(s) since 2026-07-01T00:00:00Z, always to com.google.Chrome"
+    ));
+    fs::remove_dir_all(state).unwrap();
+}
+
 #[test]
 fn launch_agent_status_is_read_only_for_an_isolated_directory() {
     let unique = SystemTime::now()
@@ -522,6 +1114,170 @@ fn launch_agent_status_is_read_only_for_an_isolated_directory() {
     assert_eq!(response["data"]["loaded"], false);
 }
 
+#[test]
+fn launch_agent_list_and_status_address_named_agents() {
+    let unique = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let directory = std::env::temp_dir().join(format!(
+        "dutis-launch-agents-{}-{unique}",
+        std::process::id()
+    ));
+    fs::create_dir_all(&directory).unwrap();
+    let agent = |arguments: &str| {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0"><dict>
+<key>ProgramArguments</key><array><string>/usr/local/bin/dutis</string><string>watch</string>{arguments}</array>
+</dict></plist>
+"#
+        )
+    };
+    fs::write(
+        directory.join("io.github.tsonglew.dutis.watch.plist"),
+        agent("<string>/Users/test/dutis.toml</string><string>--json</string><string>--interval-seconds</string><string>300</string>"),
+    )
+    .unwrap();
+    fs::write(
+        directory.join("io.github.tsonglew.dutis.watch.work.plist"),
+        agent(
+            "<string>/Users/test/work.toml</string><string>--json</string><string>--once</string>",
+        ),
+    )
+    .unwrap();
+    fs::write(directory.join("com.example.other.plist"), agent("")).unwrap();
+    fs::write(
+        directory.join("io.github.tsonglew.dutis.watch.broken.plist"),
+        "not a plist",
+    )
+    .unwrap();
+
+    let output = dutis()
+        .env("DUTIS_LAUNCH_AGENT_DIR", &directory)
+        .args(["launch-agent", "list", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let response: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let agents = response["data"]["agents"].as_array().unwrap();
+    assert_eq!(agents.len(), 3);
+    assert!(agents[0].get("name").is_none());
+    assert!(agents[0].get("error").is_none());
+    assert_eq!(agents[0]["config"], "/Users/test/dutis.toml");
+    assert_eq!(agents[0]["interval_seconds"], 300);
+    assert_eq!(agents[1]["name"], "broken");
+    assert_eq!(agents[1]["config"], Value::Null);
+    assert!(agents[1]["error"]
+        .as_str()
+        .unwrap()
+        .contains("failed to read"));
+    assert_eq!(agents[2]["name"], "work");
+    assert_eq!(agents[2]["label"], "io.github.tsonglew.dutis.watch.work");
+    assert_eq!(agents[2]["interval_seconds"], Value::Null);
+
+    let output = dutis()
+        .env("DUTIS_LAUNCH_AGENT_DIR", &directory)
+        .args(["launch-agent", "status", "--name", "work", "--json"])
+        .output()
+        .unwrap();
+    let response: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(response["data"]["installed"], true);
+    assert_eq!(response["data"]["name"], "work");
+
+    let output = dutis()
+        .env("DUTIS_LAUNCH_AGENT_DIR", &directory)
+        .args(["launch-agent", "uninstall", "--name", "../work"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(directory
+        .join("io.github.tsonglew.dutis.watch.work.plist")
+        .exists());
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn failing_watcher_heartbeat_is_reported_by_launch_agent_status_and_doctor() {
+    let unique = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let root = std::env::temp_dir().join(format!(
+        "dutis-watch-heartbeat-{}-{unique}",
+        std::process::id()
+    ));
+    let state = root.join("state");
+    let agents = root.join("agents");
+    fs::create_dir_all(&agents).unwrap();
+    let config = root.join("dutis.toml");
+    fs::write(&config, "version = 1\n").unwrap();
+    // The plist records a non-canonical path; the watcher keys its status by
+    // the canonical one.
+    let recorded = root.join("agents").join("..").join("dutis.toml");
+    let canonical = fs::canonicalize(&config).unwrap();
+    let store = WatchStatusStore::new(state.join("watch"));
+    let now = OffsetDateTime::now_utc();
+    let mut status = WatchStatus::start(&canonical, 60, None, now).unwrap();
+    status.failed("duti is required", now).unwrap();
+    status.failed("duti is required", now).unwrap();
+    store.save(&status).unwrap();
+
+    fs::write(
+        agents.join("io.github.tsonglew.dutis.watch.plist"),
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0"><dict>
+<key>ProgramArguments</key><array><string>/usr/local/bin/dutis</string><string>watch</string><string>{}</string></array>
+<key>EnvironmentVariables</key><dict><key>DUTIS_STATE_DIR</key><string>{}</string></dict>
+</dict></plist>
+"#,
+            recorded.display(),
+            state.display()
+        ),
+    )
+    .unwrap();
+    let output = dutis()
+        .env("DUTIS_LAUNCH_AGENT_DIR", &agents)
+        .args(["launch-agent", "status"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains("Monitor: running but last check failed 2 time(s): duti is required"),
+        "{stdout}"
+    );
+
+    let output = dutis()
+        .env("DUTIS_STATE_DIR", &state)
+        .args(["doctor", "--json"])
+        .output()
+        .unwrap();
+    let response: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let monitor = &response["data"]["monitors"][0];
+    assert_eq!(monitor["health"], "failing");
+    assert_eq!(monitor["config"], canonical.display().to_string());
+    assert_eq!(monitor["consecutive_failures"], 2);
+    assert_eq!(monitor["last_error"], "duti is required");
+
+    fs::write(store.path(&canonical), "{").unwrap();
+    let output = dutis()
+        .env("DUTIS_LAUNCH_AGENT_DIR", &agents)
+        .args(["launch-agent", "status", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let response: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let monitor = &response["data"]["monitor"];
+    assert_eq!(monitor["health"], "failing");
+    assert!(monitor["summary"]
+        .as_str()
+        .unwrap()
+        .starts_with("status is unknown: invalid watch status"));
+    fs::remove_dir_all(root).unwrap();
+}
+
 #[test]
 fn mcp_stdio_initializes_and_advertises_read_only_tools() {
     let unique = SystemTime::now()
@@ -646,6 +1402,86 @@ fn policy_show_redacts_token_hash_and_audit_starts_empty() {
         .unwrap();
     assert!(audit_output.status.success());
     let audit: Value = serde_json::from_slice(&audit_output.stdout).unwrap();
-    assert_eq!(audit["data"].as_array().unwrap().len(), 0);
+    assert_eq!(audit["data"]["total"], 0);
+    assert_eq!(audit["data"]["records"].as_array().unwrap().len(), 0);
+    assert!(audit["data"]["next_offset"].is_null());
+
+    let verify_output = dutis()
+        .env("DUTIS_STATE_DIR", &state)
+        .args(["audit", "verify", "--json"])
+        .output()
+        .unwrap();
+    assert!(verify_output.status.success());
+    let verification: Value = serde_json::from_slice(&verify_output.stdout).unwrap();
+    assert_eq!(verification["data"]["verified"], true);
+    assert_eq!(verification["data"]["signatures_checked"], false);
+
+    fs::write(state.join("audit.key"), "too-short").unwrap();
+    let short_key_output = dutis()
+        .env("DUTIS_STATE_DIR", &state)
+        .args(["audit", "verify", "--json"])
+        .output()
+        .unwrap();
+    assert_eq!(short_key_output.status.code(), Some(6));
     fs::remove_dir_all(state).unwrap();
 }
+
+#[test]
+fn launch_agent_render_prints_the_plist_without_installing() {
+    let unique = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let root = std::env::temp_dir().join(format!(
+        "dutis-launch-agent-render-{}-{unique}",
+        std::process::id()
+    ));
+    fs::create_dir_all(&root).unwrap();
+    let config = root.join("dutis.toml");
+    fs::write(
+        &config,
+        "version = 2\n[associations]\nmd = 'com.apple.TextEdit'\n",
+    )
+    .unwrap();
+    let agents = root.join("LaunchAgents");
+    let state = root.join("state");
+
+    let output = dutis()
+        .env("DUTIS_LAUNCH_AGENT_DIR", &agents)
+        .env("DUTIS_STATE_DIR", &state)
+        .args(["launch-agent", "render"])
+        .arg(&config)
+        .args(["--name", "work", "--at", "09:00"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    let xml = String::from_utf8(output.stdout).unwrap();
+    assert!(xml.starts_with("<?xml"));
+    assert!(xml.contains("<string>io.github.tsonglew.dutis.watch.work</string>"));
+    assert!(xml.contains(&format!("<string>{}</string>", state.display())));
+    assert!(xml.contains(&format!(
+        "<string>{}</string>",
+        state.join("logs/watch-work.error.log").display()
+    )));
+    assert!(xml.contains("<key>StartCalendarInterval</key>"));
+
+    let output = dutis()
+        .env("DUTIS_LAUNCH_AGENT_DIR", &agents)
+        .env("DUTIS_STATE_DIR", &state)
+        .args(["launch-agent", "render"])
+        .arg(&config)
+        .arg("--json")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let response: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(response["command"], "launch-agent");
+    assert_eq!(response["data"]["label"], "io.github.tsonglew.dutis.watch");
+    assert_eq!(
+        response["data"]["plist"]["EnvironmentVariables"]["DUTIS_STATE_DIR"],
+        state.display().to_string()
+    );
+    assert!(!agents.exists());
+    assert!(!state.exists());
+    fs::remove_dir_all(root).unwrap();
+}